@@ -0,0 +1,135 @@
+//! Display order of lines containing right-to-left text (Arabic, Hebrew, ...).
+//!
+//! Text is always stored and edited in logical order, only its display is
+//! reordered. Lines without right-to-left characters are displayed as is.
+
+use crate::graphemes::grapheme_width;
+use crate::line_ending::get_line_ending_of_str;
+use crate::RopeSlice;
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Where a grapheme of a line is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisualGrapheme {
+    /// Column of the grapheme in logical order.
+    pub logical_col: usize,
+    /// Column the grapheme is displayed at.
+    pub visual_col: usize,
+    pub width: usize,
+}
+
+/// Whether `line` contains right-to-left characters, and needs reordering for display.
+pub fn has_rtl(line: RopeSlice) -> bool {
+    line.chars()
+        .any(|c| matches!(bidi_class(c), BidiClass::R | BidiClass::AL))
+}
+
+/// The display columns of the graphemes of `line`, excluding its line ending, in logical
+/// order. Returns `None` if the line is displayed in logical order.
+pub fn visual_columns(line: RopeSlice, tab_width: usize) -> Option<Vec<VisualGrapheme>> {
+    if !has_rtl(line) {
+        return None;
+    }
+
+    let text = String::from(line);
+    let text = match get_line_ending_of_str(&text) {
+        Some(ending) => &text[..text.len() - ending.as_str().len()],
+        None => &text,
+    };
+
+    // (byte offset, logical column, width) of each grapheme
+    let mut col = 0;
+    let graphemes: Vec<_> = text
+        .grapheme_indices(true)
+        .map(|(offset, grapheme)| {
+            let width = if grapheme == "\t" {
+                tab_width
+            } else {
+                grapheme_width(grapheme)
+            };
+            col += width;
+            (offset, col - width, width)
+        })
+        .collect();
+
+    let mut columns: Vec<_> = graphemes
+        .iter()
+        .map(|&(_, logical_col, width)| VisualGrapheme {
+            logical_col,
+            visual_col: logical_col,
+            width,
+        })
+        .collect();
+
+    let info = BidiInfo::new(text, None);
+    let mut visual_col = 0;
+    for paragraph in &info.paragraphs {
+        let (levels, runs) = info.visual_runs(paragraph, paragraph.range.clone());
+        for run in runs {
+            let start = graphemes.partition_point(|&(offset, _, _)| offset < run.start);
+            let end = graphemes.partition_point(|&(offset, _, _)| offset < run.end);
+            let mut place = |column: &mut VisualGrapheme| {
+                column.visual_col = visual_col;
+                visual_col += column.width;
+            };
+            if levels[run.start].is_rtl() {
+                columns[start..end].iter_mut().rev().for_each(&mut place);
+            } else {
+                columns[start..end].iter_mut().for_each(&mut place);
+            }
+        }
+    }
+
+    Some(columns)
+}
+
+/// Translates a logical column of a line to the column it's displayed at.
+/// Columns past the end of the line are unchanged.
+pub fn visual_col(columns: &[VisualGrapheme], logical_col: usize) -> usize {
+    columns
+        .iter()
+        .find(|column| {
+            (column.logical_col..column.logical_col + column.width).contains(&logical_col)
+        })
+        .map(|column| column.visual_col + logical_col - column.logical_col)
+        .unwrap_or(logical_col)
+}
+
+/// Translates a displayed column of a line to its logical column.
+/// Columns past the end of the line are unchanged.
+pub fn logical_col(columns: &[VisualGrapheme], visual_col: usize) -> usize {
+    columns
+        .iter()
+        .find(|column| (column.visual_col..column.visual_col + column.width).contains(&visual_col))
+        .map(|column| column.logical_col + visual_col - column.visual_col)
+        .unwrap_or(visual_col)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Rope;
+
+    #[test]
+    fn test_visual_columns() {
+        let text = Rope::from("abc\n");
+        assert_eq!(visual_columns(text.slice(..), 4), None);
+
+        // the hebrew word is displayed reversed, after the left-to-right text
+        let text = Rope::from("ab אבג\n");
+        let columns = visual_columns(text.slice(..), 4).unwrap();
+        let visual: Vec<_> = columns.iter().map(|column| column.visual_col).collect();
+        assert_eq!(visual, [0, 1, 2, 5, 4, 3]);
+
+        assert_eq!(visual_col(&columns, 3), 5);
+        assert_eq!(logical_col(&columns, 5), 3);
+        assert_eq!(visual_col(&columns, 6), 6);
+
+        // a right-to-left paragraph displays its runs right to left
+        let text = Rope::from("אב cd");
+        let columns = visual_columns(text.slice(..), 4).unwrap();
+        let visual: Vec<_> = columns.iter().map(|column| column.visual_col).collect();
+        assert_eq!(visual, [4, 3, 2, 0, 1]);
+    }
+}
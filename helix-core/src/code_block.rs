@@ -0,0 +1,97 @@
+//! Fenced code blocks in markdown documents.
+
+use crate::RopeSlice;
+
+#[derive(Debug, PartialEq)]
+pub struct CodeBlock {
+    /// First word of the info string, like `rust` in ```` ```rust ````.
+    pub language: String,
+    /// Line of the opening fence.
+    pub start: usize,
+    /// Line of the closing fence.
+    pub end: usize,
+}
+
+/// Returns the opening fence at the start of `line`: the fence character and
+/// how many times it's repeated.
+fn fence(line: &str) -> Option<(char, usize)> {
+    let line = line.trim_start();
+    let ch = line.chars().next().filter(|&ch| ch == '`' || ch == '~')?;
+    let len = line.chars().take_while(|&c| c == ch).count();
+    if len >= 3 {
+        Some((ch, len))
+    } else {
+        None
+    }
+}
+
+/// All closed code blocks in `text`, in order.
+pub fn code_blocks(text: RopeSlice) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    // opening fence of the block we are in, with its language and line
+    let mut open: Option<((char, usize), String, usize)> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.to_string();
+        match (&open, fence(&line)) {
+            (None, Some((ch, len))) => {
+                let info = line.trim_start()[ch.len_utf8() * len..].trim();
+                let language = info.split_whitespace().next().unwrap_or_default();
+                open = Some(((ch, len), language.to_string(), i));
+            }
+            (Some(((ch, len), _, _)), Some((close_ch, close_len)))
+                if close_ch == *ch
+                    && close_len >= *len
+                    && line.trim_start()[close_ch.len_utf8() * close_len..]
+                        .trim()
+                        .is_empty() =>
+            {
+                let (_, language, start) = open.take().unwrap();
+                blocks.push(CodeBlock {
+                    language,
+                    start,
+                    end: i,
+                });
+            }
+            _ => (),
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Rope;
+
+    #[test]
+    fn test_code_blocks() {
+        let text = Rope::from(
+            "# Title\n\
+             ```python title\n\
+             print(1)\n\
+             ```\n\
+             \n\
+             ~~~~\n\
+             ```\n\
+             ~~~~~\n\
+             ```sh\n\
+             unclosed\n",
+        );
+        assert_eq!(
+            code_blocks(text.slice(..)),
+            vec![
+                CodeBlock {
+                    language: "python".into(),
+                    start: 1,
+                    end: 3,
+                },
+                CodeBlock {
+                    language: "".into(),
+                    start: 5,
+                    end: 7,
+                },
+            ]
+        );
+    }
+}
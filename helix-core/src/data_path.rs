@@ -0,0 +1,215 @@
+//! Paths into structured data documents like JSON and YAML, such as
+//! `spec.containers[0].image`, resolved against the syntax tree.
+
+use crate::{tree_sitter::Node, RopeSlice};
+use std::fmt::{self, Write};
+
+/// Scopes of the languages whose syntax trees are understood here.
+pub const SCOPES: &[&str] = &["source.json", "source.yaml"];
+
+/// Nodes which hold a key and a value.
+const PAIRS: &[&str] = &["pair", "block_mapping_pair", "flow_pair"];
+/// Nodes whose children are indexed.
+const SEQUENCES: &[&str] = &["array", "block_sequence", "flow_sequence"];
+/// Nodes whose children are pairs.
+const MAPPINGS: &[&str] = &["object", "block_mapping", "flow_mapping"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataPath(pub Vec<Segment>);
+
+impl fmt::Display for DataPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Key(key) if is_identifier(key) => {
+                    if i > 0 {
+                        f.write_char('.')?;
+                    }
+                    f.write_str(key)?;
+                }
+                Segment::Key(key) => write!(f, "[{:?}]", key)?,
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for DataPath {
+    type Err = String;
+
+    /// Parses paths like `spec.containers[0]["image name"]`. A leading `$` or
+    /// `.` is accepted as the root.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid path: {}", s);
+        let mut segments = Vec::new();
+        let mut rest = s.trim();
+        rest = rest.strip_prefix('$').unwrap_or(rest);
+        let mut needs_dot = false;
+
+        while !rest.is_empty() {
+            if let Some(inner) = rest.strip_prefix('[') {
+                let end = if inner.starts_with('"') || inner.starts_with('\'') {
+                    let quote = inner.chars().next().unwrap();
+                    inner[1..].find(quote).map(|i| i + 2)
+                } else {
+                    inner.find(']')
+                }
+                .ok_or_else(invalid)?;
+                let (segment, after) = inner.split_at(end);
+                let after = after.strip_prefix(']').ok_or_else(invalid)?;
+                let segment = segment.trim();
+                segments.push(match segment.parse() {
+                    Ok(index) => Segment::Index(index),
+                    Err(_) => Segment::Key(unquote(segment).to_string()),
+                });
+                rest = after;
+            } else {
+                if let Some(after) = rest.strip_prefix('.') {
+                    rest = after;
+                } else if needs_dot {
+                    return Err(invalid());
+                }
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                if end == 0 {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Key(rest[..end].to_string()));
+                rest = &rest[end..];
+            }
+            needs_dot = true;
+        }
+        Ok(Self(segments))
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+fn unquote(s: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(quote).and_then(|s| s.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    s
+}
+
+fn key_of(pair: Node, text: RopeSlice) -> Option<String> {
+    let key = pair.child_by_field_name("key")?;
+    let key = text
+        .byte_slice(key.start_byte()..key.end_byte())
+        .to_string();
+    Some(unquote(key.trim()).to_string())
+}
+
+/// Named children of a node, skipping comments.
+fn items(node: Node) -> impl Iterator<Item = Node> {
+    let mut cursor = node.walk();
+    let items: Vec<_> = node
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() != "comment")
+        .collect();
+    items.into_iter()
+}
+
+/// The path to the value containing the byte offset `byte`.
+pub fn path_at(root: Node, text: RopeSlice, byte: usize) -> DataPath {
+    let mut segments = Vec::new();
+    let mut node = match root.descendant_for_byte_range(byte, byte) {
+        Some(node) => node,
+        None => return DataPath::default(),
+    };
+    while let Some(parent) = node.parent() {
+        if PAIRS.contains(&node.kind()) {
+            if let Some(key) = key_of(node, text) {
+                segments.push(Segment::Key(key));
+            }
+        }
+        if SEQUENCES.contains(&parent.kind()) {
+            if let Some(index) = items(parent).position(|item| item == node) {
+                segments.push(Segment::Index(index));
+            }
+        }
+        node = parent;
+    }
+    segments.reverse();
+    DataPath(segments)
+}
+
+/// Descends through wrapper nodes like YAML documents and block nodes to the
+/// mapping or sequence `node` holds.
+fn container(mut node: Node) -> Option<Node> {
+    loop {
+        if MAPPINGS.contains(&node.kind()) || SEQUENCES.contains(&node.kind()) {
+            return Some(node);
+        }
+        node = items(node).next()?;
+    }
+}
+
+/// The value node at `path`, if the document has one.
+pub fn find_path<'a>(root: Node<'a>, text: RopeSlice, path: &DataPath) -> Option<Node<'a>> {
+    let mut node = root;
+    for segment in &path.0 {
+        let parent = container(node)?;
+        node = match segment {
+            Segment::Key(key) if MAPPINGS.contains(&parent.kind()) => items(parent)
+                .filter(|pair| PAIRS.contains(&pair.kind()))
+                .find(|&pair| key_of(pair, text).as_ref() == Some(key))?
+                .child_by_field_name("value")?,
+            Segment::Index(index) if SEQUENCES.contains(&parent.kind()) => {
+                let item = items(parent).nth(*index)?;
+                if item.kind() == "block_sequence_item" {
+                    items(item).next()?
+                } else {
+                    item
+                }
+            }
+            _ => return None,
+        };
+    }
+    Some(node)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display_and_parse() {
+        let path = DataPath(vec![
+            Segment::Key("spec".into()),
+            Segment::Key("containers".into()),
+            Segment::Index(0),
+            Segment::Key("image name".into()),
+        ]);
+        assert_eq!(path.to_string(), r#"spec.containers[0]["image name"]"#);
+        assert_eq!(path.to_string().parse(), Ok(path.clone()));
+        assert_eq!(
+            "$.spec.containers[0]['image name']".parse(),
+            Ok(path.clone())
+        );
+        assert_eq!(
+            "[0].a".parse(),
+            Ok(DataPath(vec![Segment::Index(0), Segment::Key("a".into())]))
+        );
+        assert_eq!("".parse(), Ok(DataPath::default()));
+
+        assert!("a..b".parse::<DataPath>().is_err());
+        assert!("a[0".parse::<DataPath>().is_err());
+        assert!("a[0]b".parse::<DataPath>().is_err());
+        assert!(r#"a["b]"#.parse::<DataPath>().is_err());
+    }
+}
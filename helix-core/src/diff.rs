@@ -53,6 +53,17 @@ pub fn compare_ropes(old: &Rope, new: &Rope) -> Transaction {
     )
 }
 
+/// A line based diff from `old` to `new` in the unified format, with three
+/// lines of context around changes. Empty when they are equal.
+pub fn unified_diff(old: &Rope, new: &Rope) -> String {
+    let old = old.to_string();
+    let new = new.to_string();
+    similar::TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .context_radius(3)
+        .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,4 +76,15 @@ mod tests {
             old.to_string() == new.to_string()
         }
     }
+
+    #[test]
+    fn test_unified_diff() {
+        let old = Rope::from("a\nb\nc\n");
+        let new = Rope::from("a\nB\nc\n");
+        assert_eq!(
+            unified_diff(&old, &new),
+            "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+        );
+        assert_eq!(unified_diff(&old, &old), "");
+    }
 }
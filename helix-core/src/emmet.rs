@@ -0,0 +1,600 @@
+//! Expansion of [Emmet](https://emmet.io) abbreviations like `ul>li.item$*3`
+//! into HTML.
+//!
+//! Supported syntax: tag names, `#id`, `.class`, `[attr=value]`, `{text}`,
+//! children (`>`), siblings (`+`), multiplication (`*3`) with `$` numbering,
+//! and grouping with parentheses.
+
+/// The expanded markup and the char offsets into it where the cursor should
+/// go, in order: empty attribute values and empty element contents.
+#[derive(Debug, PartialEq)]
+pub struct Expansion {
+    pub text: String,
+    pub tab_stops: Vec<usize>,
+}
+
+/// Elements which have no closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// HTML elements. An abbreviation which is just a name is only expanded if
+/// it's one of these, so that typing a word and pressing tab indents.
+const ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "address",
+    "area",
+    "article",
+    "aside",
+    "audio",
+    "b",
+    "base",
+    "bdi",
+    "bdo",
+    "blockquote",
+    "body",
+    "br",
+    "button",
+    "canvas",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "datalist",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "i",
+    "iframe",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "label",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "map",
+    "mark",
+    "menu",
+    "meta",
+    "meter",
+    "nav",
+    "noscript",
+    "object",
+    "ol",
+    "optgroup",
+    "option",
+    "output",
+    "p",
+    "picture",
+    "pre",
+    "progress",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "script",
+    "section",
+    "select",
+    "slot",
+    "small",
+    "source",
+    "span",
+    "strong",
+    "style",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "title",
+    "tr",
+    "track",
+    "u",
+    "ul",
+    "var",
+    "video",
+    "wbr",
+];
+
+/// The most elements an abbreviation may expand to, counting repetitions.
+const MAX_ELEMENTS: usize = 1000;
+
+/// Attributes added to elements which are useless without them.
+const DEFAULT_ATTRIBUTES: &[(&str, &[(&str, &str)])] = &[
+    ("a", &[("href", "")]),
+    ("img", &[("src", ""), ("alt", "")]),
+    ("link", &[("rel", "stylesheet"), ("href", "")]),
+    ("input", &[("type", "text")]),
+    ("label", &[("for", "")]),
+];
+
+/// Returns the byte offset where the abbreviation ending at the end of `text`
+/// starts. The abbreviation might be empty or invalid.
+pub fn find_abbreviation_start(text: &str) -> usize {
+    // depth inside of `{text}` or `[attributes]`, which may contain anything
+    let mut depth = 0usize;
+    for (i, ch) in text.char_indices().rev() {
+        match ch {
+            '}' | ']' => depth += 1,
+            '{' | '[' => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return i + ch.len_utf8(),
+            },
+            _ if depth > 0 => (),
+            ch if ch.is_alphanumeric() || ".#>+*$-_:()".contains(ch) => (),
+            _ => return i + ch.len_utf8(),
+        }
+    }
+    0
+}
+
+/// Expand `abbreviation` to HTML. Lines after the first are prefixed with
+/// `line_indent`, and nested elements are indented by `indent_unit`.
+///
+/// Returns `None` for abbreviations which are a single unknown name, like
+/// most words, and for those expanding to more than [`MAX_ELEMENTS`].
+pub fn expand(abbreviation: &str, indent_unit: &str, line_indent: &str) -> Option<Expansion> {
+    let is_name = abbreviation
+        .chars()
+        .all(|ch| ch.is_alphanumeric() || "-_:".contains(ch));
+    if is_name && !ELEMENTS.contains(&abbreviation) {
+        return None;
+    }
+
+    let mut parser = Parser {
+        chars: abbreviation.chars().collect(),
+        pos: 0,
+    };
+    let nodes = parser.sequence()?;
+    if parser.pos != parser.chars.len() || count(&nodes) > MAX_ELEMENTS {
+        return None;
+    }
+
+    let mut output = Output {
+        text: String::new(),
+        tab_stops: Vec::new(),
+        indent_unit,
+        line_indent,
+    };
+    output.nodes(&nodes, "", 0, None);
+    Some(Expansion {
+        text: output.text,
+        tab_stops: output.tab_stops,
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+struct Element {
+    name: String,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, String)>,
+    text: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    Element(Element),
+    Group(Vec<Node>),
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    kind: NodeKind,
+    repeat: usize,
+    children: Vec<Node>,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.peek() == Some(ch);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while matches!(self.peek(), Some(ch) if f(ch)) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn ident(&mut self) -> String {
+        self.take_while(|ch| ch.is_alphanumeric() || "-_:$".contains(ch))
+    }
+
+    /// Reads everything up to `close`, consuming it.
+    fn until(&mut self, close: char) -> Option<String> {
+        let s = self.take_while(|ch| ch != close);
+        if self.eat(close) {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    /// `chain ('+' chain)*`
+    fn sequence(&mut self) -> Option<Vec<Node>> {
+        let mut nodes = vec![self.chain()?];
+        while self.eat('+') {
+            nodes.push(self.chain()?);
+        }
+        Some(nodes)
+    }
+
+    /// `item ('>' sequence)?`
+    fn chain(&mut self) -> Option<Node> {
+        let mut node = self.item()?;
+        if self.eat('>') {
+            if let NodeKind::Group(_) = node.kind {
+                return None;
+            }
+            node.children = self.sequence()?;
+        }
+        Some(node)
+    }
+
+    /// `('(' sequence ')' | element) ('*' number)?`
+    fn item(&mut self) -> Option<Node> {
+        let kind = if self.eat('(') {
+            let nodes = self.sequence()?;
+            if !self.eat(')') {
+                return None;
+            }
+            NodeKind::Group(nodes)
+        } else {
+            NodeKind::Element(self.element()?)
+        };
+        let repeat = if self.eat('*') {
+            self.take_while(|ch| ch.is_ascii_digit()).parse().ok()?
+        } else {
+            1
+        };
+        Some(Node {
+            kind,
+            repeat,
+            children: Vec::new(),
+        })
+    }
+
+    fn element(&mut self) -> Option<Element> {
+        let mut element = Element {
+            name: self.ident(),
+            ..Default::default()
+        };
+        loop {
+            if self.eat('.') {
+                element.classes.push(self.ident());
+            } else if self.eat('#') {
+                element.id = Some(self.ident());
+            } else if self.eat('[') {
+                let attributes = self.until(']')?;
+                for attribute in attributes.split_whitespace() {
+                    let (name, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+                    let value = value.trim_matches(|ch| ch == '"' || ch == '\'');
+                    element
+                        .attributes
+                        .push((name.to_string(), value.to_string()));
+                }
+            } else if self.eat('{') {
+                element.text = Some(self.until('}')?);
+            } else {
+                break;
+            }
+        }
+
+        let is_empty = element.name.is_empty()
+            && element.id.is_none()
+            && element.classes.is_empty()
+            && element.attributes.is_empty()
+            && element.text.is_none();
+        if is_empty {
+            None
+        } else {
+            Some(element)
+        }
+    }
+}
+
+/// Number of elements `nodes` expand to, saturating on overflow.
+fn count(nodes: &[Node]) -> usize {
+    nodes.iter().fold(0usize, |total, node| {
+        let own = match &node.kind {
+            NodeKind::Element(_) => 1,
+            NodeKind::Group(nodes) => count(nodes),
+        };
+        let each = own.saturating_add(count(&node.children));
+        total.saturating_add(each.saturating_mul(node.repeat))
+    })
+}
+
+/// Replace runs of `$` with `index`, zero padded to the length of the run.
+fn number(s: &str, index: Option<usize>) -> String {
+    let index = match index {
+        Some(index) => index,
+        None => return s.to_string(),
+    };
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '$' {
+            out.push(ch);
+            continue;
+        }
+        let mut width = 1;
+        while chars.next_if_eq(&'$').is_some() {
+            width += 1;
+        }
+        out.push_str(&format!("{:0width$}", index, width = width));
+    }
+    out
+}
+
+/// Tag used for elements like `.foo` which only have a class or id.
+fn implicit_name(parent: &str) -> &'static str {
+    match parent {
+        "ul" | "ol" => "li",
+        "table" | "tbody" | "thead" | "tfoot" => "tr",
+        "tr" => "td",
+        "select" | "optgroup" => "option",
+        _ => "div",
+    }
+}
+
+struct Output<'a> {
+    text: String,
+    tab_stops: Vec<usize>,
+    indent_unit: &'a str,
+    line_indent: &'a str,
+}
+
+impl<'a> Output<'a> {
+    fn tab_stop(&mut self) {
+        self.tab_stops.push(self.text.chars().count());
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if !self.text.is_empty() {
+            self.text.push('\n');
+            self.text.push_str(self.line_indent);
+            for _ in 0..depth {
+                self.text.push_str(self.indent_unit);
+            }
+        }
+    }
+
+    fn nodes(&mut self, nodes: &[Node], parent: &str, depth: usize, index: Option<usize>) {
+        for node in nodes {
+            for i in 1..=node.repeat {
+                let index = if node.repeat > 1 { Some(i) } else { index };
+                match &node.kind {
+                    NodeKind::Group(nodes) => self.nodes(nodes, parent, depth, index),
+                    NodeKind::Element(element) => {
+                        self.newline(depth);
+                        self.element(element, &node.children, parent, depth, index);
+                    }
+                }
+            }
+        }
+    }
+
+    fn element(
+        &mut self,
+        element: &Element,
+        children: &[Node],
+        parent: &str,
+        depth: usize,
+        index: Option<usize>,
+    ) {
+        let text = element.text.as_deref().map(|text| number(text, index));
+        let is_text_only = element.name.is_empty()
+            && element.id.is_none()
+            && element.classes.is_empty()
+            && element.attributes.is_empty();
+        if is_text_only {
+            self.text.push_str(text.as_deref().unwrap_or_default());
+            return;
+        }
+
+        let name = match element.name.as_str() {
+            "" => implicit_name(parent).to_string(),
+            name => number(name, index),
+        };
+
+        self.text.push('<');
+        self.text.push_str(&name);
+        let mut attributes: Vec<(String, String)> = Vec::new();
+        if let Some(id) = &element.id {
+            attributes.push(("id".to_string(), number(id, index)));
+        }
+        if !element.classes.is_empty() {
+            let classes: Vec<_> = element
+                .classes
+                .iter()
+                .map(|class| number(class, index))
+                .collect();
+            attributes.push(("class".to_string(), classes.join(" ")));
+        }
+        for (attribute, value) in &element.attributes {
+            attributes.push((attribute.clone(), number(value, index)));
+        }
+        if let Some((_, defaults)) = DEFAULT_ATTRIBUTES.iter().find(|(tag, _)| *tag == name) {
+            for (attribute, value) in defaults.iter() {
+                if !attributes.iter().any(|(a, _)| a == attribute) {
+                    attributes.push((attribute.to_string(), value.to_string()));
+                }
+            }
+        }
+        for (attribute, value) in attributes {
+            self.text.push_str(&format!(" {}=\"", attribute));
+            if value.is_empty() {
+                self.tab_stop();
+            }
+            self.text.push_str(&value);
+            self.text.push('"');
+        }
+        self.text.push('>');
+
+        if VOID_ELEMENTS.contains(&name.as_str()) {
+            return;
+        }
+
+        if let Some(text) = &text {
+            self.text.push_str(text);
+        }
+        if children.is_empty() {
+            if text.is_none() {
+                self.tab_stop();
+            }
+        } else {
+            self.nodes(children, &name, depth + 1, index);
+            self.newline(depth);
+        }
+        self.text.push_str(&format!("</{}>", name));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expand_text(abbreviation: &str) -> Option<String> {
+        expand(abbreviation, "  ", "").map(|expansion| expansion.text)
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expand_text("div").as_deref(), Some("<div></div>"));
+        assert_eq!(
+            expand_text("p#intro.lead.big{Hi}").as_deref(),
+            Some(r#"<p id="intro" class="lead big">Hi</p>"#)
+        );
+        assert_eq!(
+            expand_text("ul>li.item$*2").as_deref(),
+            Some("<ul>\n  <li class=\"item1\"></li>\n  <li class=\"item2\"></li>\n</ul>")
+        );
+        assert_eq!(
+            expand_text("(h1+p)*2").as_deref(),
+            Some("<h1></h1>\n<p></p>\n<h1></h1>\n<p></p>")
+        );
+        assert_eq!(
+            expand_text("table>.row>.cell").as_deref(),
+            Some("<table>\n  <tr class=\"row\">\n    <td class=\"cell\"></td>\n  </tr>\n</table>")
+        );
+        assert_eq!(
+            expand_text("a[target=_blank]+br+img").as_deref(),
+            Some(concat!(
+                "<a target=\"_blank\" href=\"\"></a>\n",
+                "<br>\n",
+                "<img src=\"\" alt=\"\">"
+            ))
+        );
+        assert_eq!(
+            expand_text("li{$$}*10").unwrap().lines().last(),
+            Some("<li>10</li>")
+        );
+
+        assert_eq!(expand_text(""), None);
+        assert_eq!(expand_text("div>"), None);
+        assert_eq!(expand_text("(div"), None);
+        assert_eq!(expand_text("div)"), None);
+        assert_eq!(expand_text("(a)>b"), None);
+    }
+
+    #[test]
+    fn test_expand_limits() {
+        // plain words are only expanded if they're elements
+        assert_eq!(expand_text("hello"), None);
+        assert_eq!(expand_text("Button"), None);
+        assert_eq!(
+            expand_text("hello>p").as_deref(),
+            Some("<hello>\n  <p></p>\n</hello>")
+        );
+        assert_eq!(
+            expand_text(".hello").as_deref(),
+            Some("<div class=\"hello\"></div>")
+        );
+
+        assert!(expand_text("p*1000").is_some());
+        assert_eq!(expand_text("p*1001"), None);
+        assert_eq!(expand_text("ul>li*100>a*10"), None);
+        assert_eq!(expand_text("(p*99999999999)*99999999999"), None);
+        assert_eq!(expand_text("p*999999999999999999999999"), None);
+    }
+
+    #[test]
+    fn test_tab_stops_and_indent() {
+        let expansion = expand("ul>li*2>a", "\t", "    ").unwrap();
+        assert_eq!(
+            expansion.text,
+            "<ul>\n    \t<li>\n    \t\t<a href=\"\"></a>\n    \t</li>\n    \t<li>\n    \t\t<a href=\"\"></a>\n    \t</li>\n    </ul>"
+        );
+        let stops: Vec<String> = expansion
+            .tab_stops
+            .iter()
+            .map(|&i| expansion.text.chars().skip(i).take(4).collect())
+            .collect();
+        assert_eq!(stops, ["\"></", "</a>", "\"></", "</a>"]);
+    }
+
+    #[test]
+    fn test_find_abbreviation_start() {
+        let text = "  <p>Hello ul>li{two words}*2";
+        assert_eq!(&text[find_abbreviation_start(text)..], "ul>li{two words}*2");
+        let text = "<div>";
+        assert_eq!(&text[find_abbreviation_start(text)..], "div>");
+        assert_eq!(find_abbreviation_start(""), 0);
+        assert_eq!(find_abbreviation_start("x "), 2);
+    }
+}
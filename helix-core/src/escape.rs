@@ -0,0 +1,79 @@
+//! Escaping text pasted into string literals.
+
+use crate::{RopeSlice, Syntax};
+
+const QUOTES: &[char] = &['"', '\'', '`'];
+
+/// Returns the quote delimiting the string literal whose contents contain `pos`, if any. Raw and
+/// triple-quoted strings are skipped since their contents are taken literally.
+pub fn string_quote_at(syntax: &Syntax, text: RopeSlice, pos: usize) -> Option<char> {
+    let byte_pos = text.char_to_byte(pos);
+    let mut node = syntax
+        .tree()
+        .root_node()
+        .descendant_for_byte_range(byte_pos, byte_pos)?;
+
+    loop {
+        let kind = node.kind();
+        if kind.contains("raw") {
+            return None;
+        }
+        if kind.contains("string") {
+            // skip prefixes like `b"` or `f'`
+            let start = text.byte_to_char(node.start_byte());
+            let end = text.byte_to_char(node.end_byte());
+            let literal = text.slice(start..end);
+            let quote_offset = literal
+                .chars()
+                .take_while(|ch| ch.is_alphanumeric())
+                .count();
+            if let Some(quote) = literal
+                .get_char(quote_offset)
+                .filter(|ch| QUOTES.contains(ch))
+            {
+                if literal.get_char(quote_offset + 1) == Some(quote)
+                    && literal.get_char(quote_offset + 2) == Some(quote)
+                {
+                    return None;
+                }
+                let inside = start + quote_offset < pos && pos < end;
+                return if inside { Some(quote) } else { None };
+            }
+        }
+        node = node.parent()?;
+    }
+}
+
+/// Escapes backslashes, `quote` and line breaks in `text` so it can be put inside a string
+/// literal delimited by `quote`. Backtick strings span lines, so their line breaks are kept.
+pub fn escape_string(text: &str, quote: char) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' if quote != '`' => escaped.push_str("\\n"),
+            '\r' if quote != '`' => escaped.push_str("\\r"),
+            ch if ch == quote => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_escape_string() {
+        assert_eq!(
+            escape_string("say \"hi\"\nto C:\\", '"'),
+            "say \\\"hi\\\"\\nto C:\\\\"
+        );
+        assert_eq!(escape_string("it's \"fine\"", '\''), "it\\'s \"fine\"");
+        assert_eq!(escape_string("a\n`b`", '`'), "a\n\\`b\\`");
+    }
+}
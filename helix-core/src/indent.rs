@@ -186,6 +186,45 @@ pub fn indent_level_for_line(line: RopeSlice, tab_width: usize) -> usize {
     len / tab_width
 }
 
+/// Reindents `block`, a number of whole lines, so that its least indented line
+/// starts with `indent`. The other lines keep their indentation relative to it.
+pub fn reindent_block(block: &str, indent: &str, tab_width: usize) -> String {
+    let width = |line: &str| {
+        line.chars()
+            .take_while(|ch| *ch == ' ' || *ch == '\t')
+            .map(|ch| if ch == '\t' { tab_width } else { 1 })
+            .sum::<usize>()
+    };
+    let min_width = block
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(width)
+        .min()
+        .unwrap_or(0);
+
+    let mut reindented = String::with_capacity(block.len());
+    for line in block.split_inclusive('\n') {
+        let content = line.trim_end_matches(&['\r', '\n'][..]);
+        if content.trim().is_empty() {
+            // keep blank lines free of trailing whitespace
+            reindented.push_str(&line[content.len()..]);
+            continue;
+        }
+        let mut stripped = 0;
+        let rest = content.trim_start_matches(|ch| {
+            let is_indent = stripped < min_width && (ch == ' ' || ch == '\t');
+            if is_indent {
+                stripped += if ch == '\t' { tab_width } else { 1 };
+            }
+            is_indent
+        });
+        reindented.push_str(indent);
+        reindented.push_str(rest);
+        reindented.push_str(&line[content.len()..]);
+    }
+    reindented
+}
+
 /// Find the highest syntax node at position.
 /// This is to identify the column where this node (e.g., an HTML closing tag) ends.
 fn get_highest_syntax_node_at_bytepos(syntax: &Syntax, pos: usize) -> Option<Node> {
@@ -318,6 +357,20 @@ mod test {
     use super::*;
     use crate::Rope;
 
+    #[test]
+    fn test_reindent_block() {
+        let block = "    if x {\n        y();\n\n    }\n";
+        assert_eq!(
+            reindent_block(block, "\t", 4),
+            "\tif x {\n\t    y();\n\n\t}\n"
+        );
+        assert_eq!(reindent_block(block, "", 4), "if x {\n    y();\n\n}\n");
+        assert_eq!(
+            reindent_block("\tfoo\r\n\t\tbar\r\n", "  ", 4),
+            "  foo\r\n  \tbar\r\n"
+        );
+    }
+
     #[test]
     fn test_indent_level() {
         let tab_width = 4;
@@ -430,11 +483,14 @@ where
                 language_id: "Rust".to_string(),
                 highlight_config: OnceCell::new(),
                 config: None,
+                settings: None,
                 //
                 injection_regex: None,
                 roots: vec![],
                 comment_token: None,
                 auto_format: false,
+                emmet: false,
+                auto_close_tags: false,
                 diagnostic_severity: Severity::Warning,
                 tree_sitter_library: None,
                 language_server: None,
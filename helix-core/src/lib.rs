@@ -1,10 +1,15 @@
 pub use encoding_rs as encoding;
 
 pub mod auto_pairs;
+pub mod bidi;
 pub mod chars;
+pub mod code_block;
 pub mod comment;
+pub mod data_path;
 pub mod diagnostic;
 pub mod diff;
+pub mod emmet;
+pub mod escape;
 pub mod graphemes;
 pub mod history;
 pub mod increment;
@@ -17,13 +22,20 @@ pub mod object;
 pub mod path;
 mod position;
 pub mod register;
+pub mod replace;
 pub mod search;
 pub mod selection;
 pub mod shellwords;
+pub mod snippets;
 mod state;
+pub mod stats;
 pub mod surround;
 pub mod syntax;
+pub mod tag;
+pub mod template;
 pub mod textobject;
+pub mod timings;
+pub mod todo;
 mod transaction;
 
 pub mod unicode {
@@ -84,11 +96,42 @@ pub fn find_root(root: Option<&str>, root_markers: &[String]) -> Option<std::pat
     }
 }
 
+/// Name of the file which enables portable mode when it's next to the
+/// executable.
+pub const PORTABLE_MARKER: &str = "helix-portable";
+
+static PORTABLE_DIR: once_cell::sync::OnceCell<Option<std::path::PathBuf>> =
+    once_cell::sync::OnceCell::new();
+
+fn executable_dir() -> Option<std::path::PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(|path| path.to_path_buf()))
+}
+
+/// Keep the config, runtime, cache and data directories next to the executable,
+/// like when there's a [`PORTABLE_MARKER`] file there. Must be called before
+/// any of those directories are looked up.
+pub fn enable_portable_mode() {
+    let _ = PORTABLE_DIR.set(executable_dir());
+}
+
+/// The directory of the executable when running in portable mode.
+pub fn portable_dir() -> Option<&'static std::path::Path> {
+    PORTABLE_DIR
+        .get_or_init(|| executable_dir().filter(|dir| dir.join(PORTABLE_MARKER).exists()))
+        .as_deref()
+}
+
 pub fn runtime_dir() -> std::path::PathBuf {
     if let Ok(dir) = std::env::var("HELIX_RUNTIME") {
         return dir.into();
     }
 
+    if let Some(dir) = portable_dir() {
+        return dir.join("runtime");
+    }
+
     const RT_DIR: &str = "runtime";
     let conf_dir = config_dir().join(RT_DIR);
     if conf_dir.exists() {
@@ -108,6 +151,9 @@ pub fn runtime_dir() -> std::path::PathBuf {
 }
 
 pub fn config_dir() -> std::path::PathBuf {
+    if let Some(dir) = portable_dir() {
+        return dir.join("config");
+    }
     // TODO: allow env var override
     let strategy = choose_base_strategy().expect("Unable to find the config directory!");
     let mut path = strategy.config_dir();
@@ -116,6 +162,9 @@ pub fn config_dir() -> std::path::PathBuf {
 }
 
 pub fn cache_dir() -> std::path::PathBuf {
+    if let Some(dir) = portable_dir() {
+        return dir.join("cache");
+    }
     // TODO: allow env var override
     let strategy = choose_base_strategy().expect("Unable to find the config directory!");
     let mut path = strategy.cache_dir();
@@ -123,6 +172,39 @@ pub fn cache_dir() -> std::path::PathBuf {
     path
 }
 
+pub fn data_dir() -> std::path::PathBuf {
+    if let Some(dir) = portable_dir() {
+        return dir.join("data");
+    }
+    // TODO: allow env var override
+    let strategy = choose_base_strategy().expect("Unable to find the data directory!");
+    let mut path = strategy.data_dir();
+    path.push("helix");
+    path
+}
+
+static CONFIG_FILE: once_cell::sync::OnceCell<std::path::PathBuf> =
+    once_cell::sync::OnceCell::new();
+
+/// Uses `path` as the config file instead of `config.toml` in the config
+/// directory. Must be called before the config is loaded.
+pub fn set_config_file(path: std::path::PathBuf) {
+    let _ = CONFIG_FILE.set(path);
+}
+
+/// The config file, `config.toml` in the config directory unless it was
+/// overridden with [`set_config_file`].
+pub fn config_file() -> std::path::PathBuf {
+    CONFIG_FILE
+        .get()
+        .cloned()
+        .unwrap_or_else(|| config_dir().join("config.toml"))
+}
+
+pub fn log_file() -> std::path::PathBuf {
+    cache_dir().join("helix.log")
+}
+
 // right overrides left
 pub fn merge_toml_values(left: toml::Value, right: toml::Value) -> toml::Value {
     use toml::Value;
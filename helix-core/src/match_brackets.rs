@@ -13,6 +13,12 @@ const PAIRS: &[(char, char)] = &[
 
 // limit matching pairs to only ( ) { } [ ] < > ' ' " "
 
+// brackets matched without a syntax tree, where quotes and angle brackets are ambiguous
+const PLAINTEXT_PAIRS: &[(char, char)] = &[('(', ')'), ('{', '}'), ('[', ']')];
+
+// how far to look for the matching bracket without a syntax tree
+const MAX_PLAINTEXT_SCAN: usize = 10_000;
+
 // Returns the position of the matching bracket under cursor.
 //
 // If the cursor is one the opening bracket, the position of
@@ -43,6 +49,65 @@ pub fn find_matching_bracket_fuzzy(syntax: &Syntax, doc: &Rope, pos: usize) -> O
     find_pair(syntax, doc, pos, true)
 }
 
+// Returns the position of the bracket matching the one under the cursor by counting the
+// brackets in between, for documents without a syntax tree. Brackets in strings or comments
+// are counted too.
+#[must_use]
+pub fn find_matching_bracket_plaintext(doc: &Rope, pos: usize) -> Option<usize> {
+    if pos >= doc.len_chars() {
+        return None;
+    }
+    let bracket = doc.char(pos);
+    let (open, close, forward) = PLAINTEXT_PAIRS.iter().find_map(|&(open, close)| {
+        if bracket == open {
+            Some((open, close, true))
+        } else if bracket == close {
+            Some((open, close, false))
+        } else {
+            None
+        }
+    })?;
+
+    let mut depth = 0usize;
+    let mut step = |(i, ch): (usize, char)| {
+        if ch == bracket {
+            depth += 1;
+        } else if ch == open || ch == close {
+            depth -= 1;
+        }
+        (depth == 0).then(|| i)
+    };
+    if forward {
+        let chars = doc.chars_at(pos).enumerate().map(|(i, ch)| (pos + i, ch));
+        chars.take(MAX_PLAINTEXT_SCAN).find_map(&mut step)
+    } else {
+        let mut chars = doc.chars_at(pos + 1);
+        chars.reverse();
+        let chars = chars.enumerate().map(|(i, ch)| (pos - i, ch));
+        chars.take(MAX_PLAINTEXT_SCAN).find_map(&mut step)
+    }
+}
+
+// Returns the bracket under the cursor, or else the one right before it like after typing
+// a closing bracket, and the position of its match. Documents without a syntax tree are
+// matched with [`find_matching_bracket_plaintext`].
+#[must_use]
+pub fn find_matching_pair(
+    syntax: Option<&Syntax>,
+    doc: &Rope,
+    pos: usize,
+) -> Option<(usize, usize)> {
+    let find = |pos| match syntax {
+        Some(syntax) => find_matching_bracket(syntax, doc, pos),
+        None => find_matching_bracket_plaintext(doc, pos),
+    };
+    if let Some(matching) = find(pos) {
+        return Some((pos, matching));
+    }
+    let before = pos.checked_sub(1)?;
+    find(before).map(|matching| (before, matching))
+}
+
 fn find_pair(syntax: &Syntax, doc: &Rope, pos: usize, traverse_parents: bool) -> Option<usize> {
     let tree = syntax.tree();
     let pos = doc.char_to_byte(pos);
@@ -90,3 +155,29 @@ fn surrounding_bytes(doc: &Rope, node: &Node) -> Option<(usize, usize)> {
 
     Some((start_byte, end_byte))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_matching_bracket_plaintext() {
+        let doc = Rope::from("f(a[0], (b)) }");
+        assert_eq!(find_matching_bracket_plaintext(&doc, 1), Some(11));
+        assert_eq!(find_matching_bracket_plaintext(&doc, 11), Some(1));
+        assert_eq!(find_matching_bracket_plaintext(&doc, 3), Some(5));
+        assert_eq!(find_matching_bracket_plaintext(&doc, 10), Some(8));
+        // not a bracket
+        assert_eq!(find_matching_bracket_plaintext(&doc, 0), None);
+        // unbalanced
+        assert_eq!(find_matching_bracket_plaintext(&doc, 13), None);
+    }
+
+    #[test]
+    fn test_find_matching_pair_before_cursor() {
+        let doc = Rope::from("(a) b");
+        assert_eq!(find_matching_pair(None, &doc, 0), Some((0, 2)));
+        assert_eq!(find_matching_pair(None, &doc, 3), Some((2, 0)));
+        assert_eq!(find_matching_pair(None, &doc, 4), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::iter;
+use std::{borrow::Cow, iter};
 
 use ropey::iter::Chars;
 
@@ -6,9 +6,10 @@ use crate::{
     chars::{categorize_char, char_is_line_ending, CharCategory},
     coords_at_pos,
     graphemes::{
-        next_grapheme_boundary, nth_next_grapheme_boundary, nth_prev_grapheme_boundary,
-        prev_grapheme_boundary,
+        grapheme_width, next_grapheme_boundary, nth_next_grapheme_boundary,
+        nth_prev_grapheme_boundary, prev_grapheme_boundary, RopeGraphemes,
     },
+    line_ending::line_end_char_index,
     pos_at_coords, Position, Range, RopeSlice,
 };
 
@@ -78,6 +79,106 @@ pub fn move_vertically(
     new_range
 }
 
+/// Moves by screen rows of lines soft-wrapped at `wrap_width` columns instead of by lines,
+/// keeping the column within the row. Like [`move_vertically`], the column kept in
+/// [`Range::horiz`] counts characters, here from the start of the row.
+pub fn move_vertically_visual(
+    slice: RopeSlice,
+    range: Range,
+    dir: Direction,
+    count: usize,
+    behaviour: Movement,
+    wrap_width: usize,
+    tab_width: usize,
+) -> Range {
+    let wrap_width = wrap_width.max(1);
+    let pos = range.cursor(slice);
+    let mut line = slice.char_to_line(pos);
+    let positions = wrapped_positions(slice, line, wrap_width, tab_width);
+    let mut row = positions
+        .iter()
+        .take_while(|(start, _)| *start <= pos)
+        .last()
+        .map_or(0, |(_, row)| *row);
+    let row_start = positions
+        .iter()
+        .find(|(_, start_row)| *start_row == row)
+        .map_or(pos, |(start, _)| *start);
+    let horiz = range.horiz.unwrap_or((pos - row_start) as u32);
+
+    let last_row = |line: usize| {
+        wrapped_positions(slice, line, wrap_width, tab_width)
+            .last()
+            .map_or(0, |(_, row)| *row)
+    };
+    for _ in 0..count {
+        match dir {
+            Direction::Forward if row < last_row(line) => row += 1,
+            Direction::Forward if line + 1 < slice.len_lines() => {
+                line += 1;
+                row = 0;
+            }
+            Direction::Backward if row > 0 => row -= 1,
+            Direction::Backward if line > 0 => {
+                line -= 1;
+                row = last_row(line);
+            }
+            _ => break,
+        }
+    }
+
+    // the last position of the row that is at most `horiz` characters into it
+    let positions = wrapped_positions(slice, line, wrap_width, tab_width);
+    let mut in_row = positions
+        .iter()
+        .filter(|(_, start_row)| *start_row <= row)
+        .skip_while(|(_, start_row)| *start_row < row)
+        .map(|(start, _)| *start)
+        .peekable();
+    let row_start = in_row
+        .peek()
+        .copied()
+        .unwrap_or_else(|| slice.line_to_char(line));
+    let new_pos = in_row
+        .take_while(|start| start - row_start <= horiz as usize)
+        .last()
+        .unwrap_or(row_start);
+
+    // Special-case to avoid moving to the end of the last non-empty line.
+    if behaviour == Movement::Extend && slice.line(line).len_chars() == 0 {
+        return range;
+    }
+
+    let mut new_range = range.put_cursor(slice, new_pos, behaviour == Movement::Extend);
+    new_range.horiz = Some(horiz);
+    new_range
+}
+
+/// The start of each grapheme of `line` and the row it's shown on when soft-wrapped at
+/// `wrap_width` columns, followed by the end of the line.
+fn wrapped_positions(
+    slice: RopeSlice,
+    line: usize,
+    wrap_width: usize,
+    tab_width: usize,
+) -> Vec<(usize, usize)> {
+    let line_start = slice.line_to_char(line);
+    let line_end = line_end_char_index(&slice, line);
+    let mut positions = Vec::new();
+    let (mut pos, mut width) = (line_start, 0);
+    for grapheme in RopeGraphemes::new(slice.slice(line_start..line_end)) {
+        positions.push((pos, width / wrap_width));
+        width += if grapheme == "\t" {
+            tab_width
+        } else {
+            grapheme_width(&Cow::from(grapheme))
+        };
+        pos += grapheme.len_chars();
+    }
+    positions.push((pos, width / wrap_width));
+    positions
+}
+
 pub fn move_next_word_start(slice: RopeSlice, range: Range, count: usize) -> Range {
     word_move(slice, range, count, WordMotionTarget::NextWordStart)
 }
@@ -341,6 +442,39 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_vertical_move_visual() {
+        let text = Rope::from("abcdefg\nij");
+        let slice = text.slice(..);
+        let move_visual = |pos, dir| {
+            move_vertically_visual(slice, Range::point(pos), dir, 1, Movement::Move, 4, 4).head
+        };
+
+        // "abcd" and "efg" are shown on rows of their own
+        assert_eq!(move_visual(1, Direction::Forward), 5);
+        assert_eq!(move_visual(5, Direction::Forward), 9);
+        assert_eq!(move_visual(9, Direction::Backward), 5);
+        assert_eq!(move_visual(5, Direction::Backward), 1);
+        // past the end of a shorter row
+        assert_eq!(move_visual(3, Direction::Forward), 7);
+    }
+
+    #[test]
+    fn test_vertical_move_visual_keeps_char_column() {
+        let text = Rope::from("パーティー\nabcdefgh\n");
+        let slice = text.slice(..);
+        let move_visual = |range| {
+            move_vertically_visual(slice, range, Direction::Forward, 1, Movement::Move, 4, 4)
+        };
+
+        // the kana are two columns wide, so "ティ" is the second row. The column counts
+        // characters like `move_vertically` does, not screen columns.
+        let range = move_visual(Range::point(3));
+        assert_eq!((range.head, range.horiz), (5, Some(1)));
+        let range = move_visual(range);
+        assert_eq!((range.head, range.horiz), (7, Some(1)));
+    }
+
     #[test]
     fn horizontal_moves_through_single_line_text() {
         let text = Rope::from(SINGLE_LINE_SAMPLE);
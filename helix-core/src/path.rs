@@ -81,6 +81,18 @@ pub fn get_canonicalized_path(path: &Path) -> std::io::Result<PathBuf> {
     Ok(get_normalized_path(path.as_path()))
 }
 
+/// Like [`get_canonicalized_path`], but also resolves symlinks if the path exists, so that a
+/// file reached through different links always has the same path.
+///
+/// Windows paths are only normalized, as resolving them results in verbatim `\\?\` paths.
+pub fn get_resolved_path(path: &Path) -> std::io::Result<PathBuf> {
+    let path = get_canonicalized_path(path)?;
+    if cfg!(windows) {
+        return Ok(path);
+    }
+    Ok(std::fs::canonicalize(&path).unwrap_or(path))
+}
+
 pub fn get_relative_path(path: &Path) -> PathBuf {
     let path = if path.is_absolute() {
         let cwdir = std::env::current_dir().expect("couldn't determine current directory");
@@ -90,3 +102,30 @@ pub fn get_relative_path(path: &Path) -> PathBuf {
     };
     fold_home_dir(path)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn resolved_path_follows_symlinks() {
+        let dir = std::env::temp_dir().join(format!("helix-resolve-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = std::fs::canonicalize(&dir).unwrap();
+        let file = dir.join("file.txt");
+        let link = dir.join("link.txt");
+        std::fs::write(&file, "").unwrap();
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        assert_eq!(get_resolved_path(&link).unwrap(), file);
+        assert_eq!(get_resolved_path(&dir.join("./link.txt")).unwrap(), file);
+        assert_eq!(get_canonicalized_path(&link).unwrap(), link);
+
+        // paths that don't exist yet are only normalized
+        let missing = dir.join("missing.txt");
+        assert_eq!(get_resolved_path(&missing).unwrap(), missing);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 #[derive(Debug)]
 pub struct Register {
@@ -43,10 +43,11 @@ impl Register {
     }
 }
 
-/// Currently just wraps a `HashMap` of `Register`s
+/// Wraps a `HashMap` of `Register`s, along with the history of recent yanks.
 #[derive(Debug, Default)]
 pub struct Registers {
     inner: HashMap<char, Register>,
+    pub history: YankHistory,
 }
 
 impl Registers {
@@ -68,4 +69,123 @@ impl Registers {
     pub fn read(&self, name: char) -> Option<&[String]> {
         self.get(name).map(|reg| reg.read())
     }
+
+    /// Writes yanked or deleted `values` to register `name` and records them in the
+    /// history, unless `name` is the black hole register `_`.
+    pub fn yank(&mut self, name: char, values: Vec<String>) {
+        if name != '_' {
+            self.history.push(values.clone());
+        }
+        self.write(name, values);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YankEntry {
+    /// One value per selection, like a register.
+    pub values: Vec<String>,
+    /// Pinned entries are never dropped from the history.
+    pub pinned: bool,
+}
+
+/// Recent yanks and deletes for the session, most recent first.
+#[derive(Debug, Default)]
+pub struct YankHistory {
+    entries: VecDeque<YankEntry>,
+}
+
+impl YankHistory {
+    /// Number of unpinned entries kept before the oldest ones are dropped.
+    pub const MAX_UNPINNED: usize = 50;
+
+    /// Records `values` as the most recent entry. Yanking text already in the history moves its
+    /// entry to the front instead of duplicating it.
+    pub fn push(&mut self, values: Vec<String>) {
+        if values.iter().all(String::is_empty) {
+            return;
+        }
+        let pinned = match self.entries.iter().position(|entry| entry.values == values) {
+            Some(index) => self
+                .entries
+                .remove(index)
+                .map_or(false, |entry| entry.pinned),
+            None => false,
+        };
+        self.entries.push_front(YankEntry { values, pinned });
+
+        if self.entries.iter().filter(|entry| !entry.pinned).count() > Self::MAX_UNPINNED {
+            if let Some(index) = self.entries.iter().rposition(|entry| !entry.pinned) {
+                self.entries.remove(index);
+            }
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&YankEntry> {
+        self.entries.get(index)
+    }
+
+    /// Pins or unpins the entry at `index`, returning whether it is now pinned.
+    pub fn toggle_pin(&mut self, index: usize) -> Option<bool> {
+        let entry = self.entries.get_mut(index)?;
+        entry.pinned = !entry.pinned;
+        Some(entry.pinned)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &YankEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn values(s: &str) -> Vec<String> {
+        vec![s.to_string()]
+    }
+
+    #[test]
+    fn test_yank_history() {
+        let mut history = YankHistory::default();
+        history.push(values("a"));
+        history.push(values("b"));
+        history.push(values(""));
+        history.push(values("a"));
+        let texts: Vec<_> = history
+            .iter()
+            .map(|entry| entry.values[0].as_str())
+            .collect();
+        assert_eq!(texts, ["a", "b"]);
+
+        // pinned entries survive past the limit
+        assert_eq!(history.toggle_pin(1), Some(true));
+        for i in 0..YankHistory::MAX_UNPINNED {
+            history.push(values(&i.to_string()));
+        }
+        assert_eq!(history.len(), YankHistory::MAX_UNPINNED + 1);
+        assert!(history.iter().any(|entry| entry.values == values("b")));
+        assert!(!history.iter().any(|entry| entry.values == values("a")));
+    }
+
+    #[test]
+    fn test_black_hole_not_in_history() {
+        let mut registers = Registers::default();
+        registers.yank('"', values("a"));
+        registers.yank('_', values("b"));
+        registers.yank('x', values("c"));
+        let texts: Vec<_> = registers
+            .history
+            .iter()
+            .map(|entry| entry.values[0].as_str())
+            .collect();
+        assert_eq!(texts, ["c", "a"]);
+    }
 }
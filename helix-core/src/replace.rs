@@ -0,0 +1,79 @@
+//! Replacing the matches of a regex, one hunk at a time.
+
+use crate::{regex::Regex, Rope, Transaction};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The 0-indexed line the match starts on.
+    pub line: usize,
+    /// Char range of the match.
+    pub start: usize,
+    pub end: usize,
+    pub matched: String,
+    /// The replacement, with `$1` style references to capture groups expanded.
+    pub replacement: String,
+}
+
+/// Returns a hunk for each match of `regex` in `text`, replaced by `replacement`.
+pub fn find_hunks(text: &Rope, regex: &Regex, replacement: &str) -> Vec<Hunk> {
+    let contents = text.to_string();
+    regex
+        .captures_iter(&contents)
+        .filter_map(|captures| {
+            let whole = captures.get(0)?;
+            // empty matches would insert the replacement between every character
+            if whole.as_str().is_empty() {
+                return None;
+            }
+            let mut expanded = String::new();
+            captures.expand(replacement, &mut expanded);
+            Some(Hunk {
+                line: text.byte_to_line(whole.start()),
+                start: text.byte_to_char(whole.start()),
+                end: text.byte_to_char(whole.end()),
+                matched: whole.as_str().to_string(),
+                replacement: expanded,
+            })
+        })
+        .collect()
+}
+
+/// Builds the transaction replacing `hunks`, sorted by position, in `text`. Returns `None` if
+/// the text of any hunk changed since it was found.
+pub fn apply(text: &Rope, hunks: &[&Hunk]) -> Option<Transaction> {
+    let unchanged = hunks.iter().all(|hunk| {
+        hunk.end <= text.len_chars() && text.slice(hunk.start..hunk.end) == hunk.matched.as_str()
+    });
+    if !unchanged {
+        return None;
+    }
+    let changes = hunks
+        .iter()
+        .map(|hunk| (hunk.start, hunk.end, Some(hunk.replacement.as_str().into())));
+    Some(Transaction::change(text, changes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_and_apply_hunks() {
+        let mut text = Rope::from("let a = foo(1);\nlet b = foo(22);\n");
+        let regex = Regex::new(r"foo\((\d+)\)").unwrap();
+        let hunks = find_hunks(&text, &regex, "bar($1, 0)");
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[1].line, 1);
+        assert_eq!(hunks[1].matched, "foo(22)");
+        assert_eq!(hunks[1].replacement, "bar(22, 0)");
+
+        // only the second hunk is applied
+        let transaction = apply(&text, &[&hunks[1]]).unwrap();
+        assert!(transaction.apply(&mut text));
+        assert_eq!(text, "let a = foo(1);\nlet b = bar(22, 0);\n");
+
+        // the first hunk still applies, the second one is stale
+        assert!(apply(&text, &[&hunks[0]]).is_some());
+        assert!(apply(&text, &[&hunks[0], &hunks[1]]).is_none());
+    }
+}
@@ -1,4 +1,9 @@
 use crate::RopeSlice;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::ops::Range;
+use unicode_general_category::{get_general_category, GeneralCategory};
+use unicode_normalization::UnicodeNormalization;
 
 pub fn find_nth_next(text: RopeSlice, ch: char, mut pos: usize, n: usize) -> Option<usize> {
     if pos >= text.len_chars() || n == 0 {
@@ -43,3 +48,144 @@ pub fn find_nth_prev(text: RopeSlice, ch: char, mut pos: usize, n: usize) -> Opt
 
     Some(pos)
 }
+
+/// How text and search patterns are normalized before matching.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Normalization {
+    /// Match the text as is
+    None,
+
+    /// Match regardless of the Unicode normalization form (NFC or NFD) of the text
+    Canonical,
+
+    /// Also ignore diacritics, so that "resume" matches "résumé"
+    FoldDiacritics,
+}
+
+impl std::str::FromStr for Normalization {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "canonical" => Ok(Self::Canonical),
+            "fold-diacritics" => Ok(Self::FoldDiacritics),
+            _ => anyhow::bail!(
+                "Search normalization can only be `none`, `canonical` or `fold-diacritics`."
+            ),
+        }
+    }
+}
+
+impl Normalization {
+    fn push(self, out: &mut String, ch: char) {
+        match self {
+            Self::None => out.push(ch),
+            Self::Canonical => out.extend(std::iter::once(ch).nfd()),
+            Self::FoldDiacritics => out.extend(
+                std::iter::once(ch)
+                    .nfd()
+                    .filter(|&ch| get_general_category(ch) != GeneralCategory::NonspacingMark),
+            ),
+        }
+    }
+
+    /// Normalizes a search pattern, to match it against a [`NormalizedText`].
+    pub fn normalize(self, text: &str) -> Cow<'_, str> {
+        if self == Self::None {
+            return text.into();
+        }
+        let mut normalized = String::with_capacity(text.len());
+        for ch in text.chars() {
+            self.push(&mut normalized, ch);
+        }
+        normalized.into()
+    }
+}
+
+/// A copy of a text to search in, normalized, which maps matches back to the original text.
+#[derive(Debug, Clone)]
+pub struct NormalizedText {
+    text: String,
+    /// Byte offset in `text` of each char of the original text and of its end,
+    /// unless the text isn't normalized.
+    starts: Option<Vec<usize>>,
+}
+
+impl NormalizedText {
+    pub fn new(text: RopeSlice, normalization: Normalization) -> Self {
+        if normalization == Normalization::None {
+            return Self {
+                text: text.to_string(),
+                starts: None,
+            };
+        }
+
+        let mut normalized = String::with_capacity(text.len_bytes());
+        let mut starts = Vec::with_capacity(text.len_chars() + 1);
+        for ch in text.chars() {
+            starts.push(normalized.len());
+            normalization.push(&mut normalized, ch);
+        }
+        starts.push(normalized.len());
+
+        Self {
+            text: normalized,
+            starts: Some(starts),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset in the normalized text of the char at `char_idx` in `text`,
+    /// the text this was created from.
+    pub fn char_to_byte(&self, text: RopeSlice, char_idx: usize) -> usize {
+        match &self.starts {
+            Some(starts) => starts[char_idx],
+            None => text.char_to_byte(char_idx),
+        }
+    }
+
+    /// Translates the byte range of a match in the normalized text to a char range in `text`,
+    /// the text this was created from. Diacritics that were folded away are included at its end.
+    pub fn char_range(&self, text: RopeSlice, range: Range<usize>) -> Range<usize> {
+        match &self.starts {
+            Some(starts) => {
+                let start = starts.partition_point(|&byte| byte <= range.start) - 1;
+                let end = starts.partition_point(|&byte| byte <= range.end) - 1;
+                start..end
+            }
+            None => text.byte_to_char(range.start)..text.byte_to_char(range.end),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Rope;
+
+    #[test]
+    fn test_normalized_text() {
+        // precomposed and decomposed accents
+        let rope = Rope::from("r\u{e9}sume\u{301} x");
+        let text = rope.slice(..);
+
+        let normalized = NormalizedText::new(text, Normalization::FoldDiacritics);
+        assert_eq!(normalized.as_str(), "resume x");
+        // the folded accent is part of the match
+        assert_eq!(normalized.char_range(text, 0..6), 0..7);
+        assert_eq!(normalized.char_range(text, 7..8), 8..9);
+        assert_eq!(normalized.char_to_byte(text, 8), 7);
+
+        let normalized = NormalizedText::new(text, Normalization::Canonical);
+        let pattern = Normalization::Canonical.normalize("r\u{e9}sum\u{e9}");
+        assert!(normalized.as_str().starts_with(pattern.as_ref()));
+
+        let normalized = NormalizedText::new(text, Normalization::None);
+        assert_eq!(normalized.char_range(text, 1..3), 1..2);
+    }
+}
@@ -620,8 +620,14 @@ pub fn select_on_matches(
     regex: &crate::regex::Regex,
 ) -> Option<Selection> {
     let mut result = SmallVec::with_capacity(selection.len());
+    // the first match in the primary range, or after it if it has none
+    let mut primary_index = 0;
+
+    for (i, sel) in selection.iter().enumerate() {
+        if i == selection.primary_index() {
+            primary_index = result.len();
+        }
 
-    for sel in selection {
         // TODO: can't avoid occasional allocations since Regex can't operate on chunks yet
         let fragment = sel.fragment(text);
 
@@ -637,9 +643,9 @@ pub fn select_on_matches(
         }
     }
 
-    // TODO: figure out a new primary index
     if !result.is_empty() {
-        return Some(Selection::new(result, 0));
+        let primary_index = primary_index.min(result.len() - 1);
+        return Some(Selection::new(result, primary_index));
     }
 
     None
@@ -652,8 +658,14 @@ pub fn split_on_matches(
     regex: &crate::regex::Regex,
 ) -> Selection {
     let mut result = SmallVec::with_capacity(selection.len());
+    // the first part of the primary range
+    let mut primary_index = 0;
+
+    for (i, sel) in selection.iter().enumerate() {
+        if i == selection.primary_index() {
+            primary_index = result.len();
+        }
 
-    for sel in selection {
         // Special case: zero-width selection.
         if sel.from() == sel.to() {
             result.push(*sel);
@@ -682,8 +694,8 @@ pub fn split_on_matches(
         }
     }
 
-    // TODO: figure out a new primary index
-    Selection::new(result, 0)
+    let primary_index = primary_index.min(result.len().saturating_sub(1));
+    Selection::new(result, primary_index)
 }
 
 #[cfg(test)]
@@ -1019,6 +1031,29 @@ mod test {
             result.fragments(text.slice(..)).collect::<Vec<_>>(),
             &["", "abcd", "efg", "rs", "xyz"]
         );
+
+        // the primary range stays in the primary selection
+        let mut selection = selection;
+        selection.set_primary_index(1);
+        let result = split_on_matches(text.slice(..), &selection, &Regex::new(r"\s+").unwrap());
+        assert_eq!(result.primary(), Range::new(11, 13));
+    }
+
+    #[test]
+    fn test_select_on_matches() {
+        use crate::regex::Regex;
+
+        let text = Rope::from("ab ab ab\nab ab");
+        let selection = Selection::new(smallvec![Range::new(0, 8), Range::new(9, 14)], 1);
+
+        let result = select_on_matches(text.slice(..), &selection, &Regex::new("ab").unwrap());
+        assert_eq!(result.as_ref().map(Selection::len), Some(5));
+        assert_eq!(result.unwrap().primary(), Range::new(9, 11));
+
+        assert_eq!(
+            select_on_matches(text.slice(..), &selection, &Regex::new("x").unwrap()),
+            None
+        );
     }
     #[test]
     fn test_selection_contains() {
@@ -0,0 +1,125 @@
+//! User snippets: bodies of text inserted by completing their prefix, stored per language in
+//! `snippets/<language>.toml` in the config directory.
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Snippet {
+    /// What to type to complete the snippet.
+    pub prefix: String,
+    pub body: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Shown in new snippet files.
+pub const EXAMPLE: &str = r#"# Snippets are completed by typing their prefix, see `:snippets` for all of them.
+#
+# [println]
+# prefix = "pl"
+# body = 'println!("{}");'
+# description = "Print a line"
+"#;
+
+/// The snippet file of a language, named after its `language-id` in `languages.toml`.
+pub fn path(language_id: &str) -> PathBuf {
+    crate::config_dir()
+        .join("snippets")
+        .join(format!("{}.toml", language_id))
+}
+
+/// Parses a snippet file, mapping snippet names to snippets.
+pub fn parse(toml: &str) -> Result<BTreeMap<String, Snippet>, toml::de::Error> {
+    toml::from_str(toml)
+}
+
+/// The snippets of a language. A missing snippet file has none.
+pub fn load(language_id: &str) -> std::io::Result<BTreeMap<String, Snippet>> {
+    let path = path(language_id);
+    match std::fs::read_to_string(&path) {
+        Ok(toml) => parse(&toml).map_err(|err| {
+            let message = format!("failed to parse {}: {}", path.display(), err);
+            std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+        }),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err),
+    }
+}
+
+type Snippets = Arc<BTreeMap<String, Snippet>>;
+/// The modification time of a snippet file when it was read, or `None` if it was missing, and
+/// its snippets.
+type CacheEntry = (Option<SystemTime>, Snippets);
+
+/// The snippets loaded for each language.
+static CACHE: Lazy<Mutex<HashMap<String, CacheEntry>>> = Lazy::new(Default::default);
+
+/// Like [`load`], but only reads the snippet file again once it was modified, for completing
+/// snippets on every keystroke.
+pub fn load_cached(language_id: &str) -> std::io::Result<Snippets> {
+    let modified = std::fs::metadata(path(language_id))
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let mut cache = CACHE.lock().unwrap();
+    if let Some((cached_modified, snippets)) = cache.get(language_id) {
+        if *cached_modified == modified {
+            return Ok(snippets.clone());
+        }
+    }
+    let snippets = Arc::new(load(language_id)?);
+    cache.insert(language_id.to_string(), (modified, snippets.clone()));
+    Ok(snippets)
+}
+
+/// The line of `toml` where the snippet `name` is defined.
+pub fn line(toml: &str, name: &str) -> Option<usize> {
+    let header = format!("[{}]", name);
+    let quoted = format!("[\"{}\"]", name);
+    toml.lines()
+        .position(|line| line.trim() == header || line.trim() == quoted)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_snippets() {
+        let toml = r#"
+            [println]
+            prefix = "pl"
+            body = 'println!("{}");'
+            description = "Print a line"
+
+            ["test module"]
+            prefix = "tm"
+            body = """
+            #[cfg(test)]
+            mod test {}
+            """
+        "#;
+        let snippets = parse(toml).unwrap();
+        assert_eq!(
+            snippets["println"],
+            Snippet {
+                prefix: "pl".into(),
+                body: "println!(\"{}\");".into(),
+                description: Some("Print a line".into()),
+            }
+        );
+        assert_eq!(snippets["test module"].description, None);
+        assert_eq!(line(toml, "println"), Some(1));
+        assert_eq!(line(toml, "test module"), Some(6));
+        assert_eq!(line(toml, "missing"), None);
+
+        // the example is valid, and has no snippets
+        assert!(parse(EXAMPLE).unwrap().is_empty());
+        assert!(parse("[a]\nbody = \"b\"").is_err());
+    }
+}
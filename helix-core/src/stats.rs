@@ -0,0 +1,89 @@
+//! Line, word, character and byte counts of text.
+
+use crate::{chars::char_is_whitespace, RopeSlice};
+use std::ops::AddAssign;
+
+/// Words read per minute, for reading time estimates.
+const READING_SPEED: usize = 200;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    pub bytes: usize,
+}
+
+impl Stats {
+    pub fn new(text: RopeSlice) -> Self {
+        let mut words = 0;
+        let mut in_word = false;
+        for ch in text.chars() {
+            let is_word = !char_is_whitespace(ch);
+            if is_word && !in_word {
+                words += 1;
+            }
+            in_word = is_word;
+        }
+
+        let chars = text.len_chars();
+        // a trailing line ending doesn't start another line
+        let lines = match chars.checked_sub(1).map(|last| text.char(last)) {
+            None => 0,
+            Some('\n') => text.len_lines() - 1,
+            Some(_) => text.len_lines(),
+        };
+
+        Self {
+            lines,
+            words,
+            chars,
+            bytes: text.len_bytes(),
+        }
+    }
+
+    /// Estimated time to read the text in minutes, rounded up.
+    pub fn reading_minutes(&self) -> usize {
+        match self.words {
+            0 => 0,
+            words => (words - 1) / READING_SPEED + 1,
+        }
+    }
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Rope;
+
+    #[test]
+    fn test_stats() {
+        let text = Rope::from("hello  wörld\n\tfoo-bar baz\n");
+        assert_eq!(
+            Stats::new(text.slice(..)),
+            Stats {
+                lines: 2,
+                words: 4,
+                chars: 26,
+                bytes: 27,
+            }
+        );
+        assert_eq!(Stats::new(Rope::from("a").slice(..)).lines, 1);
+        assert_eq!(Stats::new(Rope::new().slice(..)), Stats::default());
+        assert_eq!(Stats::default().reading_minutes(), 0);
+        let stats = Stats {
+            words: 201,
+            ..Default::default()
+        };
+        assert_eq!(stats.reading_minutes(), 2);
+    }
+}
@@ -61,9 +61,18 @@ pub struct LanguageConfiguration {
 
     #[serde(default, skip_serializing, deserialize_with = "deserialize_lsp_config")]
     pub config: Option<serde_json::Value>,
+    /// Language server settings, answering `workspace/configuration` requests.
+    #[serde(default, skip_serializing, deserialize_with = "deserialize_lsp_config")]
+    pub settings: Option<serde_json::Value>,
 
     #[serde(default)]
     pub auto_format: bool,
+    /// Expand Emmet abbreviations before the cursor when pressing tab in insert mode.
+    #[serde(default)]
+    pub emmet: bool,
+    /// Insert the closing tag when typing the `>` of an opening tag.
+    #[serde(default)]
+    pub auto_close_tags: bool,
     #[serde(default)]
     pub diagnostic_severity: Severity,
 
@@ -530,6 +539,31 @@ impl Syntax {
         result.sort_layers();
         result
     }
+
+    /// The byte ranges captured as `comment` by the highlight query, in order.
+    pub fn comment_ranges(&self, source: RopeSlice) -> Vec<std::ops::Range<usize>> {
+        let query = &self.config.query;
+        let comment_captures: Vec<_> = query
+            .capture_names()
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| *name == "comment" || name.starts_with("comment."))
+            .map(|(i, _)| i as u32)
+            .collect();
+        if comment_captures.is_empty() {
+            return Vec::new();
+        }
+
+        let mut cursor = QueryCursor::new();
+        let mut ranges: Vec<_> = cursor
+            .captures(query, self.tree().root_node(), RopeProvider(source))
+            .map(|(mat, idx)| mat.captures[idx])
+            .filter(|capture| comment_captures.contains(&capture.index))
+            .map(|capture| capture.node.byte_range())
+            .collect();
+        ranges.dedup();
+        ranges
+    }
     // on_tokenize
     // on_change_highlighting
 
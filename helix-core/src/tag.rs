@@ -0,0 +1,282 @@
+//! Matching of HTML, XML and JSX tags.
+//!
+//! Tags are matched on the text rather than the syntax tree so that it behaves
+//! the same in every markup flavour, including markup embedded in other
+//! languages. Only [`closing_tag_at`] asks the tree whether a `>` really ends
+//! an opening tag.
+
+use crate::{regex::Regex, RopeSlice, Syntax};
+use once_cell::sync::Lazy;
+use std::{borrow::Cow, ops::Range};
+
+/// Elements which have no closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+// An attribute is `name`, `name=value` or a JSX `{...spread}`.
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?x)
+        <(/?)([A-Za-z][\w:.-]*)
+        (?:\s+(?:
+            [\w:.@\#-]+(?:\s*=\s*(?:"[^"]*"|'[^']*'|\{[^{}]*\}|[^\s<>"'=`]+))?
+            | \{[^{}]*\}
+        ))*
+        \s*(/?)>"#,
+    )
+    .unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TagKind {
+    Open,
+    Close,
+    SelfClosing,
+}
+
+#[derive(Debug)]
+struct Tag {
+    kind: TagKind,
+    /// Byte range of the whole tag.
+    range: Range<usize>,
+    /// Byte range of the tag name.
+    name: Range<usize>,
+}
+
+fn tags(text: &str) -> Vec<Tag> {
+    TAG_REGEX
+        .captures_iter(text)
+        .map(|captures| {
+            let name = captures.get(2).unwrap();
+            let kind = if !captures[1].is_empty() {
+                TagKind::Close
+            } else if !captures[3].is_empty()
+                || VOID_ELEMENTS.contains(&name.as_str().to_lowercase().as_str())
+            {
+                TagKind::SelfClosing
+            } else {
+                TagKind::Open
+            };
+            Tag {
+                kind,
+                range: captures.get(0).unwrap().range(),
+                name: name.range(),
+            }
+        })
+        .collect()
+}
+
+/// Returns the closing tag to insert after `before`, which ends with the `>`
+/// of an opening tag that was just typed. `after` is the text following it,
+/// used to avoid closing a tag twice.
+pub fn closing_tag(before: &str, after: &str) -> Option<String> {
+    let start = before.rfind('<')?;
+    let tag = TAG_REGEX.find(&before[start..])?;
+    if tag.start() != 0 || tag.end() != before.len() - start {
+        return None;
+    }
+    let tag = tags(&before[start..]).pop()?;
+    if tag.kind != TagKind::Open {
+        return None;
+    }
+
+    let closing = format!(
+        "</{}>",
+        &before[start + tag.name.start..start + tag.name.end]
+    );
+    if after.trim_start().starts_with(&closing) {
+        return None;
+    }
+    Some(closing)
+}
+
+/// Node kinds of opening tags: `start_tag` in HTML, Vue and Svelte,
+/// `jsx_opening_element` in JSX and TSX.
+const OPENING_TAG_KINDS: &[&str] = &["start_tag", "jsx_opening_element"];
+
+/// Like [`closing_tag`], but for the `>` just before char index `pos` of a
+/// parsed document. The tag is only closed if the syntax tree has an opening
+/// tag ending there, so type arguments like `useState<string>` and
+/// comparisons are left alone.
+pub fn closing_tag_at(syntax: &Syntax, text: RopeSlice, pos: usize, after: &str) -> Option<String> {
+    let byte = text.char_to_byte(pos);
+    let mut node = syntax
+        .tree()
+        .root_node()
+        .descendant_for_byte_range(byte.checked_sub(1)?, byte)?;
+    while !OPENING_TAG_KINDS.contains(&node.kind()) {
+        node = node.parent()?;
+    }
+    if node.end_byte() != byte {
+        return None;
+    }
+    let tag = Cow::from(text.slice(text.byte_to_char(node.start_byte())..pos));
+    closing_tag(&tag, after)
+}
+
+/// Find the tag names of the element at byte offset `pos`: the element whose
+/// opening or closing tag contains `pos`, or else the innermost element
+/// enclosing it. Returns the byte ranges of the opening and closing tag names.
+pub fn find_tag_names(text: &str, pos: usize) -> Option<(Range<usize>, Range<usize>)> {
+    let tags = tags(text);
+    let name_of = |tag: &Tag| &text[tag.name.clone()];
+
+    // index of the opening tag of the element
+    let open = match tags.iter().position(|tag| tag.range.contains(&pos)) {
+        Some(i) if tags[i].kind == TagKind::Open => i,
+        Some(i) if tags[i].kind == TagKind::Close => {
+            // walk back to the matching opening tag
+            let mut depth = 0;
+            let name = name_of(&tags[i]);
+            let open = tags[..i].iter().rposition(|tag| match tag.kind {
+                _ if name_of(tag) != name => false,
+                TagKind::Close => {
+                    depth += 1;
+                    false
+                }
+                TagKind::Open if depth > 0 => {
+                    depth -= 1;
+                    false
+                }
+                TagKind::Open => true,
+                TagKind::SelfClosing => false,
+            })?;
+            return Some((tags[open].name.clone(), tags[i].name.clone()));
+        }
+        Some(_) => return None,
+        None => {
+            // innermost element which is still open at `pos`
+            let mut stack = Vec::new();
+            for (i, tag) in tags.iter().enumerate() {
+                if tag.range.start >= pos {
+                    break;
+                }
+                match tag.kind {
+                    TagKind::Open => stack.push(i),
+                    TagKind::Close => {
+                        if let Some(open) = stack
+                            .iter()
+                            .rposition(|&open| name_of(&tags[open]) == name_of(tag))
+                        {
+                            stack.truncate(open);
+                        }
+                    }
+                    TagKind::SelfClosing => (),
+                }
+            }
+            *stack.last()?
+        }
+    };
+
+    let name = name_of(&tags[open]);
+    let mut depth = 0;
+    let close = tags[open + 1..].iter().find(|tag| match tag.kind {
+        _ if name_of(tag) != name => false,
+        TagKind::Open => {
+            depth += 1;
+            false
+        }
+        TagKind::Close if depth > 0 => {
+            depth -= 1;
+            false
+        }
+        TagKind::Close => true,
+        TagKind::SelfClosing => false,
+    })?;
+    Some((tags[open].name.clone(), close.name.clone()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_closing_tag() {
+        assert_eq!(closing_tag("<div>", ""), Some("</div>".to_string()));
+        assert_eq!(
+            closing_tag(r#"<p> <a href="x>y" class='c'>"#, "\n"),
+            Some("</a>".to_string())
+        );
+        assert_eq!(
+            closing_tag("<Foo.Bar prop={1}>", ""),
+            Some("</Foo.Bar>".to_string())
+        );
+
+        assert_eq!(closing_tag("<div>", " </div>"), None);
+        assert_eq!(closing_tag("</div>", ""), None);
+        assert_eq!(closing_tag("<br>", ""), None);
+        assert_eq!(closing_tag("<Foo />", ""), None);
+        assert_eq!(closing_tag("<!-- x -->", ""), None);
+        assert_eq!(closing_tag("if (a <b && c>", ""), None);
+        assert_eq!(closing_tag("a > b", ""), None);
+    }
+
+    #[test]
+    fn test_closing_tag_at() {
+        use crate::syntax::{Configuration, Loader};
+        use crate::Rope;
+
+        // set runtime path so we can find the queries
+        let mut runtime = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        runtime.push("../runtime");
+        std::env::set_var("HELIX_RUNTIME", runtime.to_str().unwrap());
+
+        let config: Configuration = toml::from_str(include_str!("../../languages.toml")).unwrap();
+        let loader = Loader::new(config);
+        let closing = |scope: &str, text: &str, cursor: &str| {
+            let doc = Rope::from(text);
+            let language_config = loader.language_config_for_scope(scope).unwrap();
+            let highlight_config = language_config.highlight_config(&[]).unwrap();
+            let syntax = Syntax::new(&doc, highlight_config);
+            let pos = text.find(cursor).unwrap() + cursor.len();
+            closing_tag_at(&syntax, doc.slice(..), pos, &text[pos..])
+        };
+
+        assert_eq!(
+            closing("text.html.basic", "<ul>\n  <li>\n</ul>\n", "<li>"),
+            Some("</li>".to_string())
+        );
+        assert_eq!(
+            closing(
+                "source.tsx",
+                "const [a, setA] = useState<string>\n",
+                "useState<string>"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_tag_names() {
+        let text = "<ul>\n  <li><b>one</b></li>\n  <li>two<br></li>\n</ul>";
+        let names =
+            |pos| find_tag_names(text, pos).map(|(open, close)| (&text[open], &text[close]));
+        let position = |s: &str| text.find(s).unwrap();
+
+        // inside the tags themselves
+        assert_eq!(names(1), Some(("ul", "ul")));
+        assert_eq!(names(text.len() - 2), Some(("ul", "ul")));
+        assert_eq!(names(position("</b>") + 1), Some(("b", "b")));
+        // in the content of an element
+        assert_eq!(names(position("one")), Some(("b", "b")));
+        assert_eq!(names(position("two")), Some(("li", "li")));
+        // between elements
+        assert_eq!(names(position("\n  <li>two")), Some(("ul", "ul")));
+        // void elements have no closing tag
+        assert_eq!(names(position("<br>") + 1), None);
+    }
+
+    #[test]
+    fn test_find_nested_tag_names() {
+        let text = "<div><div>inner</div>outer</div>";
+        let (open, close) = find_tag_names(text, text.find("outer").unwrap()).unwrap();
+        assert_eq!((open.start, close.start), (1, text.rfind("div").unwrap()));
+        let (open, close) = find_tag_names(text, text.find("</div>").unwrap()).unwrap();
+        assert_eq!(
+            (open.start, close.start),
+            (6, text.find("</div>").unwrap() + 2)
+        );
+    }
+}
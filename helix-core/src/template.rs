@@ -0,0 +1,105 @@
+//! File templates with `{{variable}}` placeholders, used to scaffold new files.
+
+use std::path::Path;
+
+/// The placeholder variables of a new file at `path`, in the workspace at `root`:
+///
+/// - `filename`: the name of the file, like `insert.rs`
+/// - `stem`: the name without its extension, like `insert`
+/// - `module`: the path in the workspace without its extension and a leading `src`
+///   directory, joined with dots, like `commands.insert`
+/// - `date`: today's date, as given
+///
+/// The file variables are empty for buffers without a file.
+pub fn variables(path: Option<&Path>, root: &Path, date: &str) -> Vec<(&'static str, String)> {
+    let name = |path: Option<&std::ffi::OsStr>| {
+        path.map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+
+    let module = path
+        .map(|path| {
+            let path = path.strip_prefix(root).unwrap_or(path).with_extension("");
+            let path = path.strip_prefix("src").unwrap_or(&path);
+            path.iter()
+                .map(|component| component.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .unwrap_or_default();
+
+    vec![
+        ("filename", name(path.and_then(Path::file_name))),
+        ("stem", name(path.and_then(Path::file_stem))),
+        ("module", module),
+        ("date", date.to_string()),
+    ]
+}
+
+/// Replaces the `{{variable}}` placeholders of `template`. Unknown placeholders are kept.
+pub fn expand(template: &str, variables: &[(&str, String)]) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find("}}") {
+            Some(end) => end,
+            None => break,
+        };
+        let name = rest[2..end].trim();
+        match variables.iter().find(|(variable, _)| *variable == name) {
+            Some((_, value)) => expanded.push_str(value),
+            None => expanded.push_str(&rest[..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_variables() {
+        let root = Path::new("/project");
+        let variables = variables(
+            Some(Path::new("/project/src/commands/insert.rs")),
+            root,
+            "2022-01-31",
+        );
+        assert_eq!(
+            variables,
+            [
+                ("filename", "insert.rs".to_string()),
+                ("stem", "insert".to_string()),
+                ("module", "commands.insert".to_string()),
+                ("date", "2022-01-31".to_string()),
+            ]
+        );
+
+        let scratch = super::variables(None, root, "2022-01-31");
+        assert_eq!(scratch[0], ("filename", String::new()));
+        assert_eq!(scratch[2], ("module", String::new()));
+    }
+
+    #[test]
+    fn test_expand() {
+        let variables = [
+            ("stem", "insert".to_string()),
+            ("date", "2022-01-31".to_string()),
+        ];
+        assert_eq!(
+            expand("// {{stem}}, {{ date }}\nmod {{stem}};\n", &variables),
+            "// insert, 2022-01-31\nmod insert;\n"
+        );
+        // unknown and unclosed placeholders are kept
+        assert_eq!(
+            expand("{{unknown}} {{stem}} {{stem", &variables),
+            "{{unknown}} insert {{stem"
+        );
+        assert_eq!(expand("no placeholders", &variables), "no placeholders");
+    }
+}
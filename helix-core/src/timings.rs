@@ -0,0 +1,111 @@
+//! Timings of startup and document loading phases, collected when helix is
+//! started with `--timings`.
+
+use once_cell::sync::{Lazy, OnceCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static START: OnceCell<Instant> = OnceCell::new();
+static TIMINGS: Lazy<Mutex<Vec<Timing>>> = Lazy::new(Mutex::default);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    pub name: String,
+    pub duration: Duration,
+}
+
+/// Start collecting timings. Durations since startup are measured from here.
+pub fn enable() {
+    START.get_or_init(Instant::now);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Time since [`enable`] was called.
+pub fn since_start() -> Option<Duration> {
+    START.get().map(Instant::elapsed)
+}
+
+pub fn record(name: impl Into<String>, duration: Duration) {
+    if is_enabled() {
+        TIMINGS.lock().unwrap().push(Timing {
+            name: name.into(),
+            duration,
+        });
+    }
+}
+
+/// Like [`record`], unless there already is a timing called `name`.
+pub fn record_once(name: impl Into<String>, duration: Duration) {
+    if is_enabled() {
+        let name = name.into();
+        let mut timings = TIMINGS.lock().unwrap();
+        if !timings.iter().any(|timing| timing.name == name) {
+            timings.push(Timing { name, duration });
+        }
+    }
+}
+
+/// Runs `f`, recording how long it took as `name`.
+pub fn time<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    if !is_enabled() {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    record(name, start.elapsed());
+    result
+}
+
+pub fn timings() -> Vec<Timing> {
+    TIMINGS.lock().unwrap().clone()
+}
+
+/// The timings as a table, in the order they were recorded.
+pub fn report(timings: &[Timing]) -> String {
+    let width = timings
+        .iter()
+        .map(|timing| timing.name.chars().count())
+        .max()
+        .unwrap_or_default();
+    timings
+        .iter()
+        .map(|timing| {
+            format!(
+                "{:<width$}  {:>9.2}ms",
+                timing.name,
+                timing.duration.as_secs_f64() * 1000.0,
+                width = width
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let timings = [
+            Timing {
+                name: "config".into(),
+                duration: Duration::from_micros(1500),
+            },
+            Timing {
+                name: "first frame".into(),
+                duration: Duration::from_millis(42),
+            },
+        ];
+        assert_eq!(
+            report(&timings),
+            "config            1.50ms\nfirst frame      42.00ms"
+        );
+    }
+}
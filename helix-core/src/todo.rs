@@ -0,0 +1,98 @@
+//! TODO, FIXME and HACK notes left in comments.
+
+use crate::{chars::char_is_word, Rope, Syntax};
+
+/// The words marking a note, matched case-sensitively as whole words.
+pub const KEYWORDS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Todo {
+    /// The 0-indexed line of the keyword.
+    pub line: usize,
+    pub keyword: &'static str,
+    /// The rest of the comment line after the keyword.
+    pub text: String,
+}
+
+/// Returns the notes in the comments of `text`, as captured by the highlight query of its
+/// `syntax`.
+pub fn find(text: &Rope, syntax: &Syntax) -> Vec<Todo> {
+    let source = text.slice(..);
+    let mut todos = Vec::new();
+    let mut last_line = None;
+    for range in syntax.comment_ranges(source) {
+        let start = text.byte_to_line(range.start);
+        let end = text.byte_to_line(range.end.saturating_sub(1).max(range.start));
+        for line in start..=end {
+            // line comments next to each other can share a line with the previous range
+            if last_line >= Some(line) {
+                continue;
+            }
+            last_line = Some(line);
+            let line_start = text.line_to_byte(line);
+            let from = range.start.max(line_start) - line_start;
+            let to = range.end.min(text.line_to_byte(line + 1)) - line_start;
+            let content = text.line(line).to_string();
+            let comment = content.get(from..to).unwrap_or(&content);
+            if let Some((keyword, rest)) = find_keyword(comment) {
+                todos.push(Todo {
+                    line,
+                    keyword,
+                    text: rest.to_string(),
+                });
+            }
+        }
+    }
+    todos
+}
+
+/// Finds the first keyword in a line of a comment, returning it and the text following it.
+pub fn find_keyword(comment: &str) -> Option<(&'static str, &str)> {
+    let is_word_at = |pos: usize| comment[pos..].chars().next().map_or(false, char_is_word);
+    let (start, keyword) = KEYWORDS
+        .iter()
+        .flat_map(|&keyword| {
+            comment
+                .match_indices(keyword)
+                .map(move |(start, _)| (start, keyword))
+        })
+        .filter(|&(start, keyword)| {
+            let before = comment[..start]
+                .chars()
+                .next_back()
+                .map_or(false, char_is_word);
+            !before && !is_word_at(start + keyword.len())
+        })
+        .min_by_key(|&(start, _)| start)?;
+
+    let rest = comment[start + keyword.len()..]
+        .trim_start_matches(|ch: char| ch == ':' || ch.is_whitespace())
+        .trim_end();
+    let rest = rest.strip_suffix("*/").unwrap_or(rest).trim_end();
+    Some((keyword, rest))
+}
+
+/// Whether `text` mentions a keyword at all, to skip parsing files without notes.
+pub fn mentions_keyword(text: &str) -> bool {
+    KEYWORDS.iter().any(|keyword| text.contains(keyword))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_keyword() {
+        assert_eq!(
+            find_keyword("// TODO: handle errors"),
+            Some(("TODO", "handle errors"))
+        );
+        assert_eq!(find_keyword("/* FIXME */"), Some(("FIXME", "")));
+        assert_eq!(
+            find_keyword("# HACK(x) until TODO"),
+            Some(("HACK", "(x) until TODO"))
+        );
+        assert_eq!(find_keyword("// TODOS and NOTODO"), None);
+        assert_eq!(find_keyword("// nothing to do"), None);
+    }
+}
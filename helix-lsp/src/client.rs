@@ -11,7 +11,7 @@ use std::future::Future;
 use std::process::Stdio;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
-    Arc,
+    Arc, Mutex,
 };
 use tokio::{
     io::{BufReader, BufWriter},
@@ -30,7 +30,11 @@ pub struct Client {
     request_counter: AtomicU64,
     pub(crate) capabilities: OnceCell<lsp::ServerCapabilities>,
     offset_encoding: OffsetEncoding,
+    /// Sent as `initializationOptions`.
     config: Option<Value>,
+    /// Settings sent with `workspace/didChangeConfiguration` and in reply to
+    /// `workspace/configuration` requests.
+    settings: Mutex<Option<Value>>,
     root_markers: Vec<String>,
 }
 
@@ -40,6 +44,7 @@ impl Client {
         cmd: &str,
         args: &[String],
         config: Option<Value>,
+        settings: Option<Value>,
         root_markers: Vec<String>,
         id: usize,
     ) -> Result<(Self, UnboundedReceiver<(usize, Call)>, Arc<Notify>)> {
@@ -70,6 +75,7 @@ impl Client {
             capabilities: OnceCell::new(),
             offset_encoding: OffsetEncoding::Utf8,
             config,
+            settings: Mutex::new(settings),
             root_markers,
         };
 
@@ -110,6 +116,30 @@ impl Client {
         self.offset_encoding
     }
 
+    pub fn settings(&self) -> Option<Value> {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// The settings for each item of a `workspace/configuration` request: the
+    /// value at the item's dotted `section`, or all of them without one. Null
+    /// for sections that aren't set.
+    pub fn configuration(&self, params: &lsp::ConfigurationParams) -> Vec<Value> {
+        let settings = self.settings();
+        params
+            .items
+            .iter()
+            .map(|item| {
+                let mut value = settings.as_ref();
+                if let Some(section) = &item.section {
+                    for key in section.split('.') {
+                        value = value.and_then(|value| value.get(key));
+                    }
+                }
+                value.cloned().unwrap_or(Value::Null)
+            })
+            .collect()
+    }
+
     /// Execute a RPC request on the language server.
     async fn request<R: lsp::request::Request>(&self, params: R::Params) -> Result<R::Result>
     where
@@ -231,8 +261,8 @@ impl Client {
         let root = find_root(None, &self.root_markers)
             .and_then(|root| lsp::Url::from_file_path(root).ok());
 
-        if self.config.is_some() {
-            log::info!("Using custom LSP config: {}", self.config.as_ref().unwrap());
+        if let Some(config) = &self.config {
+            log::info!("Using custom LSP config: {}", config);
         }
 
         #[allow(deprecated)]
@@ -261,6 +291,16 @@ impl Client {
                         content_format: Some(vec![lsp::MarkupKind::Markdown]),
                         ..Default::default()
                     }),
+                    signature_help: Some(lsp::SignatureHelpClientCapabilities {
+                        signature_information: Some(lsp::SignatureInformationSettings {
+                            documentation_format: Some(vec![lsp::MarkupKind::PlainText]),
+                            parameter_information: Some(lsp::ParameterInformationSettings {
+                                label_offset_support: Some(true),
+                            }),
+                            active_parameter_support: Some(true),
+                        }),
+                        ..Default::default()
+                    }),
                     rename: Some(lsp::RenameClientCapabilities {
                         dynamic_registration: Some(false),
                         prepare_support: Some(false),
@@ -293,6 +333,18 @@ impl Client {
                     work_done_progress: Some(true),
                     ..Default::default()
                 }),
+                workspace: Some(lsp::WorkspaceClientCapabilities {
+                    configuration: Some(true),
+                    did_change_configuration: Some(lsp::DynamicRegistrationClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
+                    file_operations: Some(lsp::WorkspaceFileOperationsClientCapabilities {
+                        will_rename: Some(true),
+                        did_rename: Some(true),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
                 ..Default::default()
             },
             trace: None,
@@ -331,6 +383,14 @@ impl Client {
     // Text document
     // -------------------------------------------------------------------------------------------
 
+    /// Replace the settings of the server without restarting it.
+    pub fn did_change_configuration(&self, settings: Value) -> impl Future<Output = Result<()>> {
+        *self.settings.lock().unwrap() = Some(settings.clone());
+        self.notify::<lsp::notification::DidChangeConfiguration>(
+            lsp::DidChangeConfigurationParams { settings },
+        )
+    }
+
     pub fn text_document_did_open(
         &self,
         uri: lsp::Url,
@@ -605,6 +665,26 @@ impl Client {
         self.call::<lsp::request::HoverRequest>(params)
     }
 
+    pub fn text_document_document_highlight(
+        &self,
+        text_document: lsp::TextDocumentIdentifier,
+        position: lsp::Position,
+        work_done_token: Option<lsp::ProgressToken>,
+    ) -> impl Future<Output = Result<Value>> {
+        let params = lsp::DocumentHighlightParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document,
+                position,
+            },
+            work_done_progress_params: lsp::WorkDoneProgressParams { work_done_token },
+            partial_result_params: lsp::PartialResultParams {
+                partial_result_token: None,
+            },
+        };
+
+        self.call::<lsp::request::DocumentHighlightRequest>(params)
+    }
+
     // formatting
 
     pub fn text_document_formatting(
@@ -824,4 +904,31 @@ impl Client {
 
         self.call::<lsp::request::ExecuteCommand>(params)
     }
+
+    fn file_operations(&self) -> Option<&lsp::WorkspaceFileOperationsServerCapabilities> {
+        self.capabilities()
+            .workspace
+            .as_ref()?
+            .file_operations
+            .as_ref()
+    }
+
+    /// Asks for the edits to make before renaming files, like updating imports of them.
+    /// Returns `None` if the server isn't interested in renames. The server's filters aren't
+    /// checked, servers ignore the files they don't handle.
+    pub fn will_rename_files(
+        &self,
+        files: Vec<lsp::FileRename>,
+    ) -> Option<impl Future<Output = Result<Value>>> {
+        self.file_operations()?.will_rename.as_ref()?;
+        Some(self.call::<lsp::request::WillRenameFiles>(lsp::RenameFilesParams { files }))
+    }
+
+    pub fn did_rename_files(
+        &self,
+        files: Vec<lsp::FileRename>,
+    ) -> Option<impl Future<Output = Result<()>>> {
+        self.file_operations()?.did_rename.as_ref()?;
+        Some(self.notify::<lsp::notification::DidRenameFiles>(lsp::RenameFilesParams { files }))
+    }
 }
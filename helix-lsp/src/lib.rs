@@ -191,6 +191,7 @@ pub mod util {
 pub enum MethodCall {
     WorkDoneProgressCreate(lsp::WorkDoneProgressCreateParams),
     ApplyWorkspaceEdit(lsp::ApplyWorkspaceEditParams),
+    WorkspaceConfiguration(lsp::ConfigurationParams),
 }
 
 impl MethodCall {
@@ -209,6 +210,12 @@ impl MethodCall {
                     .expect("Failed to parse ApplyWorkspaceEdit params");
                 Self::ApplyWorkspaceEdit(params)
             }
+            lsp::request::WorkspaceConfiguration::METHOD => {
+                let params: lsp::ConfigurationParams = params
+                    .parse()
+                    .expect("Failed to parse WorkspaceConfiguration params");
+                Self::WorkspaceConfiguration(params)
+            }
             _ => {
                 log::warn!("unhandled lsp request: {}", method);
                 return None;
@@ -313,6 +320,7 @@ impl Registry {
                     &config.command,
                     &config.args,
                     language_config.config.clone(),
+                    language_config.settings.clone(),
                     language_config.roots.clone(),
                     id,
                 )?;
@@ -343,6 +351,12 @@ impl Registry {
                         .await
                         .unwrap();
 
+                    if let Some(settings) = _client.settings() {
+                        if let Err(e) = _client.did_change_configuration(settings).await {
+                            log::error!("failed to send language server settings: {}", e);
+                        }
+                    }
+
                     initialize_notify.notify_one();
                 });
 
@@ -1,10 +1,14 @@
-use helix_core::{merge_toml_values, syntax};
+use helix_core::{pos_at_coords, syntax, timings, Selection};
 use helix_lsp::{lsp, util::lsp_pos_to_pos, LspProgressMap};
 use helix_view::{theme, Editor};
 use serde_json::json;
 
 use crate::{
-    args::Args, commands::apply_workspace_edit, compositor::Compositor, config::Config, job::Jobs,
+    args::Args,
+    commands::{align_view, apply_workspace_edit, Align},
+    compositor::Compositor,
+    config::{Config, ConfigError},
+    job::Jobs,
     ui,
 };
 
@@ -31,6 +35,9 @@ use {
 #[cfg(windows)]
 type Signals = futures_util::stream::Empty<()>;
 
+/// How often the statusline spinner is redrawn while jobs are running.
+const JOB_SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+
 pub struct Application {
     compositor: Compositor,
     editor: Editor,
@@ -54,7 +61,11 @@ pub struct Application {
 }
 
 impl Application {
-    pub fn new(args: Args, mut config: Config) -> Result<Self, Error> {
+    pub fn new(
+        args: Args,
+        mut config: Config,
+        config_error: Option<ConfigError>,
+    ) -> Result<Self, Error> {
         use helix_view::editor::Action;
         let mut compositor = Compositor::new()?;
         let size = compositor.size();
@@ -64,55 +75,41 @@ impl Application {
         let theme_loader =
             std::sync::Arc::new(theme::Loader::new(&conf_dir, &helix_core::runtime_dir()));
 
-        // load default and user config, and merge both
-        let builtin_err_msg =
-            "Could not parse built-in languages.toml, something must be very wrong";
-        let def_lang_conf: toml::Value =
-            toml::from_slice(include_bytes!("../../languages.toml")).expect(builtin_err_msg);
-        let def_syn_loader_conf: helix_core::syntax::Configuration =
-            def_lang_conf.clone().try_into().expect(builtin_err_msg);
-        let user_lang_conf = std::fs::read(conf_dir.join("languages.toml"))
-            .ok()
-            .map(|raw| toml::from_slice(&raw));
-        let lang_conf = match user_lang_conf {
-            Some(Ok(value)) => Ok(merge_toml_values(def_lang_conf, value)),
-            Some(err @ Err(_)) => err,
-            None => Ok(def_lang_conf),
-        };
-
         let true_color = config.editor.true_color || crate::true_color();
-        let theme = config
-            .theme
-            .as_ref()
-            .and_then(|theme| {
-                theme_loader
-                    .load(theme)
-                    .map_err(|e| {
-                        log::warn!("failed to load theme `{}` - {}", theme, e);
-                        e
-                    })
-                    .ok()
-                    .filter(|theme| (true_color || theme.is_16_color()))
-            })
-            .unwrap_or_else(|| {
-                if true_color {
-                    theme_loader.default()
-                } else {
-                    theme_loader.base16_default()
-                }
-            });
-
-        let syn_loader_conf: helix_core::syntax::Configuration = lang_conf
-            .and_then(|conf| conf.try_into())
-            .unwrap_or_else(|err| {
-                eprintln!("Bad language config: {}", err);
-                eprintln!("Press <ENTER> to continue with default language config");
-                use std::io::Read;
-                // This waits for an enter press.
-                let _ = std::io::stdin().read(&mut []);
-                def_syn_loader_conf
-            });
-        let syn_loader = std::sync::Arc::new(syntax::Loader::new(syn_loader_conf));
+        let theme = timings::time("theme load", || {
+            config
+                .theme
+                .as_ref()
+                .and_then(|theme| {
+                    theme_loader
+                        .load(theme)
+                        .map_err(|e| {
+                            log::warn!("failed to load theme `{}` - {}", theme, e);
+                            e
+                        })
+                        .ok()
+                        .filter(|theme| true_color || theme.is_16_color())
+                })
+                .unwrap_or_else(|| {
+                    if true_color {
+                        theme_loader.default()
+                    } else {
+                        theme_loader.base16_default()
+                    }
+                })
+        });
+
+        let mut config_errors: Vec<_> = config_error.into_iter().collect();
+        let syn_loader_conf = timings::time("languages.toml parse", || {
+            crate::config::load_lang_config(&conf_dir.join("languages.toml"))
+        })
+        .unwrap_or_else(|err| {
+            config_errors.push(err);
+            crate::config::default_lang_config()
+        });
+        let syn_loader = std::sync::Arc::new(timings::time("language loader", || {
+            syntax::Loader::new(syn_loader_conf)
+        }));
 
         let mut editor = Editor::new(
             size,
@@ -130,7 +127,7 @@ impl Application {
             // Unset path to prevent accidentally saving to the original tutor file.
             doc_mut!(editor).set_path(None)?;
         } else if !args.files.is_empty() {
-            let first = &args.files[0]; // we know it's not empty
+            let (first, _) = &args.files[0]; // we know it's not empty
             if first.is_dir() {
                 std::env::set_current_dir(&first)?;
                 editor.new_file(Action::VerticalSplit);
@@ -138,13 +135,25 @@ impl Application {
             } else {
                 let nr_of_files = args.files.len();
                 editor.open(first.to_path_buf(), Action::VerticalSplit)?;
-                for file in args.files {
+                for (file, position) in args.files {
                     if file.is_dir() {
                         return Err(anyhow::anyhow!(
                             "expected a path to file, found a directory. (to open a directory pass it as first argument)"
                         ));
-                    } else {
-                        editor.open(file.to_path_buf(), Action::Load)?;
+                    }
+                    let doc_id = editor.open(file, Action::Load)?;
+                    if let Some(position) = position {
+                        // loaded documents get a selection for the focused view, but only the
+                        // first one is shown in it. The others are centered on the cursor once
+                        // they're switched to.
+                        let view_id = editor.tree.focus;
+                        let doc = editor.documents.get_mut(&doc_id).unwrap();
+                        let pos = pos_at_coords(doc.text().slice(..), position, true);
+                        doc.set_selection(view_id, Selection::point(pos));
+                        let view = editor.tree.get_mut(view_id);
+                        if view.doc == doc_id {
+                            align_view(doc, view, Align::Center);
+                        }
                     }
                 }
                 editor.set_status(format!("Loaded {} files.", nr_of_files));
@@ -164,6 +173,10 @@ impl Application {
 
         editor.set_theme(theme);
 
+        if let Some(err) = config_errors.first() {
+            editor.set_error(format!("{} (see :goto-config-error)", err));
+        }
+
         #[cfg(windows)]
         let signals = futures_util::stream::empty();
         #[cfg(not(windows))]
@@ -203,10 +216,20 @@ impl Application {
     pub async fn event_loop(&mut self) {
         let mut reader = EventStream::new();
         let mut last_render = Instant::now();
-        let deadline = Duration::from_secs(1) / 60;
 
         self.render();
 
+        if let Some(elapsed) = timings::since_start() {
+            timings::record("first frame", elapsed);
+        }
+        if let (Some(elapsed), None) = (timings::since_start(), &self.editor.status_msg) {
+            self.editor.set_status(format!(
+                "Started in {:.2}ms, see :timings",
+                elapsed.as_secs_f64() * 1000.0
+            ));
+            self.render();
+        }
+
         loop {
             if self.editor.should_close() {
                 self.jobs.finish();
@@ -215,6 +238,11 @@ impl Application {
 
             use futures_util::StreamExt;
 
+            let frame_interval = match self.editor.config.max_fps {
+                0 => Duration::ZERO,
+                fps => Duration::from_secs(1) / fps as u32,
+            };
+
             tokio::select! {
                 biased;
 
@@ -228,7 +256,7 @@ impl Application {
                     self.handle_language_server_message(call, id).await;
                     // limit render calls for fast language server messages
                     let last = self.editor.language_servers.incoming.is_empty();
-                    if last || last_render.elapsed() > deadline {
+                    if last || last_render.elapsed() > frame_interval {
                         self.render();
                         last_render = Instant::now();
                     }
@@ -241,11 +269,24 @@ impl Application {
                     self.jobs.handle_callback(&mut self.editor, &mut self.compositor, callback);
                     self.render();
                 }
+                // animate the statusline spinner of running jobs
+                _ = tokio::time::sleep(JOB_SPINNER_INTERVAL.max(frame_interval)), if !self.editor.tasks.is_empty() => {
+                    self.render();
+                }
                 _ = &mut self.editor.idle_timer => {
                     // idle timeout
                     self.editor.clear_idle_timer();
                     self.handle_idle_timeout();
                 }
+                _ = &mut self.editor.bell_timer => {
+                    // stop flashing the visual bell
+                    self.editor.clear_bell();
+                    self.render();
+                }
+                _ = &mut self.editor.sequence_timer => {
+                    self.editor.clear_sequence_timer();
+                    self.handle_sequence_timeout();
+                }
             }
         }
     }
@@ -276,20 +317,23 @@ impl Application {
     }
 
     pub fn handle_idle_timeout(&mut self) {
-        use crate::commands::{insert::idle_completion, Context};
+        use crate::commands::{
+            detect_deleted_files, document_highlight, insert::idle_completion, Context,
+        };
         use helix_view::document::Mode;
 
-        if doc!(self.editor).mode != Mode::Insert || !self.config.editor.auto_completion {
+        if self.compositor.has_modal() {
             return;
         }
+
         let editor_view = self
             .compositor
             .find::<ui::EditorView>()
             .expect("expected at least one EditorView");
 
-        if editor_view.completion.is_some() {
-            return;
-        }
+        let should_complete = doc!(self.editor).mode == Mode::Insert
+            && self.editor.config.auto_completion
+            && editor_view.completion.is_none();
 
         let mut cx = Context {
             register: None,
@@ -299,7 +343,32 @@ impl Application {
             callback: None,
             on_next_key_callback: None,
         };
-        idle_completion(&mut cx);
+
+        if should_complete {
+            idle_completion(&mut cx);
+        }
+        if cx.editor.config.document_highlight {
+            document_highlight(&mut cx);
+        }
+        detect_deleted_files(&mut cx);
+        self.render();
+    }
+
+    pub fn handle_sequence_timeout(&mut self) {
+        let editor_view = self
+            .compositor
+            .find::<ui::EditorView>()
+            .expect("expected at least one EditorView");
+
+        let mut cx = crate::commands::Context {
+            register: None,
+            editor: &mut self.editor,
+            jobs: &mut self.jobs,
+            count: None,
+            callback: None,
+            on_next_key_callback: None,
+        };
+        editor_view.cancel_pending_keys(&mut cx);
         self.render();
     }
 
@@ -536,7 +605,7 @@ impl Application {
                         }
 
                         if self.config.lsp.display_messages {
-                            self.editor.set_status(status);
+                            self.editor.set_transient_status(status);
                         }
                     }
                     Notification::ProgressMessage(_params) => {
@@ -612,6 +681,19 @@ impl Application {
                             })),
                         ));
                     }
+                    MethodCall::WorkspaceConfiguration(params) => {
+                        let language_server =
+                            match self.editor.language_servers.get_by_id(server_id) {
+                                Some(language_server) => language_server,
+                                None => {
+                                    warn!("can't find language server with id `{}`", server_id);
+                                    return;
+                                }
+                            };
+
+                        let result = language_server.configuration(&params);
+                        tokio::spawn(language_server.reply(id, Ok(json!(result))));
+                    }
                 }
             }
             e => unreachable!("{:?}", e),
@@ -1,4 +1,5 @@
 use anyhow::{Error, Result};
+use helix_core::Position;
 use std::path::PathBuf;
 
 #[derive(Default)]
@@ -6,24 +7,40 @@ pub struct Args {
     pub display_help: bool,
     pub display_version: bool,
     pub load_tutor: bool,
+    pub portable: bool,
+    pub timings: bool,
     pub verbosity: u64,
-    pub files: Vec<PathBuf>,
+    /// Config file to load instead of `config.toml` in the config directory.
+    pub config_file: Option<PathBuf>,
+    /// Files to open, with the position to put the cursor at given by a `+line:col` argument
+    /// before them.
+    pub files: Vec<(PathBuf, Option<Position>)>,
 }
 
 impl Args {
     pub fn parse_args() -> Result<Args> {
-        let mut args = Args::default();
         let argv: Vec<String> = std::env::args().collect();
-        let mut iter = argv.iter();
+        // skip the program, we don't care about that
+        Self::parse(argv.iter().skip(1).map(String::as_str))
+    }
 
-        iter.next(); // skip the program, we don't care about that
+    fn parse<'a>(argv: impl IntoIterator<Item = &'a str>) -> Result<Args> {
+        let mut args = Args::default();
+        let mut iter = argv.into_iter();
+        let mut position = None;
 
-        for arg in &mut iter {
-            match arg.as_str() {
+        while let Some(arg) = iter.next() {
+            match arg {
                 "--" => break, // stop parsing at this point treat the remaining as files
                 "--version" => args.display_version = true,
                 "--help" => args.display_help = true,
                 "--tutor" => args.load_tutor = true,
+                "--portable" => args.portable = true,
+                "--timings" => args.timings = true,
+                "-c" | "--config" => match iter.next() {
+                    Some(path) => args.config_file = Some(PathBuf::from(path)),
+                    None => return Err(Error::msg(format!("{} expects a path", arg))),
+                },
                 arg if arg.starts_with("--") => {
                     return Err(Error::msg(format!(
                         "unexpected double dash argument: {}",
@@ -41,15 +58,58 @@ impl Args {
                         }
                     }
                 }
-                arg => args.files.push(PathBuf::from(arg)),
+                arg if arg.starts_with('+') => position = Some(parse_position(&arg[1..])?),
+                arg => args.files.push((PathBuf::from(arg), position.take())),
             }
         }
 
         // push the remaining args, if any to the files
         for filename in iter {
-            args.files.push(PathBuf::from(filename));
+            args.files.push((PathBuf::from(filename), position.take()));
         }
 
         Ok(args)
     }
 }
+
+/// Parses a 1-indexed `line` or `line:col` into a 0-indexed position.
+fn parse_position(s: &str) -> Result<Position> {
+    let parse = |n: &str| {
+        n.parse::<usize>()
+            .map(|n| n.saturating_sub(1))
+            .map_err(|_| Error::msg(format!("invalid position +{}, expected +line:col", s)))
+    };
+    match s.split_once(':') {
+        Some((line, col)) => Ok(Position::new(parse(line)?, parse(col)?)),
+        None => Ok(Position::new(parse(s)?, 0)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_files() {
+        let args = Args::parse(["-v", "+3:5", "a.rs", "b.rs", "--", "+2"]).unwrap();
+        assert_eq!(args.verbosity, 1);
+        assert_eq!(
+            args.files,
+            vec![
+                (PathBuf::from("a.rs"), Some(Position::new(2, 4))),
+                (PathBuf::from("b.rs"), None),
+                (PathBuf::from("+2"), None),
+            ]
+        );
+
+        let args = Args::parse(["--config", "other.toml", "+10", "a.rs"]).unwrap();
+        assert_eq!(args.config_file, Some(PathBuf::from("other.toml")));
+        assert_eq!(
+            args.files,
+            vec![(PathBuf::from("a.rs"), Some(Position::new(9, 0)))]
+        );
+
+        assert!(Args::parse(["+x", "a.rs"]).is_err());
+        assert!(Args::parse(["-c"]).is_err());
+    }
+}
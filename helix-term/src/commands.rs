@@ -1,5 +1,5 @@
 use helix_core::{
-    comment, coords_at_pos, find_first_non_whitespace_char, find_root, graphemes,
+    comment, coords_at_pos, emmet, escape, find_first_non_whitespace_char, find_root, graphemes,
     history::UndoKind,
     increment::date_time::DateTimeIncrementor,
     increment::{number::NumberIncrementor, Increment},
@@ -10,7 +10,12 @@ use helix_core::{
     movement::{self, Direction},
     object, pos_at_coords,
     regex::{self, Regex, RegexBuilder},
-    search, selection, shellwords, surround, textobject,
+    register::YankEntry,
+    replace,
+    search::{self, Normalization, NormalizedText},
+    selection, shellwords,
+    snippets::Snippet,
+    surround, tag, textobject, todo,
     unicode::width::UnicodeWidthChar,
     LineEnding, Position, Range, Rope, RopeGraphemes, RopeSlice, Selection, SmallVec, Tendril,
     Transaction,
@@ -18,9 +23,9 @@ use helix_core::{
 use helix_view::{
     clipboard::ClipboardType,
     document::{Mode, SCRATCH_BUFFER_NAME},
-    editor::{Action, Motion},
+    editor::{Action, ClosedDocument, FilePickerConfig, Motion, Severity},
     input::KeyEvent,
-    keyboard::KeyCode,
+    keyboard::{KeyCode, KeyModifiers},
     view::View,
     Document, DocumentId, Editor, ViewId,
 };
@@ -42,7 +47,10 @@ use crate::{
 
 use crate::job::{self, Job, Jobs};
 use futures_util::{FutureExt, StreamExt};
-use std::{collections::HashSet, num::NonZeroUsize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    num::NonZeroUsize,
+};
 use std::{fmt, future::Future};
 
 use std::{
@@ -112,13 +120,13 @@ impl<'a> Context<'a> {
     }
 }
 
-enum Align {
+pub(crate) enum Align {
     Top,
     Center,
     Bottom,
 }
 
-fn align_view(doc: &Document, view: &mut View, align: Align) {
+pub(crate) fn align_view(doc: &Document, view: &mut View, align: Align) {
     let pos = doc
         .selection(view.id)
         .primary()
@@ -134,6 +142,10 @@ fn align_view(doc: &Document, view: &mut View, align: Align) {
     };
 
     view.offset.row = line.saturating_sub(relative);
+    if view.soft_wrap {
+        // wrapped lines above the cursor can push it off screen
+        view.ensure_cursor_in_view(doc, 0);
+    }
 }
 
 /// A MappableCommand is either a static command like "jump_view_up" or a Typable command like
@@ -215,6 +227,10 @@ impl MappableCommand {
         extend_char_right, "Extend right",
         extend_line_up, "Extend up",
         extend_line_down, "Extend down",
+        move_visual_line_up, "Move up a row of a wrapped line",
+        move_visual_line_down, "Move down a row of a wrapped line",
+        extend_visual_line_up, "Extend up a row of a wrapped line",
+        extend_visual_line_down, "Extend down a row of a wrapped line",
         copy_selection_on_next_line, "Copy selection on next line",
         copy_selection_on_prev_line, "Copy selection on previous line",
         move_next_word_start, "Move to beginning of next word",
@@ -274,9 +290,16 @@ impl MappableCommand {
         file_picker, "Open file picker",
         code_action, "Perform code action",
         buffer_picker, "Open buffer picker",
+        closed_buffer_picker, "Open picker for recently closed buffers",
+        toggle_notes, "Toggle the notes of the workspace in a side split",
+        todo_picker, "Open picker of TODO, FIXME and HACK comments in the workspace",
+        yank_history_picker, "Open picker of recent yanks and deletes to paste one",
         symbol_picker, "Open symbol picker",
         workspace_symbol_picker, "Open workspace symbol picker",
         last_picker, "Open last picker",
+        job_picker, "Open picker of running jobs, selecting one cancels it",
+        cancel_jobs, "Cancel all running jobs",
+        bind_key, "Bind a key in normal mode to a command and save it to the config",
         prepend_to_line, "Insert at start of line",
         append_to_line, "Insert at end of line",
         open_below, "Open new line below selection",
@@ -308,14 +331,19 @@ impl MappableCommand {
         goto_next_diag, "Goto next diagnostic",
         goto_prev_diag, "Goto previous diagnostic",
         goto_line_start, "Goto line start",
+        goto_line_start_smart, "Goto first non-blank in line, or line start if already there",
         goto_line_end, "Goto line end",
         goto_next_buffer, "Goto next buffer",
         goto_previous_buffer, "Goto previous buffer",
+        goto_next_view_buffer, "Goto next buffer shown in this window",
+        goto_previous_view_buffer, "Goto previous buffer shown in this window",
+        toggle_pin, "Pin or unpin the document to the window",
         // TODO: different description ?
         goto_line_end_newline, "Goto line end",
         goto_first_nonwhitespace, "Goto first non-blank in line",
         trim_selections, "Trim whitespace from selections",
         extend_to_line_start, "Extend to line start",
+        extend_to_line_start_smart, "Extend to first non-blank in line, or line start if already there",
         extend_to_line_end, "Extend to line end",
         extend_to_line_end_newline, "Extend to line end",
         signature_help, "Show signature help",
@@ -341,6 +369,8 @@ impl MappableCommand {
         replace_selections_with_primary_clipboard, "Replace selections by primary clipboard content",
         paste_after, "Paste after selection",
         paste_before, "Paste before selection",
+        paste_after_reindent, "Paste after selection, reindenting whole lines",
+        paste_before_reindent, "Paste before selection, reindenting whole lines",
         paste_clipboard_after, "Paste clipboard after selections",
         paste_clipboard_before, "Paste clipboard before selections",
         paste_primary_clipboard_after, "Paste primary clipboard after selections",
@@ -371,6 +401,7 @@ impl MappableCommand {
         jump_view_up, "Jump to the split above",
         jump_view_down, "Jump to the split below",
         rotate_view, "Goto next window",
+        rotate_view_reverse, "Goto previous window",
         hsplit, "Horizontal bottom split",
         vsplit, "Vertical right split",
         wclose, "Close window",
@@ -400,6 +431,12 @@ impl MappableCommand {
         decrement, "Decrement",
         record_macro, "Record macro",
         replay_macro, "Replay macro",
+        pick_color, "Edit color literal under cursor with a color picker",
+        expand_abbreviation, "Expand Emmet abbreviation before cursor",
+        select_tag_names, "Select names of opening and closing tag",
+        sticky_ctrl, "Apply Ctrl to the next key",
+        sticky_alt, "Apply Alt to the next key",
+        sticky_shift, "Apply Shift to the next key",
     );
 }
 
@@ -498,7 +535,7 @@ where
     doc.set_selection(view.id, selection);
 }
 
-use helix_core::movement::{move_horizontally, move_vertically};
+use helix_core::movement::{move_horizontally, move_vertically, move_vertically_visual};
 
 fn move_char_left(cx: &mut Context) {
     move_impl(cx, move_horizontally, Direction::Backward, Movement::Move)
@@ -532,6 +569,40 @@ fn extend_line_down(cx: &mut Context) {
     move_impl(cx, move_vertically, Direction::Forward, Movement::Extend)
 }
 
+/// Moves by the rows lines are wrapped on with soft-wrap, and by lines otherwise.
+fn move_visual_line_impl(cx: &mut Context, dir: Direction, behaviour: Movement) {
+    let count = cx.count();
+    let (view, doc) = current!(cx.editor);
+    let text = doc.text().slice(..);
+    let wrap_width = if view.soft_wrap {
+        view.wrap_width()
+    } else {
+        usize::MAX
+    };
+    let tab_width = doc.tab_width();
+
+    let selection = doc.selection(view.id).clone().transform(|range| {
+        move_vertically_visual(text, range, dir, count, behaviour, wrap_width, tab_width)
+    });
+    doc.set_selection(view.id, selection);
+}
+
+fn move_visual_line_up(cx: &mut Context) {
+    move_visual_line_impl(cx, Direction::Backward, Movement::Move)
+}
+
+fn move_visual_line_down(cx: &mut Context) {
+    move_visual_line_impl(cx, Direction::Forward, Movement::Move)
+}
+
+fn extend_visual_line_up(cx: &mut Context) {
+    move_visual_line_impl(cx, Direction::Backward, Movement::Extend)
+}
+
+fn extend_visual_line_down(cx: &mut Context) {
+    move_visual_line_impl(cx, Direction::Forward, Movement::Extend)
+}
+
 fn goto_line_end_impl(view: &mut View, doc: &mut Document, movement: Movement) {
     let text = doc.text().slice(..);
 
@@ -621,6 +692,41 @@ fn goto_line_start(cx: &mut Context) {
     )
 }
 
+/// Moves to the first non-whitespace character of the line, or to the start of the line if
+/// already there, like Home in most editors.
+fn goto_line_start_smart_impl(view: &mut View, doc: &mut Document, movement: Movement) {
+    let text = doc.text().slice(..);
+
+    let selection = doc.selection(view.id).clone().transform(|range| {
+        let line = range.cursor_line(text);
+        let line_start = text.line_to_char(line);
+        let pos = match find_first_non_whitespace_char(text.line(line)) {
+            Some(first) if range.cursor(text) != line_start + first => line_start + first,
+            _ => line_start,
+        };
+        range.put_cursor(text, pos, movement == Movement::Extend)
+    });
+    doc.set_selection(view.id, selection);
+}
+
+fn goto_line_start_smart(cx: &mut Context) {
+    let (view, doc) = current!(cx.editor);
+    goto_line_start_smart_impl(
+        view,
+        doc,
+        if doc.mode == Mode::Select {
+            Movement::Extend
+        } else {
+            Movement::Move
+        },
+    )
+}
+
+fn extend_to_line_start_smart(cx: &mut Context) {
+    let (view, doc) = current!(cx.editor);
+    goto_line_start_smart_impl(view, doc, Movement::Extend)
+}
+
 fn goto_next_buffer(cx: &mut Context) {
     goto_buffer(cx, Direction::Forward);
 }
@@ -654,6 +760,41 @@ fn goto_buffer(cx: &mut Context, direction: Direction) {
     cx.editor.switch(id, Action::Replace);
 }
 
+fn goto_next_view_buffer(cx: &mut Context) {
+    goto_view_buffer(cx, Direction::Forward);
+}
+
+fn goto_previous_view_buffer(cx: &mut Context) {
+    goto_view_buffer(cx, Direction::Backward);
+}
+
+/// Like `goto_buffer` but only cycles through the documents shown in the
+/// current view.
+fn goto_view_buffer(cx: &mut Context, direction: Direction) {
+    let view = view!(cx.editor);
+    let docs: Vec<_> = view
+        .docs
+        .iter()
+        .copied()
+        .filter(|id| cx.editor.documents.contains_key(id))
+        .collect();
+    let current = docs.iter().position(|&id| id == view.doc).unwrap_or(0);
+    let id = match direction {
+        Direction::Forward => docs[(current + 1) % docs.len()],
+        Direction::Backward => docs[(current + docs.len() - 1) % docs.len()],
+    };
+
+    cx.editor.switch_in_view(id);
+}
+
+fn toggle_pin(cx: &mut Context) {
+    let view = view_mut!(cx.editor);
+    view.pinned = !view.pinned;
+    let status = if view.pinned { "Pinned" } else { "Unpinned" };
+    cx.editor
+        .set_status(format!("{} document to the view", status));
+}
+
 fn extend_to_line_start(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
     goto_line_start_impl(view, doc, Movement::Extend)
@@ -1302,16 +1443,47 @@ pub fn scroll(cx: &mut Context, offset: usize, direction: Direction) {
     }
 }
 
+/// Scrolls a page and moves the primary cursor as many lines, so it stays on the same row of
+/// the view. Near the start or end of the document the cursor moves to the first or last line.
+fn page(cx: &mut Context, direction: Direction) {
+    let (view, doc) = current!(cx.editor);
+    let height = view.inner_area().height as usize;
+    let text = doc.text().slice(..);
+    let wrap_width = if view.soft_wrap {
+        view.wrap_width()
+    } else {
+        usize::MAX
+    };
+    let tab_width = doc.tab_width();
+    let behaviour = if doc.mode == Mode::Select {
+        Movement::Extend
+    } else {
+        Movement::Move
+    };
+    let move_rows = |range, behaviour| {
+        move_vertically_visual(
+            text, range, direction, height, behaviour, wrap_width, tab_width,
+        )
+    };
+
+    // the view scrolls by as many rows as the cursors move, so they stay where they were on
+    // screen. Rows of wrapped lines count on their own.
+    let top = Range::point(text.line_to_char(view.offset.row));
+    view.offset.row = text.char_to_line(move_rows(top, Movement::Move).head);
+
+    let selection = doc
+        .selection(view.id)
+        .clone()
+        .transform(|range| move_rows(range, behaviour));
+    doc.set_selection(view.id, selection);
+}
+
 fn page_up(cx: &mut Context) {
-    let view = view!(cx.editor);
-    let offset = view.inner_area().height as usize;
-    scroll(cx, offset, Direction::Backward);
+    page(cx, Direction::Backward);
 }
 
 fn page_down(cx: &mut Context) {
-    let view = view!(cx.editor);
-    let offset = view.inner_area().height as usize;
-    scroll(cx, offset, Direction::Forward);
+    page(cx, Direction::Forward);
 }
 
 fn half_page_up(cx: &mut Context) {
@@ -1460,27 +1632,29 @@ fn split_selection_on_newline(cx: &mut Context) {
 fn search_impl(
     doc: &mut Document,
     view: &mut View,
-    contents: &str,
+    contents: &NormalizedText,
     regex: &Regex,
     movement: Movement,
     direction: Direction,
     scrolloff: usize,
-) {
+) -> bool {
     let text = doc.text().slice(..);
     let selection = doc.selection(view.id);
 
     // Get the right side of the primary block cursor for forward search, or the
     //grapheme before the start of the selection for reverse search.
     let start = match direction {
-        Direction::Forward => text.char_to_byte(graphemes::next_grapheme_boundary(
+        Direction::Forward => contents.char_to_byte(
             text,
-            selection.primary().to(),
-        )),
-        Direction::Backward => text.char_to_byte(graphemes::prev_grapheme_boundary(
+            graphemes::next_grapheme_boundary(text, selection.primary().to()),
+        ),
+        Direction::Backward => contents.char_to_byte(
             text,
-            selection.primary().from(),
-        )),
+            graphemes::prev_grapheme_boundary(text, selection.primary().from()),
+        ),
     };
+    let normalized = contents;
+    let contents = contents.as_str();
 
     //A regex::Match returns byte-positions in the str. In the case where we
     //do a reverse search and wraparound to the end, we don't need to search
@@ -1501,12 +1675,12 @@ fn search_impl(
     };
     // TODO: message on wraparound
     if let Some(mat) = mat {
-        let start = text.byte_to_char(mat.start() + offset);
-        let end = text.byte_to_char(mat.end() + offset);
+        let std::ops::Range { start, end } =
+            normalized.char_range(text, mat.start() + offset..mat.end() + offset);
 
         if end == 0 {
             // skip empty matches that don't make sense
-            return;
+            return false;
         }
 
         // Determine range direction based on the primary range
@@ -1528,7 +1702,24 @@ fn search_impl(
         } else {
             align_view(doc, view, Align::Center)
         }
-    };
+        true
+    } else {
+        false
+    }
+}
+
+/// Builds the regex for a search query, normalized like the text it's matched against.
+/// With smart case the search is case insensitive unless the query contains uppercase characters.
+fn search_regex(
+    query: &str,
+    smart_case: bool,
+    normalization: Normalization,
+) -> Result<Regex, regex::Error> {
+    let query = normalization.normalize(query);
+    let case_insensitive = smart_case && !query.chars().any(char::is_uppercase);
+    RegexBuilder::new(&query)
+        .case_insensitive(case_insensitive)
+        .build()
 }
 
 fn search_completions(cx: &mut Context, reg: Option<char>) -> Vec<String> {
@@ -1553,18 +1744,26 @@ fn searcher(cx: &mut Context, direction: Direction) {
     let reg = cx.register.unwrap_or('/');
     let scrolloff = cx.editor.config.scrolloff;
 
+    let smart_case = cx.editor.config.smart_case;
+    let normalization = cx.editor.config.search_normalization;
+    let persist_highlight = cx.editor.config.persist_search_highlight;
     let doc = doc!(cx.editor);
 
     // TODO: could probably share with select_on_matches?
 
     // HAXX: sadly we can't avoid allocating a single string for the whole buffer since we can't
     // feed chunks into the regex yet
-    let contents = doc.text().slice(..).to_string();
+    let contents = NormalizedText::new(doc.text().slice(..), normalization);
     let completions = search_completions(cx, Some(reg));
 
+    let prompt = match normalization {
+        Normalization::None => "search:",
+        Normalization::Canonical => "search (canonical):",
+        Normalization::FoldDiacritics => "search (fold diacritics):",
+    };
     let prompt = ui::regex_prompt(
         cx,
-        "search:".into(),
+        prompt.into(),
         Some(reg),
         move |input: &str| {
             completions
@@ -1574,9 +1773,23 @@ fn searcher(cx: &mut Context, direction: Direction) {
                 .collect()
         },
         move |view, doc, regex, event| {
+            if event == PromptEvent::Validate && persist_highlight {
+                // the prompt's regex isn't normalized or smart cased yet
+                if let Ok(regex) = search_regex(regex.as_str(), smart_case, normalization) {
+                    view.search_highlight = Some((regex, normalization));
+                }
+            }
             if event != PromptEvent::Update {
                 return;
             }
+            let regex = match normalization {
+                Normalization::None => regex,
+                _ => match search_regex(regex.as_str(), smart_case, normalization) {
+                    Ok(regex) => regex,
+                    Err(_) => return,
+                },
+            };
+            view.search_highlight = Some((regex.clone(), normalization));
             search_impl(
                 doc,
                 view,
@@ -1594,21 +1807,20 @@ fn searcher(cx: &mut Context, direction: Direction) {
 
 fn search_next_or_prev_impl(cx: &mut Context, movement: Movement, direction: Direction) {
     let scrolloff = cx.editor.config.scrolloff;
+    let persist_highlight = cx.editor.config.persist_search_highlight;
     let (view, doc) = current!(cx.editor);
     let registers = &cx.editor.registers;
     if let Some(query) = registers.read('/') {
         let query = query.last().unwrap();
-        let contents = doc.text().slice(..).to_string();
-        let case_insensitive = if cx.editor.config.smart_case {
-            !query.chars().any(char::is_uppercase)
-        } else {
-            false
-        };
-        if let Ok(regex) = RegexBuilder::new(query)
-            .case_insensitive(case_insensitive)
-            .build()
-        {
-            search_impl(doc, view, &contents, &regex, movement, direction, scrolloff);
+        let normalization = cx.editor.config.search_normalization;
+        let contents = NormalizedText::new(doc.text().slice(..), normalization);
+        if let Ok(regex) = search_regex(query, cx.editor.config.smart_case, normalization) {
+            if persist_highlight {
+                view.search_highlight = Some((regex.clone(), normalization));
+            }
+            if !search_impl(doc, view, &contents, &regex, movement, direction, scrolloff) {
+                cx.editor.ring_bell(Severity::Warning);
+            }
         } else {
             // get around warning `mutable_borrow_reservation_conflict`
             // which will be a hard error in the future
@@ -1639,6 +1851,12 @@ fn search_selection(cx: &mut Context) {
     let contents = doc.text().slice(..);
     let query = doc.selection(view.id).primary().fragment(contents);
     let regex = regex::escape(&query);
+    if cx.editor.config.persist_search_highlight {
+        let normalization = cx.editor.config.search_normalization;
+        if let Ok(regex) = search_regex(&regex, cx.editor.config.smart_case, normalization) {
+            view.search_highlight = Some((regex, normalization));
+        }
+    }
     cx.editor.registers.get_mut('/').push(regex);
     let msg = format!("register '{}' set to '{}'", '/', query);
     cx.editor.set_status(msg);
@@ -1675,22 +1893,20 @@ fn global_search(cx: &mut Context) {
                     .binary_detection(BinaryDetection::quit(b'\x00'))
                     .build();
 
-                let search_root = std::env::current_dir()
+                let walker = workspace_walker(&file_picker_config)
                     .expect("Global search error: Failed to get current dir");
-                WalkBuilder::new(search_root)
-                    .hidden(file_picker_config.hidden)
-                    .parents(file_picker_config.parents)
-                    .ignore(file_picker_config.ignore)
-                    .git_ignore(file_picker_config.git_ignore)
-                    .git_global(file_picker_config.git_global)
-                    .git_exclude(file_picker_config.git_exclude)
-                    .max_depth(file_picker_config.max_depth)
-                    .build_parallel()
-                    .run(|| {
+                let all_matches_sx = all_matches_sx.clone();
+                // walk off the main thread so large trees don't freeze the editor
+                tokio::task::spawn_blocking(move || {
+                    walker.build_parallel().run(|| {
                         let mut searcher_cl = searcher.clone();
                         let matcher_cl = matcher.clone();
                         let all_matches_sx_cl = all_matches_sx.clone();
                         Box::new(move |dent: Result<DirEntry, ignore::Error>| -> WalkState {
+                            // the search was cancelled
+                            if all_matches_sx_cl.is_closed() {
+                                return WalkState::Quit;
+                            }
                             let dent = match dent {
                                 Ok(dent) => dent,
                                 Err(_) => return WalkState::Continue,
@@ -1726,6 +1942,7 @@ fn global_search(cx: &mut Context) {
                             WalkState::Continue
                         })
                     });
+                });
             } else {
                 // Otherwise do nothing
                 // log::warn!("Global Search Invalid Pattern")
@@ -1788,7 +2005,8 @@ fn global_search(cx: &mut Context) {
             });
         Ok(call)
     };
-    cx.jobs.callback(show_picker);
+    cx.jobs
+        .add(Job::with_callback(show_picker).named(&cx.editor.tasks, "global-search"));
 }
 
 fn extend_line(cx: &mut Context) {
@@ -1845,9 +2063,7 @@ fn delete_selection_impl(cx: &mut Context, op: Operation) {
         // first yank the selection
         let values: Vec<String> = selection.fragments(text).map(Cow::into_owned).collect();
         let reg_name = cx.register.unwrap_or('"');
-        let registers = &mut cx.editor.registers;
-        let reg = registers.get_mut(reg_name);
-        reg.write(values);
+        cx.editor.registers.yank(reg_name, values);
     };
 
     // then delete
@@ -1998,12 +2214,17 @@ pub mod cmd {
         _args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
+        let view_id = view!(cx.editor).id;
         // last view and we have unsaved changes
         if cx.editor.tree.views().count() == 1 {
-            buffers_remaining_impl(cx.editor)?
+            let modified = modified_buffers(cx.editor);
+            if !modified.is_empty() {
+                confirm_quit(cx, modified);
+                return Ok(());
+            }
         }
 
-        cx.editor.close(view!(cx.editor).id);
+        cx.editor.close(view_id);
 
         Ok(())
     }
@@ -2037,7 +2258,13 @@ pub mod cmd {
     ) -> anyhow::Result<()> {
         let view = view!(cx.editor);
         let doc_id = view.doc;
-        cx.editor.close_document(doc_id, false)?;
+        if let Err(err) = cx.editor.close_document(doc_id, false) {
+            confirm(cx, format!("{}. Close anyway?", err), move |editor| {
+                if let Err(err) = editor.close_document(doc_id, true) {
+                    editor.set_error(err.to_string());
+                }
+            });
+        }
         Ok(())
     }
 
@@ -2053,8 +2280,21 @@ pub mod cmd {
     }
 
     fn write_impl(cx: &mut compositor::Context, path: Option<&Cow<str>>) -> anyhow::Result<()> {
+        let id = doc!(cx.editor).id();
+        write_document_impl(cx, id, path)
+    }
+
+    /// Writes the document `id`, which doesn't have to be the focused one.
+    pub(super) fn write_document_impl(
+        cx: &mut compositor::Context,
+        id: DocumentId,
+        path: Option<&Cow<str>>,
+    ) -> anyhow::Result<()> {
         let jobs = &mut cx.jobs;
-        let doc = doc_mut!(cx.editor);
+        let doc = match cx.editor.document_mut(id) {
+            Some(doc) => doc,
+            None => bail!("the buffer was closed"),
+        };
 
         if let Some(ref path) = path {
             doc.set_path(Some(path.as_ref().as_ref()))
@@ -2071,19 +2311,121 @@ pub mod cmd {
                 Modified::SetUnmodified,
                 shared.clone(),
             );
-            jobs.callback(callback);
+            // not named so it can't be cancelled, the save below awaits the same formatting
+            jobs.add(Job::with_callback(callback));
             shared
         });
+        let name = doc
+            .relative_path()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let saved_revision = doc.saved_revision();
         let future = doc.format_and_save(fmt);
-        cx.jobs.add(Job::new(future).wait_before_exiting());
+        let callback = async move {
+            let result = future.await;
+            let call: job::Callback = Box::new(
+                move |editor: &mut Editor, _compositor: &mut Compositor| match result {
+                    Ok(()) => {
+                        if let Some(doc) = editor.document_mut(id) {
+                            doc.set_written();
+                        }
+                        editor.set_status(format!("Wrote {}", name));
+                    }
+                    Err(err) => {
+                        // the buffer was marked as saved when the write started
+                        if let Some(doc) = editor.document_mut(id) {
+                            doc.set_saved_revision(saved_revision);
+                        }
+                        editor.set_error(format!("Failed to write {}: {}", name, err));
+                    }
+                },
+            );
+            Ok(call)
+        };
+        cx.jobs
+            .add(Job::with_callback(callback).wait_before_exiting());
 
         if path.is_some() {
-            let id = doc.id();
             let _ = cx.editor.refresh_language_server(id);
         }
         Ok(())
     }
 
+    /// Create a buffer from a template of the templates directory, showing a picker of the
+    /// templates if none is given. The buffer is given the path of the second argument, if any.
+    fn new_from_template(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let dir = templates_dir();
+        let name = match args.first() {
+            Some(name) => name,
+            None => {
+                let templates: Vec<PathBuf> = std::fs::read_dir(&dir)
+                    .with_context(|| format!("No templates in {}", dir.display()))?
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file())
+                    .collect();
+                ensure!(!templates.is_empty(), "No templates in {}", dir.display());
+
+                let callback = async move {
+                    let call: job::Callback =
+                        Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                            let picker = FilePicker::new(
+                                templates,
+                                |path: &PathBuf| {
+                                    path.file_name()
+                                        .map(|name| name.to_string_lossy().into_owned())
+                                        .unwrap_or_default()
+                                        .into()
+                                },
+                                |editor: &mut Editor, template, action| {
+                                    if let Err(err) =
+                                        create_from_template(editor, template, None, action)
+                                    {
+                                        editor.set_error(err.to_string());
+                                    }
+                                },
+                                |_editor, template| Some((template.clone(), None)),
+                            );
+                            compositor.push(Box::new(picker));
+                        });
+                    Ok(call)
+                };
+                cx.jobs.callback(callback);
+                return Ok(());
+            }
+        };
+        let path = args.get(1).map(|path| PathBuf::from(path.as_ref()));
+        create_from_template(
+            cx.editor,
+            &dir.join(name.as_ref()),
+            path.as_deref(),
+            Action::Replace,
+        )
+    }
+
+    fn buffer_reopen(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let closed = cx
+            .editor
+            .closed_documents
+            .back()
+            .cloned()
+            .ok_or_else(|| anyhow!("no closed buffers to reopen"))?;
+        // kept to try again if the file can't be opened
+        cx.editor.reopen(closed.clone(), Action::Replace)?;
+        cx.editor
+            .closed_documents
+            .retain(|other| other.path != closed.path);
+        Ok(())
+    }
+
     fn write(
         cx: &mut compositor::Context,
         args: &[Cow<str>],
@@ -2111,7 +2453,8 @@ pub mod cmd {
         if let Some(format) = doc.format() {
             let callback =
                 make_format_callback(doc.id(), doc.version(), Modified::LeaveModified, format);
-            cx.jobs.callback(callback);
+            cx.jobs
+                .add(Job::with_callback(callback).named(&cx.editor.tasks, "format"));
         }
 
         Ok(())
@@ -2248,10 +2591,54 @@ pub mod cmd {
         force_quit(cx, &[], event)
     }
 
-    /// Results an error if there are modified buffers remaining and sets editor error,
-    /// otherwise returns `Ok(())`
-    pub(super) fn buffers_remaining_impl(editor: &mut Editor) -> anyhow::Result<()> {
-        let modified: Vec<_> = editor
+    /// Asks for confirmation in a popup before running `confirm_fn`.
+    fn confirm(
+        cx: &mut compositor::Context,
+        message: String,
+        confirm_fn: impl FnOnce(&mut Editor) + Send + 'static,
+    ) {
+        let callback = async move {
+            let call: job::Callback =
+                Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    let confirm = ui::Confirm::new(message, confirm_fn);
+                    compositor.push(Box::new(Popup::new("confirm", confirm)));
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
+    }
+
+    /// Asks whether to write the modified buffers, discard their changes or keep editing,
+    /// before quitting.
+    fn confirm_quit(cx: &mut compositor::Context, modified: Vec<String>) {
+        let message = format!(
+            "{} unsaved buffer(s): {}",
+            modified.len(),
+            modified.join(", ")
+        );
+        let callback = async move {
+            let call: job::Callback =
+                Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    let choices = ui::Choices::new(message)
+                        .choice('w', "write all and quit", |_, cx| {
+                            let event = PromptEvent::Validate;
+                            if let Err(err) = write_all_impl(cx, &[], event, true, false) {
+                                cx.editor.set_error(err.to_string());
+                            }
+                        })
+                        .choice('d', "discard changes and quit", |_, cx| {
+                            let _ = quit_all_impl(cx.editor, true);
+                        })
+                        .choice('c', "cancel", |_, _| ());
+                    compositor.push(Box::new(Popup::new("quit", choices)));
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
+    }
+
+    fn modified_buffers(editor: &Editor) -> Vec<String> {
+        editor
             .documents()
             .filter(|doc| doc.is_modified())
             .map(|doc| {
@@ -2259,7 +2646,13 @@ pub mod cmd {
                     .map(|path| path.to_string_lossy().to_string())
                     .unwrap_or_else(|| SCRATCH_BUFFER_NAME.into())
             })
-            .collect();
+            .collect()
+    }
+
+    /// Results an error if there are modified buffers remaining and sets editor error,
+    /// otherwise returns `Ok(())`
+    pub(super) fn buffers_remaining_impl(editor: &mut Editor) -> anyhow::Result<()> {
+        let modified = modified_buffers(editor);
         if !modified.is_empty() {
             bail!(
                 "{} unsaved buffer(s) remaining: {:?}",
@@ -2270,6 +2663,9 @@ pub mod cmd {
         Ok(())
     }
 
+    /// Writes every modified buffer in one job. The results are reported once all writes
+    /// finished: if any of them failed, they're listed in a popup, the failed buffers stay
+    /// modified and quitting is aborted unless `force` is set.
     fn write_all_impl(
         cx: &mut compositor::Context,
         _args: &[Cow<str>],
@@ -2277,33 +2673,100 @@ pub mod cmd {
         quit: bool,
         force: bool,
     ) -> anyhow::Result<()> {
-        let mut errors = String::new();
+        let mut errors = Vec::new();
+        let mut writes = Vec::new();
 
-        // save all documents
-        for doc in &mut cx.editor.documents.values_mut() {
-            if doc.path().is_none() {
-                errors.push_str("cannot write a buffer without a filename\n");
+        for doc in cx.editor.documents.values_mut() {
+            if !doc.is_modified() {
                 continue;
             }
+            let name = match doc.relative_path() {
+                Some(path) => path.to_string_lossy().to_string(),
+                None => {
+                    errors.push(format!(
+                        "{}: cannot write a buffer without a filename",
+                        SCRATCH_BUFFER_NAME
+                    ));
+                    continue;
+                }
+            };
+            let id = doc.id();
+            let saved_revision = doc.saved_revision();
+            writes.push(
+                doc.save()
+                    .map(move |result| (id, name, saved_revision, result)),
+            );
+        }
 
-            // TODO: handle error.
-            let handle = doc.save();
-            cx.jobs.add(Job::new(handle).wait_before_exiting());
+        if !writes.is_empty() {
+            cx.editor
+                .set_status(format!("Writing {} buffer(s)...", writes.len()));
         }
 
-        if quit {
-            if !force {
-                buffers_remaining_impl(cx.editor)?;
-            }
+        let callback = async move {
+            let results = futures_util::future::join_all(writes).await;
+            let call: job::Callback =
+                Box::new(move |editor: &mut Editor, compositor: &mut Compositor| {
+                    let mut errors = errors;
+                    let mut written = 0;
+                    for (id, name, saved_revision, result) in results {
+                        match result {
+                            Ok(()) => {
+                                written += 1;
+                                if let Some(doc) = editor.document_mut(id) {
+                                    doc.set_written();
+                                }
+                            }
+                            Err(err) => {
+                                errors.push(format!("{}: {}", name, err));
+                                if let Some(doc) = editor.document_mut(id) {
+                                    doc.set_saved_revision(saved_revision);
+                                }
+                            }
+                        }
+                    }
 
-            // close all views
-            let views: Vec<_> = cx.editor.tree.views().map(|(view, _)| view.id).collect();
-            for view_id in views {
-                cx.editor.close(view_id);
-            }
-        }
+                    if !errors.is_empty() {
+                        let mut report = format!(
+                            "Wrote {} buffer(s), failed to write {}:\n\n{}",
+                            written,
+                            errors.len(),
+                            errors.join("\n")
+                        );
+                        if quit && !force {
+                            report.push_str("\n\nNot quitting.");
+                        }
+                        editor.set_error(format!("Failed to write {} buffer(s)", errors.len()));
+                        let contents = ui::Text::new(report);
+                        compositor.push(Box::new(Popup::new("write-all", contents)));
+                        if !force {
+                            return;
+                        }
+                    } else {
+                        editor.set_status(format!("Wrote {} buffer(s)", written));
+                    }
+
+                    if quit {
+                        if !force {
+                            if let Err(err) = buffers_remaining_impl(editor) {
+                                editor.set_error(err.to_string());
+                                return;
+                            }
+                        }
 
-        bail!(errors)
+                        // close all views
+                        let views: Vec<_> = editor.tree.views().map(|(view, _)| view.id).collect();
+                        for view_id in views {
+                            editor.close(view_id);
+                        }
+                    }
+                });
+            Ok(call)
+        };
+        cx.jobs
+            .add(Job::with_callback(callback).wait_before_exiting());
+
+        Ok(())
     }
 
     fn write_all(
@@ -2349,6 +2812,11 @@ pub mod cmd {
         _args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
+        let modified = modified_buffers(cx.editor);
+        if !modified.is_empty() {
+            confirm_quit(cx, modified);
+            return Ok(());
+        }
         quit_all_impl(cx.editor, false)
     }
 
@@ -2576,41 +3044,306 @@ pub mod cmd {
         }
     }
 
-    /// Reload the [`Document`] from its source file.
-    fn reload(
+    fn clear_search_highlight(
         cx: &mut compositor::Context,
         _args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
-        let (view, doc) = current!(cx.editor);
-        doc.reload(view.id)
+        view_mut!(cx.editor).search_highlight = None;
+        Ok(())
     }
 
-    fn tree_sitter_scopes(
+    fn pin_yank(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let index = match args.first() {
+            Some(arg) => arg
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| anyhow!("invalid yank history index: {}", arg))?,
+            None => 1,
+        };
+        let pinned = cx
+            .editor
+            .registers
+            .history
+            .toggle_pin(index - 1)
+            .ok_or_else(|| anyhow!("no yank history entry {}", index))?;
+        cx.editor.set_status(format!(
+            "{} yank history entry {}",
+            if pinned { "Pinned" } else { "Unpinned" },
+            index
+        ));
+        Ok(())
+    }
+
+    /// Reload the [`Document`] from its source file.
+    fn reload(
         cx: &mut compositor::Context,
         _args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
         let (view, doc) = current!(cx.editor);
-        let text = doc.text().slice(..);
-
-        let pos = doc.selection(view.id).primary().cursor(text);
-        let scopes = indent::get_scopes(doc.syntax(), text, pos);
-        cx.editor.set_status(format!("scopes: {:?}", &scopes));
-        Ok(())
+        doc.reload(view.id)
     }
 
-    fn vsplit(
+    /// Replace the matches of a regex in the files of the workspace, after picking which ones to
+    /// replace in a staging list.
+    fn global_replace(
         cx: &mut compositor::Context,
         args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
-        let id = view!(cx.editor).doc;
+        ensure!(args.len() == 2, "expected a regex and its replacement");
+        let regex = Regex::new(&args[0]).context("invalid regex")?;
+        let replacement = args[1].to_string();
+
+        // open buffers are searched as they are now, with unsaved changes
+        let mut hunks = Vec::new();
+        let mut open = HashSet::new();
+        for doc in cx.editor.documents() {
+            if let Some(path) = doc.path() {
+                open.insert(path.clone());
+                hunks.extend(
+                    replace::find_hunks(doc.text(), &regex, &replacement)
+                        .into_iter()
+                        .map(|hunk| (path.clone(), hunk)),
+                );
+            }
+        }
 
-        if args.is_empty() {
-            cx.editor.switch(id, Action::VerticalSplit);
-        } else {
-            for arg in args {
+        let config = cx.editor.config.file_picker.clone();
+        let callback = async move {
+            let scanned = tokio::task::spawn_blocking(move || {
+                scan_replace(&config, &regex, &replacement, &open)
+            })
+            .await?;
+            let call: job::Callback =
+                Box::new(move |editor: &mut Editor, compositor: &mut Compositor| {
+                    let mut hunks = hunks;
+                    hunks.extend(scanned);
+                    if hunks.is_empty() {
+                        editor.set_status("No matches found".to_string());
+                        return;
+                    }
+                    hunks.sort_by(|(a_path, a), (b_path, b)| {
+                        a_path.cmp(b_path).then(a.start.cmp(&b.start))
+                    });
+
+                    let files: HashSet<_> = hunks.iter().map(|(path, _)| path).collect();
+                    let header = format!(
+                        "Replace {} match(es) in {} file(s)",
+                        hunks.len(),
+                        files.len()
+                    );
+                    let staging = ui::Staging::new(
+                        header,
+                        hunks,
+                        |(path, hunk)| {
+                            format!(
+                                "{}:{}: {} -> {}",
+                                helix_core::path::get_relative_path(path).display(),
+                                hunk.line + 1,
+                                hunk.matched.replace('\n', "\\n"),
+                                hunk.replacement.replace('\n', "\\n"),
+                            )
+                        },
+                        apply_replace,
+                    );
+                    compositor.push(Box::new(Popup::new("global-replace", staging)));
+                });
+            Ok(call)
+        };
+        cx.jobs
+            .add(Job::with_callback(callback).named(&cx.editor.tasks, "global-replace"));
+        Ok(())
+    }
+
+    /// Undo the last `:global-replace` in every file it changed, unless the file was edited
+    /// since.
+    fn global_replace_undo(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            !cx.editor.replace_journal.is_empty(),
+            "no global replace to undo"
+        );
+        let mut undone = 0;
+        let mut skipped = 0;
+        for (doc_id, revision) in std::mem::take(&mut cx.editor.replace_journal) {
+            let doc = match cx.editor.document_mut(doc_id) {
+                Some(doc) => doc,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            match doc.selections().keys().next().copied() {
+                Some(view_id) if doc.current_revision() == revision => {
+                    doc.undo(view_id);
+                    undone += 1;
+                }
+                _ => skipped += 1,
+            }
+        }
+        if skipped == 0 {
+            cx.editor
+                .set_status(format!("Undid the replace in {} file(s)", undone));
+        } else {
+            cx.editor.set_error(format!(
+                "Undid the replace in {} file(s), skipped {} closed or edited since",
+                undone, skipped
+            ));
+        }
+        Ok(())
+    }
+
+    /// Move the file of the current buffer. Language servers interested in renames are asked
+    /// for edits to make first, like updating the imports of the file.
+    fn move_buffer(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let new_path = args.first().context("Usage: :move <path>")?;
+        let new_path = helix_core::path::get_canonicalized_path(Path::new(new_path.as_ref()))?;
+        let doc = doc!(cx.editor);
+        let old_path = doc
+            .path()
+            .context("cannot move a buffer without a filename")?
+            .clone();
+        ensure!(!new_path.exists(), "{} already exists", new_path.display());
+        ensure!(
+            matches!(new_path.parent(), Some(parent) if parent.exists()),
+            "the directory of {} does not exist",
+            new_path.display()
+        );
+
+        let to_uri = |path: &Path| {
+            lsp::Url::from_file_path(path)
+                .map(String::from)
+                .map_err(|_| anyhow!("invalid filepath: {}", path.display()))
+        };
+        let files = vec![lsp::FileRename {
+            old_uri: to_uri(&old_path)?,
+            new_uri: to_uri(&new_path)?,
+        }];
+
+        let doc_id = doc.id();
+        let will_rename = doc.language_server().and_then(|language_server| {
+            let offset_encoding = language_server.offset_encoding();
+            let future = language_server.will_rename_files(files.clone())?;
+            Some((offset_encoding, future))
+        });
+
+        let callback = async move {
+            let edit = match will_rename {
+                Some((offset_encoding, future)) => match future.await {
+                    Ok(edit) => serde_json::from_value::<Option<lsp::WorkspaceEdit>>(edit)?
+                        .map(|edit| (offset_encoding, edit)),
+                    Err(err) => {
+                        log::warn!("language server failed to prepare the rename: {}", err);
+                        None
+                    }
+                },
+                None => None,
+            };
+            let call: job::Callback =
+                Box::new(move |editor: &mut Editor, compositor: &mut Compositor| {
+                    let diff = match &edit {
+                        Some((offset_encoding, edit)) => {
+                            workspace_edit_diff(editor, *offset_encoding, edit)
+                        }
+                        None => String::new(),
+                    };
+                    let apply = move |editor: &mut Editor| {
+                        if let Some((offset_encoding, edit)) = &edit {
+                            apply_workspace_edit(editor, *offset_encoding, edit);
+                        }
+                        move_file(editor, doc_id, &old_path, &new_path, files);
+                    };
+                    // declining the edits the server asked for cancels the move
+                    if diff.is_empty() {
+                        apply(editor);
+                    } else {
+                        let preview = ui::DiffPreview::new(&diff, apply);
+                        compositor.push(Box::new(Popup::new("diff-preview", preview)));
+                    }
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
+        Ok(())
+    }
+
+    /// Moves the file of `doc_id` to `new_path` and points the buffer to it.
+    fn move_file(
+        editor: &mut Editor,
+        doc_id: DocumentId,
+        old_path: &Path,
+        new_path: &Path,
+        files: Vec<lsp::FileRename>,
+    ) {
+        if let Err(err) = std::fs::rename(old_path, new_path) {
+            editor.set_error(format!("Failed to move the file: {}", err));
+            return;
+        }
+
+        if let Some(doc) = editor.document_mut(doc_id) {
+            // the server knows the buffer by its old path
+            if let Some(language_server) = doc.language_server() {
+                tokio::spawn(language_server.text_document_did_close(doc.identifier()));
+            }
+            doc.set_language_server(None);
+            if let Err(err) = doc.set_path(Some(new_path)) {
+                editor.set_error(format!("invalid filepath: {}", err));
+                return;
+            }
+            let _ = editor.refresh_language_server(doc_id);
+        }
+        if let Some(notification) = editor
+            .document(doc_id)
+            .and_then(|doc| doc.language_server())
+            .and_then(|language_server| language_server.did_rename_files(files))
+        {
+            tokio::spawn(notification);
+        }
+        editor.set_status(format!(
+            "Moved to {}",
+            helix_core::path::get_relative_path(new_path).display()
+        ));
+    }
+
+    fn tree_sitter_scopes(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let (view, doc) = current!(cx.editor);
+        let text = doc.text().slice(..);
+
+        let pos = doc.selection(view.id).primary().cursor(text);
+        let scopes = indent::get_scopes(doc.syntax(), text, pos);
+        cx.editor.set_status(format!("scopes: {:?}", &scopes));
+        Ok(())
+    }
+
+    fn vsplit(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let id = view!(cx.editor).doc;
+
+        if args.is_empty() {
+            cx.editor.switch(id, Action::VerticalSplit);
+        } else {
+            for arg in args {
                 cx.editor
                     .open(PathBuf::from(arg.as_ref()), Action::VerticalSplit)?;
             }
@@ -2650,101 +3383,809 @@ pub mod cmd {
         Ok(())
     }
 
-    pub(super) fn goto_line_number(
+    pub(super) fn goto_line_number(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        ensure!(!args.is_empty(), "Line number required");
+
+        let line = args[0].parse::<usize>()?;
+
+        goto_line_impl(cx.editor, NonZeroUsize::new(line));
+
+        let (view, doc) = current!(cx.editor);
+
+        view.ensure_cursor_in_view(doc, line);
+        Ok(())
+    }
+
+    fn setting(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let runtime_config = &mut cx.editor.config;
+
+        if args.len() != 2 {
+            anyhow::bail!("Bad arguments. Usage: `:set key field`");
+        }
+
+        let (key, arg) = (&args[0].to_lowercase(), &args[1]);
+
+        match key.as_ref() {
+            "scrolloff" => runtime_config.scrolloff = arg.parse()?,
+            "scroll-lines" => runtime_config.scroll_lines = arg.parse()?,
+            "max-fps" => runtime_config.max_fps = arg.parse()?,
+            "mouse" => runtime_config.mouse = arg.parse()?,
+            "line-number" => runtime_config.line_number = arg.parse()?,
+            "gutters" => {
+                runtime_config.gutters = arg
+                    .split(',')
+                    .filter(|gutter| !gutter.is_empty())
+                    .map(str::parse)
+                    .collect::<anyhow::Result<_>>()?
+            }
+            "gutter-position" => runtime_config.gutter_position = arg.parse()?,
+            "soft-wrap" => runtime_config.soft_wrap = arg.parse()?,
+            "render-whitespace" => runtime_config.render_whitespace = arg.parse()?,
+            "bufferline" => runtime_config.bufferline = arg.parse()?,
+            "cursorline" => runtime_config.cursorline = arg.parse()?,
+            "indent-guides" => runtime_config.indent_guides = arg.parse()?,
+            "rulers" => {
+                runtime_config.rulers = arg
+                    .split(',')
+                    .filter(|ruler| !ruler.is_empty())
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?
+            }
+            "middle-click_paste" => runtime_config.middle_click_paste = arg.parse()?,
+            "paste-reindent" => runtime_config.paste_reindent = arg.parse()?,
+            "paste-escape-strings" => runtime_config.paste_escape_strings = arg.parse()?,
+            "smart-case" => runtime_config.smart_case = arg.parse()?,
+            "search-normalization" => runtime_config.search_normalization = arg.parse()?,
+            "persist-search-highlight" => runtime_config.persist_search_highlight = arg.parse()?,
+            "auto-pairs" => runtime_config.auto_pairs = arg.parse()?,
+            "auto-completion" => runtime_config.auto_completion = arg.parse()?,
+            "document-highlight" => runtime_config.document_highlight = arg.parse()?,
+            "completion-trigger-len" => runtime_config.completion_trigger_len = arg.parse()?,
+            "auto-info" => runtime_config.auto_info = arg.parse()?,
+            "true-color" => runtime_config.true_color = arg.parse()?,
+            "visual-bell" => runtime_config.visual_bell = arg.parse()?,
+            "keyboard-layout" => runtime_config.keyboard_layout = arg.parse()?,
+            "show-keys" => runtime_config.show_keys = arg.parse()?,
+            "dim-unsaved-time" => runtime_config.dim_unsaved_time = arg.parse()?,
+            "jumplist-indicator" => runtime_config.jumplist_indicator = arg.parse()?,
+            "resolve-symlinks" => runtime_config.resolve_symlinks = arg.parse()?,
+            _ => anyhow::bail!("Unknown key `{}`.", args[0]),
+        }
+
+        let (gutters, gutter_position, soft_wrap) = (
+            runtime_config.gutters.clone(),
+            runtime_config.gutter_position,
+            runtime_config.soft_wrap,
+        );
+        for (view, _) in cx.editor.tree.views_mut() {
+            view.gutters = gutters.clone();
+            view.gutter_position = gutter_position;
+            view.soft_wrap = soft_wrap;
+        }
+
+        Ok(())
+    }
+
+    fn bind(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let (mode, args) = match args.first().map(|arg| arg.parse::<Mode>()) {
+            Some(Ok(mode)) => (mode, &args[1..]),
+            _ => (Mode::Normal, args),
+        };
+        let (command, keys) = args
+            .split_last()
+            .context("Bad arguments. Usage: `:bind [mode] keys... command`")?;
+        ensure!(!keys.is_empty(), "Keys not provided");
+        let keys = keys
+            .iter()
+            .map(|key| key.parse::<KeyEvent>())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        super::bind_impl(cx, mode, keys, command.parse()?)
+    }
+
+    /// Like `:set`, and also `:theme`, but saves the option to config.toml.
+    fn config_set(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let (table, key, value) = if args.len() == 2 && args[0] == "theme" {
+            theme(cx, &args[1..], event)?;
+            let value = toml::Value::String(args[1].to_string());
+            (None, "theme".to_string(), value)
+        } else {
+            setting(cx, args, event)?;
+            // saved as parsed by `:set`, so that lists are written as arrays
+            let key = args[0].to_lowercase().replace('_', "-");
+            let value = toml::Value::try_from(&cx.editor.config)?
+                .get(&key)
+                .cloned()
+                .with_context(|| format!("`{}` can't be saved to config.toml", key))?;
+            (Some("editor"), key, value)
+        };
+
+        let path = helix_core::config_file();
+        let config = match std::fs::read_to_string(&path) {
+            Ok(config) => config,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err).context("Failed to read config.toml"),
+        };
+        let config = crate::config::set_toml_value(&config, table, &key, &value);
+        std::fs::create_dir_all(helix_core::config_dir())?;
+        std::fs::write(&path, config).context("Failed to write config.toml")?;
+        cx.editor
+            .set_status(format!("Saved {} to {}", args[0], path.display()));
+        Ok(())
+    }
+
+    fn sort(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        sort_impl(cx, args, false)
+    }
+
+    fn sort_reverse(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        sort_impl(cx, args, true)
+    }
+
+    fn sort_impl(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        reverse: bool,
+    ) -> anyhow::Result<()> {
+        let (view, doc) = current!(cx.editor);
+        let text = doc.text().slice(..);
+
+        let selection = doc.selection(view.id);
+
+        let mut fragments: Vec<_> = selection
+            .fragments(text)
+            .map(|fragment| Tendril::from_slice(&fragment))
+            .collect();
+
+        fragments.sort_by(match reverse {
+            true => |a: &Tendril, b: &Tendril| b.cmp(a),
+            false => |a: &Tendril, b: &Tendril| a.cmp(b),
+        });
+
+        let transaction = Transaction::change(
+            doc.text(),
+            selection
+                .into_iter()
+                .zip(fragments)
+                .map(|(s, fragment)| (s.from(), s.to(), Some(fragment))),
+        );
+
+        doc.apply(&transaction, view.id);
+        doc.append_changes_to_history(view.id);
+
+        Ok(())
+    }
+
+    fn messages(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        use helix_view::editor::StatusMessage;
+
+        ensure!(!cx.editor.status_history.is_empty(), "No messages");
+
+        // most recent first
+        let messages: Vec<StatusMessage> = cx.editor.status_history.iter().rev().cloned().collect();
+
+        let callback = async move {
+            let call: job::Callback =
+                Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    let picker = Picker::new(
+                        true,
+                        messages,
+                        |status: &StatusMessage| {
+                            let time = chrono::DateTime::<chrono::Local>::from(status.time);
+                            let severity = match status.severity {
+                                Severity::Hint => "hint",
+                                Severity::Info => "info",
+                                Severity::Warning => "warning",
+                                Severity::Error => "error",
+                            };
+                            format!(
+                                "{} {:<7} {}",
+                                time.format("%H:%M:%S"),
+                                severity,
+                                status.message
+                            )
+                            .into()
+                        },
+                        |editor: &mut Editor, status: &StatusMessage, _action| match editor
+                            .clipboard_provider
+                            .set_contents(status.message.clone(), ClipboardType::Clipboard)
+                        {
+                            Ok(()) => editor.set_transient_status(
+                                "Copied the message to the clipboard".to_string(),
+                            ),
+                            Err(err) => editor.set_error(err.to_string()),
+                        },
+                    );
+                    compositor.push(Box::new(picker));
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
+
+        Ok(())
+    }
+
+    fn goto_json_path(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        use helix_core::data_path::{find_path, DataPath};
+
+        let path: DataPath = args
+            .first()
+            .context("Path not provided")?
+            .parse()
+            .map_err(|err: String| anyhow!(err))?;
+        let doc = doc!(cx.editor);
+        let syntax = doc.syntax().context("Document has no syntax tree")?;
+        let text = doc.text().slice(..);
+        let node = find_path(syntax.tree().root_node(), text, &path)
+            .with_context(|| format!("Path not found: {}", path))?;
+        let range = Range::new(
+            text.byte_to_char(node.start_byte()),
+            text.byte_to_char(node.end_byte()),
+        );
+
+        push_jump(cx.editor);
+        let (view, doc) = current!(cx.editor);
+        doc.set_selection(view.id, Selection::single(range.anchor, range.head));
+        align_view(doc, view, Align::Center);
+        Ok(())
+    }
+
+    /// Opens `path`, first creating it with `template` if it doesn't exist.
+    fn open_config_file(
+        cx: &mut compositor::Context,
+        path: PathBuf,
+        template: &str,
+    ) -> anyhow::Result<()> {
+        if !path.exists() {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, template)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+        }
+        cx.editor.open(path, Action::Replace)?;
+        Ok(())
+    }
+
+    fn config_open(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        open_config_file(
+            cx,
+            helix_core::config_file(),
+            "# See https://docs.helix-editor.com/configuration.html\n\n[editor]\n",
+        )
+    }
+
+    /// Open the snippets of the current buffer's language, creating the file if needed.
+    fn snippets_open(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let language_id = snippet_language(doc!(cx.editor)).to_string();
+        open_config_file(
+            cx,
+            helix_core::snippets::path(&language_id),
+            helix_core::snippets::EXAMPLE,
+        )
+    }
+
+    /// Pick a snippet of the current buffer's language to insert, previewed in its file.
+    fn snippets(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let language_id = snippet_language(doc!(cx.editor)).to_string();
+        let path = helix_core::snippets::path(&language_id);
+        let snippets = helix_core::snippets::load(&language_id)?;
+        ensure!(
+            !snippets.is_empty(),
+            "No {} snippets, add some with :snippets-open",
+            language_id
+        );
+        let toml = std::fs::read_to_string(&path)?;
+        let snippets: Vec<_> = snippets
+            .into_iter()
+            .map(|(name, snippet)| {
+                let line = helix_core::snippets::line(&toml, &name);
+                (name, snippet, line)
+            })
+            .collect();
+
+        let callback = async move {
+            let call: job::Callback =
+                Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    let picker = FilePicker::new(
+                        snippets,
+                        |(name, snippet, _): &(String, Snippet, Option<usize>)| {
+                            format!(
+                                "{}  {}",
+                                snippet.prefix,
+                                snippet.description.as_deref().unwrap_or(name)
+                            )
+                            .into()
+                        },
+                        |editor: &mut Editor, (_, snippet, _), _action| {
+                            let (view, doc) = current!(editor);
+                            let transaction = Transaction::insert(
+                                doc.text(),
+                                doc.selection(view.id),
+                                snippet.body.as_str().into(),
+                            );
+                            doc.apply(&transaction, view.id);
+                            doc.append_changes_to_history(view.id);
+                        },
+                        move |_editor, (_, _, line)| {
+                            Some((path.clone(), line.map(|line| (line, line))))
+                        },
+                    );
+                    compositor.push(Box::new(picker));
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
+        Ok(())
+    }
+
+    fn languages_open(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        open_config_file(
+            cx,
+            helix_core::config_dir().join("languages.toml"),
+            "# Overrides the built-in language config, see\n# https://docs.helix-editor.com/languages.html\n",
+        )
+    }
+
+    /// Opens the user's copy of a theme. A new theme starts as a copy of the
+    /// built-in theme with the same name, or of the default theme.
+    fn theme_open(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let name = args.first().context("Theme name not provided")?;
+        ensure!(
+            name != "default" && name != "base16_default",
+            "The built-in `{}` theme can't be changed, copy it under another name",
+            name
+        );
+        let filename = format!("{}.toml", name);
+        let template =
+            std::fs::read_to_string(helix_core::runtime_dir().join("themes").join(&filename))
+                .unwrap_or_else(|_| include_str!("../../theme.toml").to_string());
+        open_config_file(
+            cx,
+            helix_core::config_dir().join("themes").join(filename),
+            &template,
+        )
+    }
+
+    fn log_open(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        cx.editor.open(helix_core::log_file(), Action::Replace)?;
+        Ok(())
+    }
+
+    /// Check config.toml and languages.toml again and open the first problem.
+    fn goto_config_error(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let err = match crate::config::validate().into_iter().next() {
+            Some(err) => err,
+            None => {
+                cx.editor
+                    .set_status("No errors in config.toml or languages.toml".to_string());
+                return Ok(());
+            }
+        };
+
+        push_jump(cx.editor);
+        cx.editor.open(err.path.clone(), Action::Replace)?;
+        if let Some((line, col)) = err.position {
+            let (view, doc) = current!(cx.editor);
+            let text = doc.text().slice(..);
+            let line = line.min(text.len_lines() - 1);
+            let line_start = text.line_to_char(line);
+            let pos = (line_start + col).min(line_end_char_index(&text, line));
+            doc.set_selection(view.id, Selection::point(pos));
+            align_view(doc, view, Align::Center);
+        }
+        cx.editor.set_error(err.to_string());
+        Ok(())
+    }
+
+    /// Show line, word, character and byte counts of the buffer and the
+    /// selections, and the reading time of prose.
+    fn stats(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        use helix_core::stats::Stats;
+
+        let (view, doc) = current_ref!(cx.editor);
+        let text = doc.text().slice(..);
+        let buffer = Stats::new(text);
+        let selection = doc.selection(view.id);
+
+        let row = |name: &str, stats: &Stats| {
+            format!(
+                "{:<12}{:>8}{:>8}{:>9}{:>9}",
+                name, stats.lines, stats.words, stats.chars, stats.bytes
+            )
+        };
+        let mut lines = vec![
+            format!(
+                "{:<12}{:>8}{:>8}{:>9}{:>9}",
+                "", "lines", "words", "chars", "bytes"
+            ),
+            row("buffer", &buffer),
+        ];
+        let mut total = Stats::default();
+        for (i, range) in selection.iter().enumerate() {
+            let stats = Stats::new(text.slice(range.from()..range.to()));
+            if selection.len() > 1 {
+                lines.push(row(&format!("selection {}", i + 1), &stats));
+            }
+            total += stats;
+        }
+        lines.push(row(
+            if selection.len() > 1 {
+                "selections"
+            } else {
+                "selection"
+            },
+            &total,
+        ));
+
+        // plain text and markdown
+        if matches!(doc.language(), None | Some("source.md")) {
+            lines.push(String::new());
+            lines.push(format!(
+                "Reading time: about {} min",
+                buffer.reading_minutes()
+            ));
+        }
+
+        let contents = lines.join("\n");
+        let callback = async move {
+            let call: job::Callback =
+                Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    let contents = ui::Text::new(contents);
+                    compositor.push(Box::new(Popup::new("stats", contents)));
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
+        Ok(())
+    }
+
+    /// Show the startup and file loading timings collected with `--timings`.
+    fn timings(
+        cx: &mut compositor::Context,
+        _args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            helix_core::timings::is_enabled(),
+            "Timings are only collected when started with `hx --timings`"
+        );
+        let report = helix_core::timings::report(&helix_core::timings::timings());
+        let callback = async move {
+            let call: job::Callback =
+                Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    let contents = ui::Text::new(report);
+                    compositor.push(Box::new(Popup::new("timings", contents)));
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
+        Ok(())
+    }
+
+    /// Show the settings of the current language server, or replace them
+    /// with the JSON given as the argument.
+    fn lsp_config(
+        cx: &mut compositor::Context,
+        args: &[Cow<str>],
+        _event: PromptEvent,
+    ) -> anyhow::Result<()> {
+        let doc = doc!(cx.editor);
+        let language_server = doc
+            .language_server()
+            .context("Language server not active for current buffer")?;
+
+        if args.is_empty() {
+            let settings = language_server
+                .settings()
+                .unwrap_or(serde_json::Value::Null);
+            let contents = format!("```json\n{:#}\n```", settings);
+            let callback = async move {
+                let call: job::Callback =
+                    Box::new(move |editor: &mut Editor, compositor: &mut Compositor| {
+                        let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
+                        compositor.push(Box::new(Popup::new("lsp-config", contents)));
+                    });
+                Ok(call)
+            };
+            cx.jobs.callback(callback);
+            return Ok(());
+        }
+
+        let settings: serde_json::Value =
+            serde_json::from_str(&args.join(" ")).context("Invalid JSON")?;
+        let notify = language_server.did_change_configuration(settings);
+        cx.jobs
+            .spawn(async move { notify.await.map_err(Into::into) });
+        cx.editor
+            .set_status("Sent new settings to the language server".to_string());
+        Ok(())
+    }
+
+    /// Replace the buffer with the output of a shell command once the diff
+    /// has been confirmed.
+    fn reload_from(
         cx: &mut compositor::Context,
         args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
-        ensure!(!args.is_empty(), "Line number required");
-
-        let line = args[0].parse::<usize>()?;
-
-        goto_line_impl(cx.editor, NonZeroUsize::new(line));
-
-        let (view, doc) = current!(cx.editor);
+        ensure!(!args.is_empty(), "Command not provided");
+        let cmd = args.join(" ");
+        let shell = cx.editor.config.shell.clone();
+        let doc = doc!(cx.editor);
+        let doc_id = doc.id();
+        let old = doc.text().clone();
 
-        view.ensure_cursor_in_view(doc, line);
+        let callback = async move {
+            let (output, success) = shell_impl_async(shell, cmd.clone(), None).await?;
+            ensure!(success, "`{}` exited with an error", cmd);
+            let new = Rope::from(output);
+            let call: job::Callback =
+                Box::new(move |editor: &mut Editor, compositor: &mut Compositor| {
+                    let diff = helix_core::diff::unified_diff(&old, &new);
+                    if diff.is_empty() {
+                        editor.set_status("No changes".to_string());
+                        return;
+                    }
+                    let preview = ui::DiffPreview::new(&diff, move |editor: &mut Editor| {
+                        let focus = view!(editor).id;
+                        let doc = match editor.document_mut(doc_id) {
+                            Some(doc) if doc.text() == &old => doc,
+                            _ => {
+                                editor.set_error("Buffer changed, not applying".to_string());
+                                return;
+                            }
+                        };
+                        let view_id = if doc.selections().contains_key(&focus) {
+                            focus
+                        } else {
+                            match doc.selections().keys().next() {
+                                Some(&view_id) => view_id,
+                                None => return,
+                            }
+                        };
+                        let transaction = helix_core::diff::compare_ropes(&old, &new);
+                        doc.apply(&transaction, view_id);
+                        doc.append_changes_to_history(view_id);
+                    });
+                    compositor.push(Box::new(Popup::new("diff-preview", preview)));
+                });
+            Ok(call)
+        };
+        cx.jobs
+            .add(Job::with_callback(callback).named(&cx.editor.tasks, args.join(" ")));
         Ok(())
     }
 
-    fn setting(
+    /// Show how the system clipboard differs from the primary selection.
+    fn compare_clipboard(
         cx: &mut compositor::Context,
-        args: &[Cow<str>],
+        _args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
-        let runtime_config = &mut cx.editor.config;
-
-        if args.len() != 2 {
-            anyhow::bail!("Bad arguments. Usage: `:set key field`");
-        }
-
-        let (key, arg) = (&args[0].to_lowercase(), &args[1]);
+        let (view, doc) = current_ref!(cx.editor);
+        let selection = doc
+            .selection(view.id)
+            .primary()
+            .fragment(doc.text().slice(..))
+            .replace("\r\n", "\n");
+        let clipboard = cx
+            .editor
+            .clipboard_provider
+            .get_contents(ClipboardType::Clipboard)?
+            .replace("\r\n", "\n");
 
-        match key.as_ref() {
-            "scrolloff" => runtime_config.scrolloff = arg.parse()?,
-            "scroll-lines" => runtime_config.scroll_lines = arg.parse()?,
-            "mouse" => runtime_config.mouse = arg.parse()?,
-            "line-number" => runtime_config.line_number = arg.parse()?,
-            "middle-click_paste" => runtime_config.middle_click_paste = arg.parse()?,
-            "smart-case" => runtime_config.smart_case = arg.parse()?,
-            "auto-pairs" => runtime_config.auto_pairs = arg.parse()?,
-            "auto-completion" => runtime_config.auto_completion = arg.parse()?,
-            "completion-trigger-len" => runtime_config.completion_trigger_len = arg.parse()?,
-            "auto-info" => runtime_config.auto_info = arg.parse()?,
-            "true-color" => runtime_config.true_color = arg.parse()?,
-            _ => anyhow::bail!("Unknown key `{}`.", args[0]),
+        let diff = helix_core::diff::unified_diff(&Rope::from(selection), &Rope::from(clipboard));
+        if diff.is_empty() {
+            cx.editor
+                .set_status("The clipboard matches the selection".to_string());
+            return Ok(());
         }
 
+        let callback = async move {
+            let call: job::Callback =
+                Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    let preview =
+                        ui::DiffPreview::read_only(&diff, "Selection (-) against clipboard (+)");
+                    compositor.push(Box::new(Popup::new("diff-preview", preview)));
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
         Ok(())
     }
 
-    fn sort(
+    /// Pipe the selections into a shell command, showing its output.
+    fn write_to(
         cx: &mut compositor::Context,
         args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
-        sort_impl(cx, args, false)
+        ensure!(!args.is_empty(), "Command not provided");
+        let cmd = args.join(" ");
+        let shell = cx.editor.config.shell.clone();
+        let (view, doc) = current_ref!(cx.editor);
+        let text = doc.text().slice(..);
+        let input: String = doc
+            .selection(view.id)
+            .fragments(text)
+            .collect::<Vec<_>>()
+            .join(doc.line_ending.as_str());
+
+        let callback = async move {
+            let (output, success) =
+                shell_impl_async(shell, cmd.clone(), Some(input.into_bytes())).await?;
+            let call: job::Callback = Box::new(move |editor: &mut Editor, _| {
+                let output = output.trim_end();
+                match (success, output.is_empty()) {
+                    (false, _) => editor.set_error(format!("`{}` exited with an error", cmd)),
+                    (true, true) => editor.set_status(format!("Wrote to `{}`", cmd)),
+                    (true, false) => editor.set_status(output.to_string()),
+                }
+            });
+            Ok(call)
+        };
+        cx.jobs
+            .add(Job::with_callback(callback).named(&cx.editor.tasks, args.join(" ")));
+        Ok(())
     }
 
-    fn sort_reverse(
+    /// Run the markdown code block under the cursor and write its output to
+    /// an `output` block below it, replacing the output of a previous run.
+    fn run_cell(
         cx: &mut compositor::Context,
         args: &[Cow<str>],
         _event: PromptEvent,
     ) -> anyhow::Result<()> {
-        sort_impl(cx, args, true)
-    }
+        use helix_core::code_block::code_blocks;
 
-    fn sort_impl(
-        cx: &mut compositor::Context,
-        _args: &[Cow<str>],
-        reverse: bool,
-    ) -> anyhow::Result<()> {
-        let (view, doc) = current!(cx.editor);
+        let (view, doc) = current_ref!(cx.editor);
         let text = doc.text().slice(..);
+        let line = text.char_to_line(doc.selection(view.id).primary().cursor(text));
+        let blocks = code_blocks(text);
+        let (i, block) = blocks
+            .iter()
+            .enumerate()
+            .find(|(_, block)| block.start <= line && line <= block.end)
+            .context("No code block under cursor")?;
+
+        let runner = match args.first() {
+            Some(runner) => runner.to_string(),
+            None => cx
+                .editor
+                .config
+                .cell_runners
+                .get(&block.language)
+                .with_context(|| {
+                    format!(
+                        "No runner for `{}` code blocks, pass a command or set `cell-runners`",
+                        block.language
+                    )
+                })?
+                .clone(),
+        };
+        let code = text
+            .slice(text.line_to_char(block.start + 1)..text.line_to_char(block.end))
+            .to_string();
+        let line_ending = doc.line_ending.as_str().to_string();
+        let start = text.line_to_char(block.end + 1);
+        let end = match blocks.get(i + 1) {
+            Some(next) if next.start == block.end + 1 && next.language == "output" => {
+                text.line_to_char(next.end + 1)
+            }
+            _ => start,
+        };
+        // the closing fence is the last line and not terminated
+        let unterminated = start == text.len_chars()
+            && helix_core::line_ending::get_line_ending(&text.line(block.end)).is_none();
+        let view_id = view.id;
+        let doc_id = doc.id();
+        let version = doc.version();
+
+        let shell = cx.editor.config.shell.clone();
+        let callback = async move {
+            let (output, success) =
+                shell_impl_async(shell, format!("{} 2>&1", runner), Some(code.into_bytes()))
+                    .await?;
+
+            let mut cell = format!("```output{}{}", line_ending, output);
+            if !output.is_empty() && get_line_ending_of_str(&output).is_none() {
+                cell.push_str(&line_ending);
+            }
+            cell.push_str("```");
+            cell.push_str(&line_ending);
+            if unterminated {
+                cell.insert_str(0, &line_ending);
+            }
 
-        let selection = doc.selection(view.id);
-
-        let mut fragments: Vec<_> = selection
-            .fragments(text)
-            .map(|fragment| Tendril::from_slice(&fragment))
-            .collect();
-
-        fragments.sort_by(match reverse {
-            true => |a: &Tendril, b: &Tendril| b.cmp(a),
-            false => |a: &Tendril, b: &Tendril| a.cmp(b),
-        });
-
-        let transaction = Transaction::change(
-            doc.text(),
-            selection
-                .into_iter()
-                .zip(fragments)
-                .map(|(s, fragment)| (s.from(), s.to(), Some(fragment))),
-        );
-
-        doc.apply(&transaction, view.id);
-        doc.append_changes_to_history(view.id);
-
+            let call: job::Callback = Box::new(move |editor: &mut Editor, _| {
+                let doc = match editor.document_mut(doc_id) {
+                    Some(doc) if doc.version() == version => doc,
+                    _ => {
+                        editor.set_error("Buffer changed, not writing the cell output".to_string());
+                        return;
+                    }
+                };
+                if !doc.selections().contains_key(&view_id) {
+                    return;
+                }
+                let transaction = Transaction::change(
+                    doc.text(),
+                    std::iter::once((start, end, Some(Tendril::from(cell)))),
+                );
+                doc.apply(&transaction, view_id);
+                doc.append_changes_to_history(view_id);
+                if !success {
+                    editor.set_error("Cell exited with an error".to_string());
+                }
+            });
+            Ok(call)
+        };
+        cx.jobs
+            .add(Job::with_callback(callback).named(&cx.editor.tasks, "run-cell"));
         Ok(())
     }
 
@@ -2784,6 +4225,27 @@ pub mod cmd {
             fun: force_buffer_close,
             completer: None, // FIXME: buffer completer
         },
+        TypableCommand {
+            name: "new-from-template",
+            aliases: &["nt"],
+            doc: "Create a buffer from a template of the `templates` directory of the config directory, and give it the path of the second argument if any. Lists the templates without arguments.",
+            fun: new_from_template,
+            completer: Some(completers::template),
+        },
+        TypableCommand {
+            name: "buffer-reopen",
+            aliases: &["br", "breopen"],
+            doc: "Reopen the most recently closed buffer where it was left.",
+            fun: buffer_reopen,
+            completer: None,
+        },
+        TypableCommand {
+            name: "compare-clipboard",
+            aliases: &[],
+            doc: "Show how the system clipboard differs from the primary selection.",
+            fun: compare_clipboard,
+            completer: None,
+        },
         TypableCommand {
             name: "write",
             aliases: &["w"],
@@ -2857,7 +4319,7 @@ pub mod cmd {
         TypableCommand {
             name: "write-quit-all",
             aliases: &["wqa", "xa"],
-            doc: "Write changes from all views to disk and close all views.",
+            doc: "Write changes from all views to disk and close all views. Quitting is aborted if any write fails.",
             fun: write_all_quit,
             completer: None,
         },
@@ -2974,94 +4436,248 @@ pub mod cmd {
             completer: None,
         },
         TypableCommand {
-            name: "show-clipboard-provider",
+            name: "show-clipboard-provider",
+            aliases: &[],
+            doc: "Show clipboard provider name in status bar.",
+            fun: show_clipboard_provider,
+            completer: None,
+        },
+        TypableCommand {
+            name: "change-current-directory",
+            aliases: &["cd"],
+            doc: "Change the current working directory.",
+            fun: change_current_directory,
+            completer: Some(completers::directory),
+        },
+        TypableCommand {
+            name: "show-directory",
+            aliases: &["pwd"],
+            doc: "Show the current working directory.",
+            fun: show_current_directory,
+            completer: None,
+        },
+        TypableCommand {
+            name: "encoding",
+            aliases: &[],
+            doc: "Set encoding based on `https://encoding.spec.whatwg.org`",
+            fun: set_encoding,
+            completer: None,
+        },
+        TypableCommand {
+            name: "nohl",
+            aliases: &["noh", "nohlsearch"],
+            doc: "Stop highlighting the matches of the last search in the current view.",
+            fun: clear_search_highlight,
+            completer: None,
+        },
+        TypableCommand {
+            name: "pin-yank",
+            aliases: &[],
+            doc: "Pin or unpin an entry of the yank history, by its number in the picker (defaults to the latest), so it is never dropped.",
+            fun: pin_yank,
+            completer: None,
+        },
+        TypableCommand {
+            name: "reload",
+            aliases: &[],
+            doc: "Discard changes and reload from the source file.",
+            fun: reload,
+            completer: None,
+        },
+        TypableCommand {
+            name: "global-replace",
+            aliases: &[],
+            doc: "Replace the matches of a regex in the workspace with the second argument, which can refer to capture groups like $1. The matches are listed first, to pick which ones to replace.",
+            fun: global_replace,
+            completer: None,
+        },
+        TypableCommand {
+            name: "global-replace-undo",
+            aliases: &[],
+            doc: "Undo the last global replace in every file it changed.",
+            fun: global_replace_undo,
+            completer: None,
+        },
+        TypableCommand {
+            name: "move",
+            aliases: &["mv"],
+            doc: "Move the file of the current buffer to the given path. Language servers are asked to update references to it, like imports, and their edits are previewed before the move.",
+            fun: move_buffer,
+            completer: Some(completers::filename),
+        },
+        TypableCommand {
+            name: "tree-sitter-scopes",
+            aliases: &[],
+            doc: "Display tree sitter scopes, primarily for theming and development.",
+            fun: tree_sitter_scopes,
+            completer: None,
+        },
+        TypableCommand {
+            name: "vsplit",
+            aliases: &["vs"],
+            doc: "Open the file in a vertical split.",
+            fun: vsplit,
+            completer: Some(completers::filename),
+        },
+        TypableCommand {
+            name: "hsplit",
+            aliases: &["hs", "sp"],
+            doc: "Open the file in a horizontal split.",
+            fun: hsplit,
+            completer: Some(completers::filename),
+        },
+        TypableCommand {
+            name: "tutor",
+            aliases: &[],
+            doc: "Open the tutorial.",
+            fun: tutor,
+            completer: None,
+        },
+        TypableCommand {
+            name: "goto",
+            aliases: &["g"],
+            doc: "Go to line number.",
+            fun: goto_line_number,
+            completer: None,
+        },
+        TypableCommand {
+            name: "set-option",
+            aliases: &["set"],
+            doc: "Set a config option at runtime",
+            fun: setting,
+            completer: Some(completers::setting),
+        },
+        TypableCommand {
+            name: "sort",
+            aliases: &[],
+            doc: "Sort ranges in selection.",
+            fun: sort,
+            completer: None,
+        },
+        TypableCommand {
+            name: "rsort",
+            aliases: &[],
+            doc: "Sort ranges in selection in reverse order.",
+            fun: sort_reverse,
+            completer: None,
+        },
+        TypableCommand {
+            name: "messages",
+            aliases: &[],
+            doc: "Open a picker with the recent status and error messages. Selecting one copies it to the clipboard.",
+            fun: messages,
+            completer: None,
+        },
+        TypableCommand {
+            name: "run-cell",
+            aliases: &[],
+            doc: "Run the markdown code block under the cursor, writing its output below it. Takes the command to run it with, defaults to the `cell-runners` entry for its language.",
+            fun: run_cell,
+            completer: None,
+        },
+        TypableCommand {
+            name: "goto-json-path",
+            aliases: &[],
+            doc: "Select the value at a path like `spec.containers[0].image` in a JSON or YAML document.",
+            fun: goto_json_path,
+            completer: None,
+        },
+        TypableCommand {
+            name: "reload-from",
+            aliases: &["read"],
+            doc: "Replace the buffer with the output of a shell command, after previewing the changes.",
+            fun: reload_from,
+            completer: None,
+        },
+        TypableCommand {
+            name: "lsp-config",
+            aliases: &[],
+            doc: "Show the settings of the language server. Given a JSON value (quote it with single quotes), send it as the new settings without restarting the server.",
+            fun: lsp_config,
+            completer: None,
+        },
+        TypableCommand {
+            name: "config-set",
             aliases: &[],
-            doc: "Show clipboard provider name in status bar.",
-            fun: show_clipboard_provider,
+            doc: "Set a config option like `:set` (or the theme) and save it to config.toml, keeping its comments.",
+            fun: config_set,
             completer: None,
         },
         TypableCommand {
-            name: "change-current-directory",
-            aliases: &["cd"],
-            doc: "Change the current working directory.",
-            fun: change_current_directory,
-            completer: Some(completers::directory),
+            name: "bind",
+            aliases: &[],
+            doc: "Bind a key sequence to a command and save it to config.toml, like `:bind normal space x file_picker`. The mode defaults to normal.",
+            fun: bind,
+            completer: Some(completers::command),
         },
         TypableCommand {
-            name: "show-directory",
-            aliases: &["pwd"],
-            doc: "Show the current working directory.",
-            fun: show_current_directory,
+            name: "write-to",
+            aliases: &[],
+            doc: "Pipe the selections into a shell command's stdin, showing its output.",
+            fun: write_to,
             completer: None,
         },
         TypableCommand {
-            name: "encoding",
+            name: "timings",
             aliases: &[],
-            doc: "Set encoding based on `https://encoding.spec.whatwg.org`",
-            fun: set_encoding,
+            doc: "Show how long startup and opening files took. Requires starting with `--timings`.",
+            fun: timings,
             completer: None,
         },
         TypableCommand {
-            name: "reload",
+            name: "goto-config-error",
             aliases: &[],
-            doc: "Discard changes and reload from the source file.",
-            fun: reload,
+            doc: "Check config.toml and languages.toml and go to the first error.",
+            fun: goto_config_error,
             completer: None,
         },
         TypableCommand {
-            name: "tree-sitter-scopes",
+            name: "config-open",
             aliases: &[],
-            doc: "Display tree sitter scopes, primarily for theming and development.",
-            fun: tree_sitter_scopes,
+            doc: "Open the user config.toml file, creating it if needed.",
+            fun: config_open,
             completer: None,
         },
         TypableCommand {
-            name: "vsplit",
-            aliases: &["vs"],
-            doc: "Open the file in a vertical split.",
-            fun: vsplit,
-            completer: Some(completers::filename),
-        },
-        TypableCommand {
-            name: "hsplit",
-            aliases: &["hs", "sp"],
-            doc: "Open the file in a horizontal split.",
-            fun: hsplit,
-            completer: Some(completers::filename),
+            name: "snippets",
+            aliases: &[],
+            doc: "Pick a snippet of the current language to insert.",
+            fun: snippets,
+            completer: None,
         },
         TypableCommand {
-            name: "tutor",
+            name: "snippets-open",
             aliases: &[],
-            doc: "Open the tutorial.",
-            fun: tutor,
+            doc: "Open the snippets file of the current language, creating it if needed. Saved snippets are completed right away.",
+            fun: snippets_open,
             completer: None,
         },
         TypableCommand {
-            name: "goto",
-            aliases: &["g"],
-            doc: "Go to line number.",
-            fun: goto_line_number,
+            name: "languages-open",
+            aliases: &[],
+            doc: "Open the user languages.toml file, creating it if needed.",
+            fun: languages_open,
             completer: None,
         },
         TypableCommand {
-            name: "set-option",
-            aliases: &["set"],
-            doc: "Set a config option at runtime",
-            fun: setting,
-            completer: Some(completers::setting),
+            name: "theme-open",
+            aliases: &[],
+            doc: "Open a user theme file, creating it from the built-in theme of that name or the default theme if needed.",
+            fun: theme_open,
+            completer: Some(completers::theme),
         },
         TypableCommand {
-            name: "sort",
+            name: "log-open",
             aliases: &[],
-            doc: "Sort ranges in selection.",
-            fun: sort,
+            doc: "Open the helix log file.",
+            fun: log_open,
             completer: None,
         },
         TypableCommand {
-            name: "rsort",
+            name: "stats",
             aliases: &[],
-            doc: "Sort ranges in selection in reverse order.",
-            fun: sort_reverse,
+            doc: "Show line, word, character and byte counts of the buffer and the selections.",
+            fun: stats,
             completer: None,
         },
     ];
@@ -3177,82 +4793,639 @@ fn command_mode(cx: &mut Context) {
         None
     });
 
-    cx.push_layer(Box::new(prompt));
+    cx.push_layer(Box::new(prompt));
+}
+
+fn file_picker(cx: &mut Context) {
+    // We don't specify language markers, root will be the root of the current git repo
+    let root = find_root(None, &[]).unwrap_or_else(|| PathBuf::from("./"));
+    let picker = ui::file_picker(root, &cx.editor.config);
+    cx.push_layer(Box::new(picker));
+}
+
+fn buffer_picker(cx: &mut Context) {
+    let current = view!(cx.editor).doc;
+
+    struct BufferMeta {
+        id: DocumentId,
+        path: Option<PathBuf>,
+        is_modified: bool,
+        is_current: bool,
+    }
+
+    impl BufferMeta {
+        fn format(&self) -> Cow<str> {
+            let path = self
+                .path
+                .as_deref()
+                .map(helix_core::path::get_relative_path);
+            let path = match path.as_deref().and_then(Path::to_str) {
+                Some(path) => path,
+                None => return Cow::Borrowed(SCRATCH_BUFFER_NAME),
+            };
+
+            let mut flags = Vec::new();
+            if self.is_modified {
+                flags.push("+");
+            }
+            if self.is_current {
+                flags.push("*");
+            }
+
+            let flag = if flags.is_empty() {
+                "".into()
+            } else {
+                format!(" ({})", flags.join(""))
+            };
+            Cow::Owned(format!("{}{}", path, flag))
+        }
+    }
+
+    let new_meta = |doc: &Document| BufferMeta {
+        id: doc.id(),
+        path: doc.path().cloned(),
+        is_modified: doc.is_modified(),
+        is_current: doc.id() == current,
+    };
+
+    let picker = FilePicker::new(
+        cx.editor
+            .documents
+            .iter()
+            .map(|(_, doc)| new_meta(doc))
+            .collect(),
+        BufferMeta::format,
+        |editor: &mut Editor, meta, _action| {
+            editor.switch(meta.id, Action::Replace);
+        },
+        |editor, meta| {
+            let doc = &editor.documents.get(&meta.id)?;
+            let &view_id = doc.selections().keys().next()?;
+            let line = doc
+                .selection(view_id)
+                .primary()
+                .cursor_line(doc.text().slice(..));
+            Some((meta.path.clone()?, Some((line, line))))
+        },
+    );
+    cx.push_layer(Box::new(picker));
+}
+
+fn closed_buffer_picker(cx: &mut Context) {
+    if cx.editor.closed_documents.is_empty() {
+        cx.editor.set_status("No closed buffers".to_string());
+        return;
+    }
+
+    // most recently closed first
+    let closed = cx.editor.closed_documents.iter().rev().cloned().collect();
+    let picker = FilePicker::new(
+        closed,
+        |closed: &ClosedDocument| {
+            helix_core::path::get_relative_path(&closed.path)
+                .to_string_lossy()
+                .into_owned()
+                .into()
+        },
+        |editor: &mut Editor, closed, action| match editor.reopen(closed.clone(), action) {
+            Ok(_) => editor
+                .closed_documents
+                .retain(|other| other.path != closed.path),
+            Err(e) => editor.set_error(format!("Failed to reopen buffer: {}", e)),
+        },
+        |_editor, closed| {
+            let line = closed.offset.row;
+            Some((closed.path.clone(), Some((line, line))))
+        },
+    );
+    cx.push_layer(Box::new(picker));
+}
+
+fn yank_history_picker(cx: &mut Context) {
+    let history = &cx.editor.registers.history;
+    if history.is_empty() {
+        cx.editor.set_status("Nothing yanked yet".to_string());
+        return;
+    }
+
+    let entries = history.iter().cloned().enumerate().collect();
+    let picker = FilePicker::with_text_preview(
+        entries,
+        |(index, entry): &(usize, YankEntry)| {
+            let first_line = entry.values[0].lines().next().unwrap_or_default();
+            let mut label = format!(
+                "{:>2} {} {}",
+                index + 1,
+                if entry.pinned { "*" } else { " " },
+                first_line.trim()
+            );
+            if entry.values.len() > 1 {
+                label.push_str(&format!(" ({} selections)", entry.values.len()));
+            }
+            label.into()
+        },
+        |editor: &mut Editor, (_, entry), _action| {
+            let reindent = editor.config.paste_reindent;
+            let escape_strings = editor.config.paste_escape_strings;
+            let (view, doc) = current!(editor);
+            if let Some(transaction) = paste_impl(
+                &entry.values,
+                doc,
+                view,
+                Paste::After,
+                1,
+                reindent,
+                escape_strings,
+            ) {
+                doc.apply(&transaction, view.id);
+                doc.append_changes_to_history(view.id);
+            }
+            // make the entry the one `p` pastes next
+            editor.registers.write('"', entry.values.clone());
+        },
+        |(_, entry)| entry.values.join("\n"),
+    );
+    cx.push_layer(Box::new(picker));
+}
+
+fn templates_dir() -> PathBuf {
+    helix_core::config_dir().join("templates")
+}
+
+/// Opens a new buffer filled in from `template`, at `path` if given.
+fn create_from_template(
+    editor: &mut Editor,
+    template: &Path,
+    path: Option<&Path>,
+    action: Action,
+) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(template)
+        .with_context(|| format!("Failed to read template {}", template.display()))?;
+
+    match path {
+        Some(path) => {
+            ensure!(!path.exists(), "{} already exists", path.display());
+            editor.open(path.to_path_buf(), action)?;
+        }
+        None => {
+            editor.new_file(action);
+        }
+    }
+
+    let (view, doc) = current!(editor);
+    let root = helix_core::find_root(None, &[]).unwrap_or_default();
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let variables = helix_core::template::variables(doc.path().map(PathBuf::as_path), &root, &date);
+    let text = helix_core::template::expand(&contents, &variables);
+
+    let transaction = Transaction::change(
+        doc.text(),
+        std::iter::once((0, doc.text().len_chars(), Some(text.into()))),
+    );
+    doc.apply(&transaction, view.id);
+    doc.set_selection(view.id, Selection::point(0));
+    doc.append_changes_to_history(view.id);
+    Ok(())
+}
+
+/// The notes file of the current workspace, kept in the data directory so that no file is
+/// added to the workspace itself.
+fn notes_path() -> PathBuf {
+    let workspace = helix_core::find_root(None, &[]).unwrap_or_default();
+    // flatten the workspace path into a file name, like vim's undo files
+    let name: String = workspace
+        .to_string_lossy()
+        .chars()
+        .map(|c| {
+            if std::path::is_separator(c) || c == ':' {
+                '%'
+            } else {
+                c
+            }
+        })
+        .collect();
+    helix_core::data_dir()
+        .join("notes")
+        .join(format!("{}.md", name))
+}
+
+fn toggle_notes(cx: &mut Context) {
+    let path = notes_path();
+
+    let notes_view = cx
+        .editor
+        .tree
+        .views()
+        .find(|(view, _focus)| cx.editor.documents[&view.doc].path() == Some(&path))
+        .map(|(view, _focus)| view.id);
+
+    if let Some(view_id) = notes_view {
+        if cx.editor.tree.views().count() == 1 {
+            cx.editor
+                .set_error("Can't hide the notes, they're the only open split".to_string());
+            return;
+        }
+        let focus = cx.editor.tree.focus;
+        cx.editor.tree.focus = view_id;
+        cx.editor.close(view_id);
+        if focus != view_id {
+            cx.editor.tree.focus = focus;
+        }
+        return;
+    }
+
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            cx.editor
+                .set_error(format!("Failed to create the notes directory: {}", e));
+            return;
+        }
+    }
+    if let Err(e) = cx.editor.open(path, Action::VerticalSplit) {
+        cx.editor
+            .set_error(format!("Failed to open the notes: {}", e));
+    }
+}
+
+/// Walks the files of the workspace, the current directory, skipping those the file picker
+/// would hide.
+fn workspace_walker(config: &FilePickerConfig) -> std::io::Result<WalkBuilder> {
+    let mut walker = WalkBuilder::new(std::env::current_dir()?);
+    walker
+        .hidden(config.hidden)
+        .parents(config.parents)
+        .ignore(config.ignore)
+        .git_ignore(config.git_ignore)
+        .git_global(config.git_global)
+        .git_exclude(config.git_exclude)
+        .max_depth(config.max_depth);
+    Ok(walker)
+}
+
+/// Finds the notes in the comments of the files of the workspace, skipping the files the
+/// file picker would hide and those without a highlight query.
+fn scan_todos(
+    config: &FilePickerConfig,
+    loader: &helix_core::syntax::Loader,
+    scopes: &[String],
+) -> Vec<(PathBuf, todo::Todo)> {
+    let walker = match workspace_walker(config) {
+        Ok(walker) => walker.build(),
+        Err(_) => return Vec::new(),
+    };
+
+    let mut todos = Vec::new();
+    for entry in walker.flatten() {
+        if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+            continue;
+        }
+        let path = entry.into_path();
+        let highlight_config = match loader
+            .language_config_for_file_name(&path)
+            .and_then(|config| config.highlight_config(scopes))
+        {
+            Some(highlight_config) => highlight_config,
+            None => continue,
+        };
+        // binary files aren't valid UTF-8 and are skipped too
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) if todo::mentions_keyword(&contents) => contents,
+            _ => continue,
+        };
+        let text = Rope::from(contents);
+        let syntax = helix_core::Syntax::new(&text, highlight_config);
+        todos.extend(
+            todo::find(&text, &syntax)
+                .into_iter()
+                .map(|todo| (path.clone(), todo)),
+        );
+    }
+    todos
+}
+
+/// Finds the matches of `regex` in the files of the workspace, except for the `open` ones.
+fn scan_replace(
+    config: &FilePickerConfig,
+    regex: &Regex,
+    replacement: &str,
+    open: &HashSet<PathBuf>,
+) -> Vec<(PathBuf, replace::Hunk)> {
+    let walker = match workspace_walker(config) {
+        Ok(walker) => walker.build(),
+        Err(_) => return Vec::new(),
+    };
+
+    let mut hunks = Vec::new();
+    for entry in walker.flatten() {
+        if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+            continue;
+        }
+        let path = entry.into_path();
+        if open.contains(&path) {
+            continue;
+        }
+        // binary files aren't valid UTF-8 and are skipped too
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) if regex.is_match(&contents) => contents,
+            _ => continue,
+        };
+        let text = Rope::from(contents);
+        hunks.extend(
+            replace::find_hunks(&text, regex, replacement)
+                .into_iter()
+                .map(|hunk| (path.clone(), hunk)),
+        );
+    }
+    hunks
+}
+
+/// Applies the hunks checked in the staging list of `:global-replace`, as one change in the
+/// history of each file, and records them for `:global-replace-undo`.
+fn apply_replace(editor: &mut Editor, hunks: Vec<(PathBuf, replace::Hunk)>) {
+    let mut files: BTreeMap<&PathBuf, Vec<&replace::Hunk>> = BTreeMap::new();
+    for (path, hunk) in &hunks {
+        files.entry(path).or_default().push(hunk);
+    }
+
+    let mut journal = Vec::new();
+    let mut failed = Vec::new();
+    let mut replaced = 0;
+    for (path, hunks) in files {
+        let doc_id = match editor.open(path.clone(), Action::Load) {
+            Ok(doc_id) => doc_id,
+            Err(err) => {
+                failed.push(format!("{}: {}", path.display(), err));
+                continue;
+            }
+        };
+        let doc = match editor.document_mut(doc_id) {
+            Some(doc) => doc,
+            None => continue,
+        };
+        let view_id = match doc.selections().keys().next().copied() {
+            Some(view_id) => view_id,
+            None => continue,
+        };
+        match replace::apply(doc.text(), &hunks) {
+            Some(transaction) => {
+                doc.apply(&transaction, view_id);
+                doc.append_changes_to_history(view_id);
+                journal.push((doc_id, doc.current_revision()));
+                replaced += hunks.len();
+            }
+            None => failed.push(format!("{}: changed since the search", path.display())),
+        }
+    }
+
+    let message = format!(
+        "Replaced {} match(es) in {} file(s), write them with :write-all",
+        replaced,
+        journal.len()
+    );
+    editor.replace_journal = journal;
+    if failed.is_empty() {
+        editor.set_status(message);
+    } else {
+        editor.set_error(format!("{}. Skipped {}", message, failed.join(", ")));
+    }
+}
+
+fn todo_picker(cx: &mut Context) {
+    let file_picker_config = cx.editor.config.file_picker.clone();
+    let loader = cx.editor.syn_loader.clone();
+    let scopes = cx.editor.theme.scopes().to_vec();
+
+    let callback = async move {
+        let scanned =
+            tokio::task::spawn_blocking(move || scan_todos(&file_picker_config, &loader, &scopes))
+                .await?;
+        let call: job::Callback =
+            Box::new(move |editor: &mut Editor, compositor: &mut Compositor| {
+                // open buffers are listed as they are now, with unsaved changes
+                let mut todos = Vec::new();
+                let mut open = HashSet::new();
+                for doc in editor.documents() {
+                    if let (Some(path), Some(syntax)) = (doc.path(), doc.syntax()) {
+                        open.insert(path.clone());
+                        todos.extend(
+                            todo::find(doc.text(), syntax)
+                                .into_iter()
+                                .map(|todo| (path.clone(), todo)),
+                        );
+                    }
+                }
+                todos.extend(scanned.into_iter().filter(|(path, _)| !open.contains(path)));
+                if todos.is_empty() {
+                    editor.set_status("No TODO comments found".to_string());
+                    return;
+                }
+                // grouped by file
+                todos.sort_by(|(a_path, a), (b_path, b)| {
+                    a_path.cmp(b_path).then(a.line.cmp(&b.line))
+                });
+
+                let picker = FilePicker::new(
+                    todos,
+                    |(path, todo)| {
+                        format!(
+                            "{}:{} {}: {}",
+                            helix_core::path::get_relative_path(path).display(),
+                            todo.line + 1,
+                            todo.keyword,
+                            todo.text
+                        )
+                        .into()
+                    },
+                    |editor: &mut Editor, (path, todo), action| {
+                        push_jump(editor);
+                        if let Err(e) = editor.open(path.clone(), action) {
+                            editor.set_error(format!(
+                                "Failed to open file '{}': {}",
+                                path.display(),
+                                e
+                            ));
+                            return;
+                        }
+                        let (view, doc) = current!(editor);
+                        let text = doc.text();
+                        // the buffer may have been edited since the picker was opened, so the
+                        // note is looked up again and the nearest one with the same text wins
+                        let line = doc
+                            .syntax()
+                            .and_then(|syntax| {
+                                todo::find(text, syntax)
+                                    .into_iter()
+                                    .filter(|found| {
+                                        found.keyword == todo.keyword && found.text == todo.text
+                                    })
+                                    .min_by_key(|found| {
+                                        (found.line as isize - todo.line as isize).abs()
+                                    })
+                            })
+                            .map_or(todo.line, |found| found.line);
+                        let line = line.min(text.len_lines().saturating_sub(1));
+                        let pos = text.line_to_char(line);
+                        doc.set_selection(view.id, Selection::point(pos));
+                        align_view(doc, view, Align::Center);
+                    },
+                    |_editor, (path, todo)| Some((path.clone(), Some((todo.line, todo.line)))),
+                );
+                compositor.push(Box::new(picker));
+            });
+        Ok(call)
+    };
+    cx.jobs
+        .add(Job::with_callback(callback).named(&cx.editor.tasks, "todos"));
 }
 
-fn file_picker(cx: &mut Context) {
-    // We don't specify language markers, root will be the root of the current git repo
-    let root = find_root(None, &[]).unwrap_or_else(|| PathBuf::from("./"));
-    let picker = ui::file_picker(root, &cx.editor.config);
+fn job_picker(cx: &mut Context) {
+    use helix_view::tasks::Task;
+
+    let tasks = cx.editor.tasks.list();
+    if tasks.is_empty() {
+        cx.editor.set_status("No running jobs".to_string());
+        return;
+    }
+
+    let picker = Picker::new(
+        true,
+        tasks,
+        |task: &Task| format!("{} ({}s)", task.name, task.started.elapsed().as_secs()).into(),
+        |editor: &mut Editor, task, _action| {
+            if editor.tasks.cancel(task.id) {
+                editor.set_status(format!("Cancelled {}", task.name));
+            }
+        },
+    );
     cx.push_layer(Box::new(picker));
 }
 
-fn buffer_picker(cx: &mut Context) {
-    let current = view!(cx.editor).doc;
-
-    struct BufferMeta {
-        id: DocumentId,
-        path: Option<PathBuf>,
-        is_modified: bool,
-        is_current: bool,
+fn cancel_jobs(cx: &mut Context) {
+    match cx.editor.tasks.cancel_all() {
+        0 => cx.editor.set_status("No running jobs".to_string()),
+        1 => cx.editor.set_status("Cancelled 1 job".to_string()),
+        n => cx.editor.set_status(format!("Cancelled {} jobs", n)),
     }
+}
 
-    impl BufferMeta {
-        fn format(&self) -> Cow<str> {
-            let path = self
-                .path
-                .as_deref()
-                .map(helix_core::path::get_relative_path);
-            let path = match path.as_deref().and_then(Path::to_str) {
-                Some(path) => path,
-                None => return Cow::Borrowed(SCRATCH_BUFFER_NAME),
-            };
+fn bind_key(cx: &mut Context) {
+    use crate::keymap::KeyTrie;
 
-            let mut flags = Vec::new();
-            if self.is_modified {
-                flags.push("+");
-            }
-            if self.is_current {
-                flags.push("*");
-            }
+    cx.editor.set_status("Press the key to bind".to_string());
+    cx.on_next_key(move |cx, event| {
+        cx.callback = Some(Box::new(
+            move |compositor: &mut Compositor, _cx: &mut compositor::Context| {
+                let bound = compositor
+                    .find::<ui::EditorView>()
+                    .and_then(|editor_view| {
+                        let keymap = editor_view.keymaps_mut().get(&Mode::Normal)?;
+                        match keymap.root().search(&[event])? {
+                            KeyTrie::Leaf(command) => Some(format!(" (now {})", command.name())),
+                            KeyTrie::Node(node) => Some(format!(" (now {})", node.name())),
+                            KeyTrie::Sequence(_) => Some(" (now multiple commands)".to_string()),
+                        }
+                    })
+                    .unwrap_or_default();
+                let prompt = Prompt::new(
+                    format!("bind {}{} to:", event, bound).into(),
+                    None,
+                    ui::completers::command,
+                    move |cx: &mut compositor::Context, input: &str, event_kind: PromptEvent| {
+                        if event_kind != PromptEvent::Validate {
+                            return;
+                        }
+                        let result = input
+                            .parse()
+                            .and_then(|command| bind_impl(cx, Mode::Normal, vec![event], command));
+                        if let Err(err) = result {
+                            cx.editor.set_error(err.to_string());
+                        }
+                    },
+                );
+                compositor.push(Box::new(prompt));
+            },
+        ));
+    });
+}
 
-            let flag = if flags.is_empty() {
-                "".into()
-            } else {
-                format!(" ({})", flags.join(""))
-            };
-            Cow::Owned(format!("{}{}", path, flag))
-        }
-    }
+/// Binds `keys` to `command` in the keymap of `mode`, and saves the binding
+/// to the `[keys.<mode>]` section of config.toml.
+fn bind_impl(
+    cx: &mut compositor::Context,
+    mode: Mode,
+    keys: Vec<KeyEvent>,
+    command: MappableCommand,
+) -> anyhow::Result<()> {
+    use crate::keymap::KeyTrie;
 
-    let new_meta = |doc: &Document| BufferMeta {
-        id: doc.id(),
-        path: doc.path().cloned(),
-        is_modified: doc.is_modified(),
-        is_current: doc.id() == current,
+    // keys like `A-,` must be quoted
+    let toml_key = |key: &KeyEvent| {
+        let key = key.to_string();
+        if key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            key
+        } else {
+            toml::Value::String(key).to_string()
+        }
+    };
+    let (last, prefix) = keys.split_last().context("No keys to bind")?;
+    let mut table = format!("keys.{}", mode);
+    for key in prefix {
+        table.push('.');
+        table.push_str(&toml_key(key));
+    }
+    let name = match &command {
+        MappableCommand::Typable { name, args, .. } => std::iter::once(format!(":{}", name))
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" "),
+        MappableCommand::Static { name, .. } => name.to_string(),
     };
 
-    let picker = FilePicker::new(
-        cx.editor
-            .documents
-            .iter()
-            .map(|(_, doc)| new_meta(doc))
-            .collect(),
-        BufferMeta::format,
-        |editor: &mut Editor, meta, _action| {
-            editor.switch(meta.id, Action::Replace);
-        },
-        |editor, meta| {
-            let doc = &editor.documents.get(&meta.id)?;
-            let &view_id = doc.selections().keys().next()?;
-            let line = doc
-                .selection(view_id)
-                .primary()
-                .cursor_line(doc.text().slice(..));
-            Some((meta.path.clone()?, Some((line, line))))
-        },
+    let path = helix_core::config_file();
+    let config = match std::fs::read_to_string(&path) {
+        Ok(config) => config,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).context("Failed to read config.toml"),
+    };
+    let config = crate::config::set_toml_value(
+        &config,
+        Some(&table),
+        &toml_key(last),
+        &toml::Value::String(name.clone()),
     );
-    cx.push_layer(Box::new(picker));
+    std::fs::create_dir_all(helix_core::config_dir())?;
+    std::fs::write(&path, config).context("Failed to write config.toml")?;
+
+    let keys_name = keys
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let callback = async move {
+        let call: job::Callback =
+            Box::new(move |editor: &mut Editor, compositor: &mut Compositor| {
+                let keymap = match compositor
+                    .find::<ui::EditorView>()
+                    .and_then(|editor_view| editor_view.keymaps_mut().get_mut(&mode))
+                {
+                    Some(keymap) => keymap,
+                    None => return,
+                };
+                let replaced = match keymap.bind(&keys, command) {
+                    Some(KeyTrie::Leaf(command)) => format!(", replacing {}", command.name()),
+                    Some(KeyTrie::Node(node)) => format!(", replacing {}", node.name()),
+                    Some(KeyTrie::Sequence(_)) => ", replacing multiple commands".to_string(),
+                    None => String::new(),
+                };
+                editor.set_status(format!("Bound {} to {}{}", keys_name, name, replaced));
+            });
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
 }
 
 fn symbol_picker(cx: &mut Context) {
@@ -3631,6 +5804,88 @@ pub fn apply_workspace_edit(
     }
 }
 
+/// A unified diff of the text edits in `workspace_edit`, one section per file, for previewing
+/// it before [`apply_workspace_edit`]. Files that aren't open are read from disk and resource
+/// operations are only listed.
+fn workspace_edit_diff(
+    editor: &Editor,
+    offset_encoding: OffsetEncoding,
+    workspace_edit: &lsp::WorkspaceEdit,
+) -> String {
+    let mut diff = String::new();
+    let mut diff_edits = |uri: &helix_lsp::Url, text_edits: Vec<lsp::TextEdit>| {
+        let path = match uri.to_file_path() {
+            Ok(path) => path,
+            Err(_) => return,
+        };
+        let old = match editor.documents().find(|doc| doc.path() == Some(&path)) {
+            Some(doc) => doc.text().clone(),
+            None => match std::fs::read_to_string(&path) {
+                Ok(text) => Rope::from(text),
+                Err(_) => Rope::new(),
+            },
+        };
+        let mut new = old.clone();
+        helix_lsp::util::generate_transaction_from_edits(&old, text_edits, offset_encoding)
+            .apply(&mut new);
+        let file_diff = helix_core::diff::unified_diff(&old, &new);
+        if !file_diff.is_empty() {
+            let path = helix_core::path::get_relative_path(&path);
+            diff.push_str(&format!("--- {0}\n+++ {0}\n{1}", path.display(), file_diff));
+        }
+    };
+    let annotated = |edits: &[lsp::OneOf<lsp::TextEdit, lsp::AnnotatedTextEdit>]| {
+        edits
+            .iter()
+            .map(|edit| match edit {
+                lsp::OneOf::Left(text_edit) => text_edit,
+                lsp::OneOf::Right(annotated_text_edit) => &annotated_text_edit.text_edit,
+            })
+            .cloned()
+            .collect()
+    };
+    let mut operations = Vec::new();
+
+    if let Some(ref changes) = workspace_edit.changes {
+        for (uri, text_edits) in changes {
+            diff_edits(uri, text_edits.to_vec());
+        }
+    }
+    match &workspace_edit.document_changes {
+        Some(lsp::DocumentChanges::Edits(document_edits)) => {
+            for document_edit in document_edits {
+                diff_edits(
+                    &document_edit.text_document.uri,
+                    annotated(&document_edit.edits),
+                );
+            }
+        }
+        Some(lsp::DocumentChanges::Operations(document_operations)) => {
+            for operation in document_operations {
+                match operation {
+                    lsp::DocumentChangeOperation::Op(op) => operations.push(match op {
+                        lsp::ResourceOp::Create(op) => format!("create {}", op.uri),
+                        lsp::ResourceOp::Rename(op) => {
+                            format!("rename {} to {}", op.old_uri, op.new_uri)
+                        }
+                        lsp::ResourceOp::Delete(op) => format!("delete {}", op.uri),
+                    }),
+                    lsp::DocumentChangeOperation::Edit(document_edit) => diff_edits(
+                        &document_edit.text_document.uri,
+                        annotated(&document_edit.edits),
+                    ),
+                }
+            }
+        }
+        None => (),
+    }
+
+    for operation in operations {
+        diff.push_str(&format!("# {}\n", operation));
+    }
+    diff
+}
+
 fn last_picker(cx: &mut Context) {
     // TODO: last picker does not seem to work well with buffer_picker
     cx.callback = Some(Box::new(|compositor: &mut Compositor, _| {
@@ -3965,15 +6220,16 @@ fn goto_impl(
     compositor: &mut Compositor,
     locations: Vec<lsp::Location>,
     offset_encoding: OffsetEncoding,
+    kind: &str,
 ) {
-    push_jump(editor);
-
     fn jump_to(
         editor: &mut Editor,
         location: &lsp::Location,
         offset_encoding: OffsetEncoding,
         action: Action,
     ) {
+        // only record the jump once we're actually going somewhere
+        push_jump(editor);
         let path = location
             .uri
             .to_file_path()
@@ -3999,7 +6255,7 @@ fn goto_impl(
             jump_to(editor, location, offset_encoding, Action::Replace);
         }
         [] => {
-            editor.set_error("No definition found.".to_string());
+            editor.set_error(format!("No {} found.", kind));
         }
         _locations => {
             let picker = FilePicker::new(
@@ -4021,7 +6277,7 @@ fn goto_impl(
                         })
                         .flatten()
                         .unwrap_or_else(|| location.uri.as_str().into());
-                    let line = location.range.start.line;
+                    let line = location.range.start.line + 1;
                     format!("{}:{}", file, line).into()
                 },
                 move |editor: &mut Editor, location, action| {
@@ -4078,7 +6334,7 @@ fn goto_definition(cx: &mut Context) {
                 None => Vec::new(),
             };
 
-            goto_impl(editor, compositor, items, offset_encoding);
+            goto_impl(editor, compositor, items, offset_encoding, "definition");
         },
     );
 }
@@ -4120,7 +6376,13 @@ fn goto_type_definition(cx: &mut Context) {
                 None => Vec::new(),
             };
 
-            goto_impl(editor, compositor, items, offset_encoding);
+            goto_impl(
+                editor,
+                compositor,
+                items,
+                offset_encoding,
+                "type definition",
+            );
         },
     );
 }
@@ -4162,7 +6424,7 @@ fn goto_implementation(cx: &mut Context) {
                 None => Vec::new(),
             };
 
-            goto_impl(editor, compositor, items, offset_encoding);
+            goto_impl(editor, compositor, items, offset_encoding, "implementation");
         },
     );
 }
@@ -4196,6 +6458,7 @@ fn goto_reference(cx: &mut Context) {
                 compositor,
                 items.unwrap_or_default(),
                 offset_encoding,
+                "references",
             );
         },
     );
@@ -4296,21 +6559,63 @@ fn signature_help(cx: &mut Context) {
     cx.callback(
         future,
         move |_editor: &mut Editor,
-              _compositor: &mut Compositor,
+              compositor: &mut Compositor,
               response: Option<lsp::SignatureHelp>| {
-            if let Some(signature_help) = response {
-                log::info!("{:?}", signature_help);
-                // signatures
-                // active_signature
-                // active_parameter
-                // render as:
-
-                // signature
-                // ----------
-                // doc
-
-                // with active param highlighted
-            }
+            compositor.remove(ui::SignatureHelp::ID);
+
+            let help = match response {
+                Some(help) => help,
+                None => return,
+            };
+            let signature = match help
+                .signatures
+                .get(help.active_signature.unwrap_or(0) as usize)
+            {
+                Some(signature) => signature,
+                None => return,
+            };
+
+            let active_param = signature
+                .active_parameter
+                .or(help.active_parameter)
+                .and_then(|index| signature.parameters.as_ref()?.get(index as usize))
+                .and_then(|param| match &param.label {
+                    lsp::ParameterLabel::Simple(label) => {
+                        let start = signature.label.find(label.as_str())?;
+                        Some(start..start + label.len())
+                    }
+                    lsp::ParameterLabel::LabelOffsets([start, end]) => {
+                        let byte_offset = |utf16_offset: u32| {
+                            let mut utf16_len = 0;
+                            for (i, ch) in signature.label.char_indices() {
+                                if utf16_len >= utf16_offset as usize {
+                                    return i;
+                                }
+                                utf16_len += ch.len_utf16();
+                            }
+                            signature.label.len()
+                        };
+                        // offsets past the label are clamped, reversed ones are ignored
+                        let (start, end) = (byte_offset(*start), byte_offset(*end));
+                        if start <= end {
+                            Some(start..end)
+                        } else {
+                            None
+                        }
+                    }
+                });
+            let documentation = match &signature.documentation {
+                Some(lsp::Documentation::String(contents))
+                | Some(lsp::Documentation::MarkupContent(lsp::MarkupContent {
+                    value: contents,
+                    ..
+                })) => contents.as_str(),
+                None => "",
+            };
+
+            let contents =
+                ui::SignatureHelp::new(signature.label.clone(), active_param, documentation);
+            compositor.push(Box::new(contents));
         },
     );
 }
@@ -4377,14 +6682,19 @@ pub mod insert {
             signature_help_provider:
                 Some(lsp::SignatureHelpOptions {
                     trigger_characters: Some(triggers),
-                    // TODO: retrigger_characters
+                    retrigger_characters,
                     ..
                 }),
             ..
         } = capabilities
         {
             // TODO: what if trigger is multiple chars long
-            let is_trigger = triggers.iter().any(|trigger| trigger.contains(ch));
+            // closing the call asks again, the popup closes when there's no signature
+            let is_trigger = ch == ')'
+                || triggers
+                    .iter()
+                    .chain(retrigger_characters.iter().flatten())
+                    .any(|trigger| trigger.contains(ch));
 
             if is_trigger {
                 super::signature_help(cx);
@@ -4437,6 +6747,14 @@ pub mod insert {
             }
         }
 
+        if c == '>' && matches!(doc.language_config(), Some(config) if config.auto_close_tags) {
+            close_tags(doc, view.id);
+        }
+
+        if matches!(c, ')' | ']' | '}') {
+            reindent_closing_bracket(doc, view.id);
+        }
+
         // TODO: need a post insert hook too for certain triggers (autocomplete, signature help, etc)
         // this could also generically look at Transaction, but it's a bit annoying to look at
         // Operation instead of Change.
@@ -4446,7 +6764,96 @@ pub mod insert {
         }
     }
 
+    /// Reindent lines where a closing bracket was just typed as their first
+    /// character, so that `}` ending a block lines up with its start. Only
+    /// languages with indent queries are reindented.
+    fn reindent_closing_bracket(doc: &mut Document, view_id: ViewId) {
+        let text = doc.text().slice(..);
+        let mut changes: Vec<helix_core::Change> = Vec::new();
+
+        for range in doc.selection(view_id) {
+            let pos = range.head;
+            if pos == 0 {
+                continue;
+            }
+            let bracket = pos - 1;
+            let line = text.char_to_line(bracket);
+            let line_start = text.line_to_char(line);
+            if matches!(changes.last(), Some(&(start, _, _)) if start == line_start) {
+                continue;
+            }
+            let before = text.slice(line_start..bracket);
+            if !before.chars().all(|ch| ch == ' ' || ch == '\t') {
+                continue;
+            }
+            let indent_level = match indent::suggested_indent_for_pos(
+                doc.language_config(),
+                doc.syntax(),
+                text,
+                bracket,
+                line,
+                false,
+            ) {
+                Some(indent_level) => indent_level,
+                None => return,
+            };
+            let indent = doc.indent_unit().repeat(indent_level);
+            if before != indent.as_str() {
+                changes.push((line_start, bracket, Some(indent.into())));
+            }
+        }
+
+        if !changes.is_empty() {
+            let transaction = Transaction::change(doc.text(), changes.into_iter());
+            doc.apply(&transaction, view_id);
+        }
+    }
+
+    /// Insert closing tags after cursors which follow a just completed
+    /// opening tag, leaving the cursors in between.
+    fn close_tags(doc: &mut Document, view_id: ViewId) {
+        // how far to look for the start of the tag
+        const MAX_TAG_LEN: usize = 1024;
+
+        let text = doc.text().slice(..);
+        let selection = doc.selection(view_id);
+        let mut changes = Vec::new();
+        let mut ranges = SmallVec::with_capacity(selection.len());
+        let mut offset = 0;
+
+        for range in selection {
+            ranges.push(Range::new(range.anchor + offset, range.head + offset));
+
+            let cursor = range.cursor(text);
+            let line_end = line_end_char_index(&text, text.char_to_line(cursor));
+            let after = Cow::from(text.slice(cursor..line_end));
+            let closing = match doc.syntax() {
+                Some(syntax) => tag::closing_tag_at(syntax, text, cursor, &after),
+                None => {
+                    let before = Cow::from(text.slice(cursor.saturating_sub(MAX_TAG_LEN)..cursor));
+                    tag::closing_tag(&before, &after)
+                }
+            };
+            if let Some(closing) = closing {
+                offset += closing.chars().count();
+                changes.push((cursor, cursor, Some(Tendril::from(closing))));
+            }
+        }
+
+        if changes.is_empty() {
+            return;
+        }
+        let transaction = Transaction::change(doc.text(), changes.into_iter())
+            .with_selection(Selection::new(ranges, selection.primary_index()));
+        doc.apply(&transaction, view_id);
+    }
+
     pub fn insert_tab(cx: &mut Context) {
+        let emmet = matches!(doc!(cx.editor).language_config(), Some(config) if config.emmet);
+        if emmet && expand_abbreviation_impl(cx) {
+            return;
+        }
+
         let (view, doc) = current!(cx.editor);
         // TODO: round out to nearest indentation level (for example a line with 3 spaces should
         // indent by one to reach 4 spaces).
@@ -4705,9 +7112,8 @@ fn yank(cx: &mut Context) {
         cx.register.unwrap_or('"')
     );
 
-    cx.editor
-        .registers
-        .write(cx.register.unwrap_or('"'), values);
+    let reg_name = cx.register.unwrap_or('"');
+    cx.editor.registers.yank(reg_name, values);
 
     cx.editor.set_status(msg);
     exit_select_mode(cx);
@@ -4792,12 +7198,16 @@ enum Paste {
     After,
 }
 
+/// Pastes `values` at the selections. With `reindent`, linewise pastes are
+/// reindented to the indentation a new line at the paste position would get.
 fn paste_impl(
     values: &[String],
     doc: &mut Document,
     view: &View,
     action: Paste,
     count: usize,
+    reindent: bool,
+    escape_strings: bool,
 ) -> Option<Transaction> {
     let repeat = std::iter::repeat(
         values
@@ -4837,7 +7247,40 @@ fn paste_impl(
             // paste append
             (Paste::After, false) => range.to(),
         };
-        (pos, pos, values.next())
+        let mut value = values.next();
+        if escape_strings && !linewise {
+            let quote = doc
+                .syntax()
+                .and_then(|syntax| escape::string_quote_at(syntax, text.slice(..), pos));
+            if let Some(quote) = quote {
+                value = value.map(|value| escape::escape_string(&value, quote).into());
+            }
+        }
+        if !(reindent && linewise) {
+            return (pos, pos, value);
+        }
+
+        let line = text.char_to_line(pos);
+        let indent_level = line
+            .checked_sub(1)
+            .and_then(|prev_line| {
+                indent::suggested_indent_for_pos(
+                    doc.language_config(),
+                    doc.syntax(),
+                    text.slice(..),
+                    line_end_char_index(&text.slice(..), prev_line),
+                    prev_line,
+                    true,
+                )
+            })
+            .unwrap_or_else(|| {
+                let cursor_line = range.cursor_line(text.slice(..));
+                indent::indent_level_for_line(text.line(cursor_line), doc.tab_width())
+            });
+        let indent = doc.indent_unit().repeat(indent_level);
+        let value =
+            value.map(|value| indent::reindent_block(&value, &indent, doc.tab_width()).into());
+        (pos, pos, value)
     });
 
     Some(transaction)
@@ -4849,13 +7292,24 @@ fn paste_clipboard_impl(
     clipboard_type: ClipboardType,
     count: usize,
 ) -> anyhow::Result<()> {
+    let reindent = editor.config.paste_reindent;
+    let escape_strings = editor.config.paste_escape_strings;
     let (view, doc) = current!(editor);
 
     match editor
         .clipboard_provider
         .get_contents(clipboard_type)
-        .map(|contents| paste_impl(&[contents], doc, view, action, count))
-    {
+        .map(|contents| {
+            paste_impl(
+                &[contents],
+                doc,
+                view,
+                action,
+                count,
+                reindent,
+                escape_strings,
+            )
+        }) {
         Ok(Some(transaction)) => {
             doc.apply(&transaction, view.id);
             doc.append_changes_to_history(view.id);
@@ -4969,34 +7423,38 @@ fn replace_selections_with_primary_clipboard(cx: &mut Context) {
     let _ = replace_selections_with_clipboard_impl(cx.editor, ClipboardType::Selection, cx.count());
 }
 
-fn paste_after(cx: &mut Context) {
+fn paste_register_impl(cx: &mut Context, action: Paste, reindent: bool) {
     let count = cx.count();
     let reg_name = cx.register.unwrap_or('"');
+    let escape_strings = cx.editor.config.paste_escape_strings;
     let (view, doc) = current!(cx.editor);
     let registers = &mut cx.editor.registers;
 
     if let Some(transaction) = registers
         .read(reg_name)
-        .and_then(|values| paste_impl(values, doc, view, Paste::After, count))
+        .and_then(|values| paste_impl(values, doc, view, action, count, reindent, escape_strings))
     {
         doc.apply(&transaction, view.id);
         doc.append_changes_to_history(view.id);
     }
 }
 
+fn paste_after(cx: &mut Context) {
+    let reindent = cx.editor.config.paste_reindent;
+    paste_register_impl(cx, Paste::After, reindent)
+}
+
 fn paste_before(cx: &mut Context) {
-    let count = cx.count();
-    let reg_name = cx.register.unwrap_or('"');
-    let (view, doc) = current!(cx.editor);
-    let registers = &mut cx.editor.registers;
+    let reindent = cx.editor.config.paste_reindent;
+    paste_register_impl(cx, Paste::Before, reindent)
+}
 
-    if let Some(transaction) = registers
-        .read(reg_name)
-        .and_then(|values| paste_impl(values, doc, view, Paste::Before, count))
-    {
-        doc.apply(&transaction, view.id);
-        doc.append_changes_to_history(view.id);
-    }
+fn paste_after_reindent(cx: &mut Context) {
+    paste_register_impl(cx, Paste::After, true)
+}
+
+fn paste_before_reindent(cx: &mut Context) {
+    paste_register_impl(cx, Paste::Before, true)
 }
 
 fn get_lines(doc: &Document, view_id: ViewId) -> Vec<usize> {
@@ -5123,30 +7581,46 @@ fn format_selections(cx: &mut Context) {
 }
 
 fn join_selections(cx: &mut Context) {
-    use movement::skip_while;
+    use movement::{backwards_skip_while, skip_while};
+    let count = cx.count();
     let (view, doc) = current!(cx.editor);
     let text = doc.text();
     let slice = doc.text().slice(..);
 
     let mut changes = Vec::new();
-    let fragment = Tendril::from(" ");
 
     for selection in doc.selection(view.id) {
         let (start, mut end) = selection.line_range(slice);
         if start == end {
-            end = (end + 1).min(text.len_lines() - 1);
+            // join the `count` following lines
+            end = (end + count).min(text.len_lines() - 1);
         }
         let lines = start..end;
 
         changes.reserve(lines.len());
 
         for line in lines {
-            let start = line_end_char_index(&slice, line);
+            let line_start = text.line_to_char(line);
+            let line_end = line_end_char_index(&slice, line);
+            // drop trailing whitespace, unless the line is nothing but whitespace
+            let start = backwards_skip_while(
+                slice.slice(line_start..line_end),
+                line_end - line_start,
+                |ch| matches!(ch, ' ' | '\t'),
+            )
+            .map_or(line_end, |end| line_start + end);
             let mut end = text.line_to_char(line + 1);
             end = skip_while(slice, end, |ch| matches!(ch, ' ' | '\t')).unwrap_or(end);
 
+            // no space is needed in front of an empty line
+            let next_line_is_empty = slice
+                .get_char(end)
+                .filter(|&ch| !helix_core::chars::char_is_line_ending(ch))
+                .is_none();
+            let fragment = if next_line_is_empty { "" } else { " " };
+
             // need to skip from start, not end
-            let change = (start, end, Some(fragment.clone()));
+            let change = (start, end, Some(Tendril::from(fragment)));
             changes.push(change);
         }
     }
@@ -5154,9 +7628,6 @@ fn join_selections(cx: &mut Context) {
     changes.sort_unstable_by_key(|(from, _to, _text)| *from);
     changes.dedup();
 
-    // TODO: joining multiple empty lines should be replaced by a single space.
-    // need to merge change ranges that touch
-
     let transaction = Transaction::change(doc.text(), changes.into_iter());
     // TODO: select inserted spaces
     // .with_selection(selection);
@@ -5260,19 +7731,8 @@ pub fn completion(cx: &mut Context) {
 
     let (view, doc) = current!(cx.editor);
 
-    let language_server = match doc.language_server() {
-        Some(language_server) => language_server,
-        None => return,
-    };
-
-    let offset_encoding = language_server.offset_encoding();
     let text = doc.text().slice(..);
     let cursor = doc.selection(view.id).primary().cursor(text);
-
-    let pos = pos_to_lsp_pos(doc.text(), cursor, offset_encoding);
-
-    let future = language_server.completion(doc.identifier(), pos, None);
-
     let trigger_offset = cursor;
 
     // TODO: trigger_offset should be the cursor offset but we also need a starting offset from where we want to apply
@@ -5285,17 +7745,45 @@ pub fn completion(cx: &mut Context) {
     let start_offset = cursor.saturating_sub(offset);
     let prefix = text.slice(start_offset..cursor).to_string();
 
+    let language_server = match doc.language_server() {
+        Some(language_server) => language_server,
+        None => {
+            // only snippets
+            let offset_encoding = OffsetEncoding::Utf8;
+            let snippets = snippet_completions(doc, start_offset, cursor, offset_encoding);
+            if snippets.is_empty() {
+                return;
+            }
+            let callback = async move {
+                let call: job::Callback =
+                    Box::new(move |editor: &mut Editor, compositor: &mut Compositor| {
+                        show_completion(
+                            editor,
+                            compositor,
+                            snippets,
+                            &prefix,
+                            offset_encoding,
+                            start_offset,
+                            trigger_offset,
+                        );
+                    });
+                Ok(call)
+            };
+            cx.jobs.callback(callback);
+            return;
+        }
+    };
+
+    let offset_encoding = language_server.offset_encoding();
+    let pos = pos_to_lsp_pos(doc.text(), cursor, offset_encoding);
+    let future = language_server.completion(doc.identifier(), pos, None);
+    let snippets = snippet_completions(doc, start_offset, cursor, offset_encoding);
+
     cx.callback(
         future,
         move |editor: &mut Editor,
               compositor: &mut Compositor,
               response: Option<lsp::CompletionResponse>| {
-            let doc = doc!(editor);
-            if doc.mode() != Mode::Insert {
-                // we're not in insert mode anymore
-                return;
-            }
-
             let mut items = match response {
                 Some(lsp::CompletionResponse::Array(items)) => items,
                 // TODO: do something with is_incomplete
@@ -5305,37 +7793,101 @@ pub fn completion(cx: &mut Context) {
                 })) => items,
                 None => Vec::new(),
             };
+            items.extend(snippets);
 
-            if !prefix.is_empty() {
-                items = items
-                    .into_iter()
-                    .filter(|item| {
-                        item.filter_text
-                            .as_ref()
-                            .unwrap_or(&item.label)
-                            .starts_with(&prefix)
-                    })
-                    .collect();
-            }
-
-            if items.is_empty() {
-                // editor.set_error("No completion available".to_string());
-                return;
-            }
-            let size = compositor.size();
-            let ui = compositor.find::<ui::EditorView>().unwrap();
-            ui.set_completion(
+            show_completion(
                 editor,
+                compositor,
                 items,
+                &prefix,
                 offset_encoding,
                 start_offset,
                 trigger_offset,
-                size,
             );
         },
     );
 }
 
+fn show_completion(
+    editor: &mut Editor,
+    compositor: &mut Compositor,
+    mut items: Vec<lsp::CompletionItem>,
+    prefix: &str,
+    offset_encoding: OffsetEncoding,
+    start_offset: usize,
+    trigger_offset: usize,
+) {
+    let doc = doc!(editor);
+    if doc.mode() != Mode::Insert {
+        // we're not in insert mode anymore
+        return;
+    }
+
+    if !prefix.is_empty() {
+        items.retain(|item| {
+            item.filter_text
+                .as_ref()
+                .unwrap_or(&item.label)
+                .starts_with(prefix)
+        });
+    }
+
+    if items.is_empty() {
+        // editor.set_error("No completion available".to_string());
+        return;
+    }
+    let size = compositor.size();
+    let ui = compositor.find::<ui::EditorView>().unwrap();
+    ui.set_completion(
+        editor,
+        items,
+        offset_encoding,
+        start_offset,
+        trigger_offset,
+        size,
+    );
+}
+
+/// The language id of the snippets of `doc`, `text` if it has no language.
+fn snippet_language(doc: &Document) -> &str {
+    doc.language_config()
+        .map_or("text", |config| config.language_id.as_str())
+}
+
+/// Completion items of the snippets of `doc`'s language, replacing the word from `start` to
+/// the `cursor`. The body of the snippet is shown as its detail.
+fn snippet_completions(
+    doc: &Document,
+    start: usize,
+    cursor: usize,
+    offset_encoding: OffsetEncoding,
+) -> Vec<lsp::CompletionItem> {
+    let snippets = match helix_core::snippets::load_cached(snippet_language(doc)) {
+        Ok(snippets) => snippets,
+        Err(err) => {
+            log::warn!("{}", err);
+            return Vec::new();
+        }
+    };
+    let range = range_to_lsp_range(doc.text(), Range::new(start, cursor), offset_encoding);
+    snippets
+        .iter()
+        .map(|(name, snippet)| lsp::CompletionItem {
+            label: snippet.prefix.clone(),
+            kind: Some(lsp::CompletionItemKind::SNIPPET),
+            detail: Some(snippet.body.clone()),
+            documentation: Some(lsp::Documentation::String(
+                snippet.description.clone().unwrap_or_else(|| name.clone()),
+            )),
+            text_edit: Some(lsp::CompletionTextEdit::Edit(lsp::TextEdit {
+                range,
+                new_text: snippet.body.clone(),
+            })),
+            ..Default::default()
+        })
+        .collect()
+}
+
 fn hover(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
 
@@ -5399,6 +7951,136 @@ fn hover(cx: &mut Context) {
     );
 }
 
+/// Request references to the symbol under the primary cursor and store them in the view so
+/// they're highlighted until the next keypress.
+pub fn document_highlight(cx: &mut Context) {
+    let (view, doc) = current!(cx.editor);
+
+    let language_server = match doc.language_server() {
+        Some(language_server) => language_server,
+        None => return,
+    };
+
+    if language_server
+        .capabilities()
+        .document_highlight_provider
+        .is_none()
+    {
+        return;
+    }
+
+    let offset_encoding = language_server.offset_encoding();
+    let cursor = doc
+        .selection(view.id)
+        .primary()
+        .cursor(doc.text().slice(..));
+    let pos = pos_to_lsp_pos(doc.text(), cursor, offset_encoding);
+
+    let future = language_server.text_document_document_highlight(doc.identifier(), pos, None);
+
+    let view_id = view.id;
+    let doc_id = doc.id();
+    let version = doc.version();
+    cx.callback(
+        future,
+        move |editor: &mut Editor,
+              _compositor: &mut Compositor,
+              response: Option<Vec<lsp::DocumentHighlight>>| {
+            let (view, doc) = current!(editor);
+            // the cursor moved or the document was edited in the meantime
+            if view.id != view_id
+                || doc.id() != doc_id
+                || doc.version() != version
+                || doc
+                    .selection(view_id)
+                    .primary()
+                    .cursor(doc.text().slice(..))
+                    != cursor
+            {
+                return;
+            }
+
+            view.document_highlights = response
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|highlight| {
+                    lsp_range_to_range(doc.text(), highlight.range, offset_encoding)
+                })
+                .collect();
+            view.document_highlights.sort_by_key(|range| range.from());
+        },
+    );
+}
+
+const DELETED_FILES_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Checks whether the files of the open documents still exist, and asks what to do with the
+/// buffers whose file was deleted or renamed by another program since the last check.
+pub fn detect_deleted_files(cx: &mut Context) {
+    // every open file is checked, so don't do it on every idle timeout
+    if cx.editor.deleted_files_checked.elapsed() < DELETED_FILES_CHECK_INTERVAL {
+        return;
+    }
+    cx.editor.deleted_files_checked = tokio::time::Instant::now();
+
+    let deleted: Vec<_> = cx
+        .editor
+        .documents_mut()
+        .filter_map(|doc| {
+            if doc.detect_deleted() {
+                let name = doc.relative_path()?.to_string_lossy().to_string();
+                Some((doc.id(), name))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    for (doc_id, name) in deleted {
+        cx.editor
+            .set_error(format!("{} was deleted or moved on disk", name));
+        let callback = async move {
+            let call: job::Callback =
+                Box::new(move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    let choices =
+                        ui::Choices::new(format!("{} was deleted or moved on disk.", name))
+                            .choice('w', "write it back", move |_, cx| {
+                                if let Err(err) = cmd::write_document_impl(cx, doc_id, None) {
+                                    cx.editor.set_error(err.to_string());
+                                }
+                            })
+                            .choice('s', "save elsewhere", move |compositor, _| {
+                                compositor.push(Box::new(save_elsewhere_prompt(doc_id)));
+                            })
+                            .choice('c', "close the buffer", move |_, cx| {
+                                if let Err(err) = cx.editor.close_document(doc_id, true) {
+                                    cx.editor.set_error(err.to_string());
+                                }
+                            });
+                    compositor.push(Box::new(Popup::new("deleted-file", choices)));
+                });
+            Ok(call)
+        };
+        cx.jobs.callback(callback);
+    }
+}
+
+fn save_elsewhere_prompt(doc_id: DocumentId) -> Prompt {
+    Prompt::new(
+        "save as:".into(),
+        None,
+        ui::completers::filename,
+        move |cx: &mut compositor::Context, input: &str, event: PromptEvent| {
+            if event != PromptEvent::Validate {
+                return;
+            }
+            if let Err(err) = cmd::write_document_impl(cx, doc_id, Some(&Cow::from(input))) {
+                cx.editor.set_error(err.to_string());
+            }
+        },
+    )
+}
+
 // comments
 fn toggle_comments(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
@@ -5549,8 +8231,8 @@ fn jump_forward(cx: &mut Context) {
     let view = view_mut!(cx.editor);
 
     if let Some((id, selection)) = view.jumps.forward(count) {
-        view.doc = *id;
-        let selection = selection.clone();
+        let (id, selection) = (*id, selection.clone());
+        view.set_doc(id);
         let (view, doc) = current!(cx.editor); // refetch doc
         doc.set_selection(view.id, selection);
 
@@ -5564,11 +8246,11 @@ fn jump_backward(cx: &mut Context) {
 
     if let Some((id, selection)) = view.jumps.backward(view.id, doc, count) {
         // manually set the alternate_file as we cannot use the Editor::switch function here.
-        if view.doc != *id {
+        let (id, selection) = (*id, selection.clone());
+        if view.doc != id {
             view.last_accessed_doc = Some(view.doc)
         }
-        view.doc = *id;
-        let selection = selection.clone();
+        view.set_doc(id);
         let (view, doc) = current!(cx.editor); // refetch doc
         doc.set_selection(view.id, selection);
 
@@ -5586,6 +8268,10 @@ fn rotate_view(cx: &mut Context) {
     cx.editor.focus_next()
 }
 
+fn rotate_view_reverse(cx: &mut Context) {
+    cx.editor.focus_prev()
+}
+
 fn jump_view_right(cx: &mut Context) {
     cx.editor.focus_right()
 }
@@ -5877,82 +8563,108 @@ fn shell_keep_pipe(cx: &mut Context) {
             if input.is_empty() {
                 return;
             }
-            let (view, doc) = current!(cx.editor);
-            let selection = doc.selection(view.id);
-
-            let mut ranges = SmallVec::with_capacity(selection.len());
-            let old_index = selection.primary_index();
-            let mut index: Option<usize> = None;
+            let shell = shell.clone();
+            let cmd = input.to_string();
+            let name = cmd.clone();
+            let (view, doc) = current_ref!(cx.editor);
+            let view_id = view.id;
+            let doc_id = doc.id();
+            let version = doc.version();
+            let selection = doc.selection(view.id).clone();
             let text = doc.text().slice(..);
-
-            for (i, range) in selection.ranges().iter().enumerate() {
-                let fragment = range.fragment(text);
-                let (_output, success) = match shell_impl(shell, input, Some(fragment.as_bytes())) {
-                    Ok(result) => result,
-                    Err(err) => {
-                        cx.editor.set_error(err.to_string());
+            let fragments: Vec<_> = selection
+                .fragments(text)
+                .map(|fragment| fragment.as_bytes().to_vec())
+                .collect();
+
+            let callback = async move {
+                let mut kept = Vec::with_capacity(fragments.len());
+                for fragment in fragments {
+                    let (_output, success) =
+                        shell_impl_async(shell.clone(), cmd.clone(), Some(fragment)).await?;
+                    kept.push(success);
+                }
+                let call: job::Callback = Box::new(move |editor: &mut Editor, _| {
+                    let doc = match editor.document_mut(doc_id) {
+                        Some(doc) if doc.version() == version => doc,
+                        _ => {
+                            editor.set_error("Buffer changed, not applying".to_string());
+                            return;
+                        }
+                    };
+                    if !doc.selections().contains_key(&view_id) {
                         return;
                     }
-                };
 
-                // if the process exits successfully, keep the selection
-                if success {
-                    ranges.push(*range);
-                    if i >= old_index && index.is_none() {
-                        index = Some(ranges.len() - 1);
+                    let mut ranges = SmallVec::with_capacity(selection.len());
+                    let old_index = selection.primary_index();
+                    let mut index: Option<usize> = None;
+                    // if the process exits successfully, keep the selection
+                    for (i, range) in selection.ranges().iter().enumerate() {
+                        if kept[i] {
+                            ranges.push(*range);
+                            if i >= old_index && index.is_none() {
+                                index = Some(ranges.len() - 1);
+                            }
+                        }
                     }
-                }
-            }
 
-            if ranges.is_empty() {
-                cx.editor.set_error("No selections remaining".to_string());
-                return;
-            }
+                    if ranges.is_empty() {
+                        editor.set_error("No selections remaining".to_string());
+                        return;
+                    }
 
-            let index = index.unwrap_or_else(|| ranges.len() - 1);
-            doc.set_selection(view.id, Selection::new(ranges, index));
+                    let index = index.unwrap_or_else(|| ranges.len() - 1);
+                    doc.set_selection(view_id, Selection::new(ranges, index));
+                });
+                Ok(call)
+            };
+            cx.jobs
+                .add(Job::with_callback(callback).named(&cx.editor.tasks, name));
         },
     );
 
     cx.push_layer(Box::new(prompt));
 }
 
-fn shell_impl(
-    shell: &[String],
-    cmd: &str,
-    input: Option<&[u8]>,
-) -> anyhow::Result<(Tendril, bool)> {
-    use std::io::Write;
-    use std::process::{Command, Stdio};
+/// Runs `cmd` with `shell`, writing `input` to its stdin. Returns the output
+/// and whether it exited successfully.
+async fn shell_impl_async(
+    shell: Vec<String>,
+    cmd: String,
+    input: Option<Vec<u8>>,
+) -> anyhow::Result<(String, bool)> {
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+    use tokio::process::Command;
     ensure!(!shell.is_empty(), "No shell set");
 
-    let mut process = match Command::new(&shell[0])
+    let mut process = Command::new(&shell[0])
         .args(&shell[1..])
-        .arg(cmd)
+        .arg(&cmd)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
-    {
-        Ok(process) => process,
-        Err(e) => {
+        .map_err(|e| {
             log::error!("Failed to start shell: {}", e);
-            return Err(e.into());
-        }
-    };
+            e
+        })?;
+    let mut stdin = process.stdin.take().unwrap();
     if let Some(input) = input {
-        let mut stdin = process.stdin.take().unwrap();
-        stdin.write_all(input)?;
+        stdin.write_all(&input).await?;
     }
-    let output = process.wait_with_output()?;
+    drop(stdin);
+    let output = process.wait_with_output().await?;
 
     if !output.stderr.is_empty() {
         log::error!("Shell error: {}", String::from_utf8_lossy(&output.stderr));
     }
 
-    let tendril = Tendril::try_from_byte_slice(&output.stdout)
+    let stdout = String::from_utf8(output.stdout)
         .map_err(|_| anyhow!("Process did not output valid UTF-8"))?;
-    Ok((tendril, output.status.success()))
+    Ok((stdout, output.status.success()))
 }
 
 fn shell(cx: &mut Context, prompt: Cow<'static, str>, behavior: ShellBehavior) {
@@ -5972,46 +8684,72 @@ fn shell(cx: &mut Context, prompt: Cow<'static, str>, behavior: ShellBehavior) {
             if input.is_empty() {
                 return;
             }
-            let (view, doc) = current!(cx.editor);
-            let selection = doc.selection(view.id);
-
-            let mut changes = Vec::with_capacity(selection.len());
+            let shell = shell.clone();
+            let cmd = input.to_string();
+            let name = cmd.clone();
+            let ignore = behavior == ShellBehavior::Ignore;
+            let (view, doc) = current_ref!(cx.editor);
+            let view_id = view.id;
+            let doc_id = doc.id();
+            let version = doc.version();
             let text = doc.text().slice(..);
-
-            for range in selection.ranges() {
-                let fragment = range.fragment(text);
-                let (output, success) =
-                    match shell_impl(shell, input, pipe.then(|| fragment.as_bytes())) {
-                        Ok(result) => result,
-                        Err(err) => {
-                            cx.editor.set_error(err.to_string());
+            let ranges: Vec<_> = doc
+                .selection(view.id)
+                .iter()
+                .map(|range| {
+                    let (from, to) = match behavior {
+                        ShellBehavior::Replace => (range.from(), range.to()),
+                        ShellBehavior::Append => (range.to(), range.to()),
+                        _ => (range.from(), range.from()),
+                    };
+                    let input = pipe.then(|| range.fragment(text).as_bytes().to_vec());
+                    (from, to, input)
+                })
+                .collect();
+
+            // the commands run in a job so a slow one doesn't block the editor
+            let callback = async move {
+                let mut changes = Vec::with_capacity(ranges.len());
+                for (from, to, input) in ranges {
+                    let (output, success) =
+                        shell_impl_async(shell.clone(), cmd.clone(), input).await?;
+                    ensure!(success, "Command failed");
+                    changes.push((from, to, output));
+                }
+                let call: job::Callback = Box::new(move |editor: &mut Editor, _| {
+                    if ignore {
+                        return;
+                    }
+                    let doc = match editor.document_mut(doc_id) {
+                        Some(doc) if doc.version() == version => doc,
+                        _ => {
+                            editor.set_error("Buffer changed, not applying".to_string());
                             return;
                         }
                     };
-
-                if !success {
-                    cx.editor.set_error("Command failed".to_string());
-                    return;
-                }
-
-                let (from, to) = match behavior {
-                    ShellBehavior::Replace => (range.from(), range.to()),
-                    ShellBehavior::Insert => (range.from(), range.from()),
-                    ShellBehavior::Append => (range.to(), range.to()),
-                    _ => (range.from(), range.from()),
-                };
-                changes.push((from, to, Some(output)));
-            }
-
-            if behavior != ShellBehavior::Ignore {
-                let transaction = Transaction::change(doc.text(), changes.into_iter());
-                doc.apply(&transaction, view.id);
-                doc.append_changes_to_history(view.id);
-            }
-
-            // after replace cursor may be out of bounds, do this to
-            // make sure cursor is in view and update scroll as well
-            view.ensure_cursor_in_view(doc, cx.editor.config.scrolloff);
+                    if !doc.selections().contains_key(&view_id) {
+                        return;
+                    }
+                    let changes = changes
+                        .into_iter()
+                        .map(|(from, to, output)| (from, to, Some(Tendril::from(output))));
+                    let transaction = Transaction::change(doc.text(), changes);
+                    doc.apply(&transaction, view_id);
+                    doc.append_changes_to_history(view_id);
+
+                    // after replace cursor may be out of bounds, do this to
+                    // make sure cursor is in view and update scroll as well
+                    let scrolloff = editor.config.scrolloff;
+                    let doc = &editor.documents[&doc_id];
+                    if let Some((view, _)) = editor.tree.views_mut().find(|(v, _)| v.id == view_id)
+                    {
+                        view.ensure_cursor_in_view(doc, scrolloff);
+                    }
+                });
+                Ok(call)
+            };
+            cx.jobs
+                .add(Job::with_callback(callback).named(&cx.editor.tasks, name));
         },
     );
 
@@ -6210,3 +8948,156 @@ fn replay_macro(cx: &mut Context) {
         },
     ));
 }
+
+fn pick_color(cx: &mut Context) {
+    let (view, doc) = current!(cx.editor);
+    let text = doc.text().slice(..);
+    let cursor = doc.selection(view.id).primary().cursor(text);
+    let line = text.line(text.char_to_line(cursor));
+    let line_start = text.line_to_char(text.char_to_line(cursor));
+
+    let (range, color) =
+        match ui::ColorLiteral::find(&Cow::from(line), line.char_to_byte(cursor - line_start)) {
+            Some(found) => found,
+            None => {
+                cx.editor
+                    .set_error("No color literal under cursor".to_string());
+                return;
+            }
+        };
+    let from = line_start + line.byte_to_char(range.start);
+    let to = line_start + line.byte_to_char(range.end);
+    let (doc_id, view_id) = (doc.id(), view.id);
+
+    let picker = ui::ColorPicker::new(color, move |editor: &mut Editor, color| {
+        let doc = match editor.document_mut(doc_id) {
+            Some(doc) => doc,
+            None => return,
+        };
+        let transaction = Transaction::change(
+            doc.text(),
+            std::iter::once((from, to, Some(color.to_string().into()))),
+        );
+        doc.apply(&transaction, view_id);
+        doc.append_changes_to_history(view_id);
+    });
+    cx.push_layer(Box::new(Popup::new("color-picker", picker)));
+}
+
+fn expand_abbreviation(cx: &mut Context) {
+    if !expand_abbreviation_impl(cx) {
+        cx.editor
+            .set_error("No abbreviation before cursor".to_string());
+    }
+}
+
+/// Expand the Emmet abbreviation before each cursor, selecting the tab stops
+/// of the expansions. Returns false if nothing was expanded.
+fn expand_abbreviation_impl(cx: &mut Context) -> bool {
+    let (view, doc) = current!(cx.editor);
+    let text = doc.text().slice(..);
+    let selection = doc.selection(view.id);
+    let insert_mode = doc.mode() == Mode::Insert;
+
+    let mut changes = Vec::new();
+    let mut ranges = SmallVec::new();
+    let mut primary_index = 0;
+    // how much the changes so far have shifted later positions
+    let mut offset = 0isize;
+    let shift = |pos: usize, offset: isize| (pos as isize + offset) as usize;
+
+    for (i, range) in selection.iter().enumerate() {
+        if i == selection.primary_index() {
+            primary_index = ranges.len();
+        }
+
+        let cursor = range.cursor(text);
+        // in normal mode the abbreviation ends with the character under the cursor
+        let end = if insert_mode {
+            cursor
+        } else {
+            graphemes::next_grapheme_boundary(text, cursor)
+        };
+        let line_start = text.line_to_char(text.char_to_line(cursor));
+        let before = Cow::from(text.slice(line_start..end));
+        let start_byte = emmet::find_abbreviation_start(&before);
+        let start = line_start + before[..start_byte].chars().count();
+        let previous_end = changes.last().map_or(0, |&(_, end, _)| end);
+
+        let indent = &before[..before.len() - before.trim_start_matches([' ', '\t']).len()];
+
+        let expansion = match emmet::expand(&before[start_byte..], doc.indent_unit(), indent) {
+            Some(expansion) if start >= previous_end => expansion,
+            _ => {
+                ranges.push(Range::new(
+                    shift(range.anchor, offset),
+                    shift(range.head, offset),
+                ));
+                continue;
+            }
+        };
+
+        let new_start = shift(start, offset);
+        let len = expansion.text.chars().count();
+        if expansion.tab_stops.is_empty() {
+            ranges.push(Range::point(new_start + len));
+        } else {
+            ranges.extend(
+                expansion
+                    .tab_stops
+                    .iter()
+                    .map(|tab_stop| Range::point(new_start + tab_stop)),
+            );
+        }
+        offset += len as isize - (end - start) as isize;
+        changes.push((start, end, Some(Tendril::from(expansion.text))));
+    }
+
+    if changes.is_empty() {
+        return false;
+    }
+    let transaction = Transaction::change(doc.text(), changes.into_iter())
+        .with_selection(Selection::new(ranges, primary_index));
+    doc.apply(&transaction, view.id);
+    if !insert_mode {
+        doc.append_changes_to_history(view.id);
+    }
+    true
+}
+
+fn select_tag_names(cx: &mut Context) {
+    let (view, doc) = current!(cx.editor);
+    let text = doc.text().slice(..);
+    let cursor = doc.selection(view.id).primary().cursor(text);
+
+    let names = tag::find_tag_names(&Cow::from(text), text.char_to_byte(cursor));
+    match names {
+        Some((open, close)) => {
+            let range = |bytes: std::ops::Range<usize>| {
+                Range::new(text.byte_to_char(bytes.start), text.byte_to_char(bytes.end))
+            };
+            let selection = Selection::new(SmallVec::from_vec(vec![range(open), range(close)]), 0);
+            doc.set_selection(view.id, selection);
+        }
+        None => cx.editor.set_error("No matching tags found".to_string()),
+    }
+}
+
+// Sticky modifiers: latch a modifier for the next key so that chords can be
+// typed one key at a time. Running the same command again releases it.
+
+fn toggle_sticky_modifier(cx: &mut Context, modifier: KeyModifiers) {
+    cx.editor.sticky_modifiers.toggle(modifier);
+}
+
+fn sticky_ctrl(cx: &mut Context) {
+    toggle_sticky_modifier(cx, KeyModifiers::CONTROL)
+}
+
+fn sticky_alt(cx: &mut Context) {
+    toggle_sticky_modifier(cx, KeyModifiers::ALT)
+}
+
+fn sticky_shift(cx: &mut Context) {
+    toggle_sticky_modifier(cx, KeyModifiers::SHIFT)
+}
@@ -69,6 +69,13 @@ pub trait Component: Any + AnyComponent {
     fn id(&self) -> Option<&'static str> {
         None
     }
+
+    /// Whether the component captures input while it's on the layer stack. Events ignored by a
+    /// modal component are not propagated to the layers below it, so the document can't be
+    /// edited while e.g. a picker or a prompt is open.
+    fn is_modal(&self) -> bool {
+        false
+    }
 }
 
 use anyhow::Error;
@@ -130,14 +137,23 @@ impl Compositor {
         self.layers.pop()
     }
 
+    /// Removes the layer with the given id.
+    pub fn remove(&mut self, id: &'static str) -> Option<Box<dyn Component>> {
+        let idx = self
+            .layers
+            .iter()
+            .position(|layer| layer.id() == Some(id))?;
+        Some(self.layers.remove(idx))
+    }
+
     pub fn handle_event(&mut self, event: Event, cx: &mut Context) -> bool {
         // If it is a key event and a macro is being recorded, push the key event to the recording.
         if let (Event::Key(key), Some((_, keys))) = (event, &mut cx.editor.macro_recording) {
             keys.push(key.into());
         }
 
-        // propagate events through the layers until we either find a layer that consumes it or we
-        // run out of layers (event bubbling)
+        // propagate events through the layers until we either find a layer that consumes it, we
+        // hit a modal layer or we run out of layers (event bubbling)
         for layer in self.layers.iter_mut().rev() {
             match layer.handle_event(event, cx) {
                 EventResult::Consumed(Some(callback)) => {
@@ -145,6 +161,7 @@ impl Compositor {
                     return true;
                 }
                 EventResult::Consumed(None) => return true,
+                EventResult::Ignored if layer.is_modal() => return false,
                 EventResult::Ignored => false,
             };
         }
@@ -181,6 +198,12 @@ impl Compositor {
         (None, CursorKind::Hidden)
     }
 
+    /// Returns true if any layer is modal, so events can't reach the layers below it, such as
+    /// the editor.
+    pub fn has_modal(&self) -> bool {
+        self.layers.iter().any(|layer| layer.is_modal())
+    }
+
     pub fn has_component(&self, type_name: &str) -> bool {
         self.layers
             .iter()
@@ -275,3 +298,119 @@ impl dyn AnyComponent {
         self.as_any().is::<T>()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{keymap::Keymaps, ui};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use helix_view::{
+        editor::{Action, Config},
+        theme,
+    };
+    use std::sync::Arc;
+    use tui::terminal::{TerminalOptions, Viewport};
+
+    /// A modal layer ignoring every event.
+    struct Modal;
+
+    impl Component for Modal {
+        fn render(&mut self, _area: Rect, _frame: &mut Surface, _ctx: &mut Context) {}
+
+        fn is_modal(&self) -> bool {
+            true
+        }
+    }
+
+    fn editor() -> Editor {
+        let syn_loader = helix_core::syntax::Loader::new(helix_core::syntax::Configuration {
+            language: Vec::new(),
+        });
+        let mut editor = Editor::new(
+            Rect::new(0, 0, 80, 24),
+            Arc::new(theme::Loader::new("", "")),
+            Arc::new(syn_loader),
+            Config::default(),
+        );
+        editor.new_file(Action::VerticalSplit);
+        editor
+    }
+
+    /// A compositor with the editor as its only layer, drawing to a fixed area so no terminal
+    /// is needed.
+    fn compositor() -> Compositor {
+        let viewport = Viewport::fixed(Rect::new(0, 0, 80, 24));
+        let terminal = Terminal::with_options(
+            CrosstermBackend::new(stdout()),
+            TerminalOptions { viewport },
+        )
+        .unwrap();
+        Compositor {
+            layers: vec![Box::new(ui::EditorView::new(Keymaps::default()))],
+            terminal,
+            last_picker: None,
+        }
+    }
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn prompt() -> Box<ui::Prompt> {
+        Box::new(ui::Prompt::new(
+            "test:".into(),
+            None,
+            |_| Vec::new(),
+            |_, _, _| {},
+        ))
+    }
+
+    // the editor spawns tasks, so it needs a runtime
+    #[tokio::test]
+    async fn test_modal_blocks_edits() {
+        let mut editor = editor();
+        let mut jobs = Jobs::new();
+        let mut cx = Context {
+            editor: &mut editor,
+            scroll: None,
+            jobs: &mut jobs,
+        };
+        let mut compositor = compositor();
+        let text = |cx: &Context| cx.editor.documents().next().unwrap().text().to_string();
+
+        compositor.layers.push(Box::new(Modal));
+        assert!(compositor.has_modal());
+        for code in [KeyCode::Char('i'), KeyCode::Char('a')] {
+            assert!(!compositor.handle_event(key(code), &mut cx));
+        }
+        assert_eq!(text(&cx), "\n");
+
+        compositor.pop();
+        assert!(!compositor.has_modal());
+        for code in [KeyCode::Char('i'), KeyCode::Char('a')] {
+            assert!(compositor.handle_event(key(code), &mut cx));
+        }
+        assert_eq!(text(&cx), "a\n");
+    }
+
+    #[tokio::test]
+    async fn test_escape_pops_one_layer() {
+        let mut editor = editor();
+        let mut jobs = Jobs::new();
+        let mut cx = Context {
+            editor: &mut editor,
+            scroll: None,
+            jobs: &mut jobs,
+        };
+        let mut compositor = compositor();
+        compositor.layers.push(prompt());
+        compositor.layers.push(prompt());
+
+        assert!(compositor.handle_event(key(KeyCode::Esc), &mut cx));
+        assert_eq!(compositor.layers.len(), 2);
+        assert!(compositor.has_modal());
+        assert!(compositor.handle_event(key(KeyCode::Esc), &mut cx));
+        assert_eq!(compositor.layers.len(), 1);
+        assert!(!compositor.has_modal());
+    }
+}
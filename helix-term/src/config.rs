@@ -1,6 +1,9 @@
+use helix_core::{merge_toml_values, syntax};
 use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
-use crate::keymap::Keymaps;
+use crate::keymap::{merge_keys, Keymaps};
 
 #[derive(Debug, Default, Clone, PartialEq, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -20,6 +23,227 @@ pub struct LspConfig {
     pub display_messages: bool,
 }
 
+impl Config {
+    /// Loads the config at `path` merged with the default keymaps, or the
+    /// default config if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        match std::fs::read_to_string(path) {
+            Ok(source) => toml::from_str(&source)
+                .map(merge_keys)
+                .map_err(|err| ConfigError::from_toml(path, &source, &err)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(ConfigError::new(path, err.to_string())),
+        }
+    }
+}
+
+const BUILTIN_LANG_CONFIG_ERR: &str =
+    "Could not parse built-in languages.toml, something must be very wrong";
+
+fn builtin_lang_config() -> toml::Value {
+    toml::from_slice(include_bytes!("../../languages.toml")).expect(BUILTIN_LANG_CONFIG_ERR)
+}
+
+/// The built-in language config.
+pub fn default_lang_config() -> syntax::Configuration {
+    builtin_lang_config()
+        .try_into()
+        .expect(BUILTIN_LANG_CONFIG_ERR)
+}
+
+/// The languages.toml at `path` merged into the built-in language config.
+pub fn load_lang_config(path: &Path) -> Result<syntax::Configuration, ConfigError> {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(default_lang_config()),
+        Err(err) => return Err(ConfigError::new(path, err.to_string())),
+    };
+    let user_lang_config =
+        toml::from_str(&source).map_err(|err| ConfigError::from_toml(path, &source, &err))?;
+    merge_toml_values(builtin_lang_config(), user_lang_config)
+        .try_into()
+        .map_err(|err| ConfigError::from_toml(path, &source, &err))
+}
+
+/// Checks the config file and languages.toml in the config directory.
+pub fn validate() -> Vec<ConfigError> {
+    let dir = helix_core::config_dir();
+    let config = Config::load(&helix_core::config_file()).err();
+    let languages = load_lang_config(&dir.join("languages.toml")).err();
+    config.into_iter().chain(languages).collect()
+}
+
+/// A config file which couldn't be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    /// Zero-based line and column of the problem.
+    pub position: Option<(usize, usize)>,
+    /// Dotted path of the offending key, like `editor.scrolloff`.
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(path: &Path, message: String) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            position: None,
+            key: None,
+            message,
+        }
+    }
+
+    /// Splits the location out of a toml error about `source`, the contents of
+    /// the file at `path`.
+    pub fn from_toml(path: &Path, source: &str, err: &toml::de::Error) -> Self {
+        let mut message = err.to_string();
+        let mut position = err.line_col();
+        if position.is_some() {
+            if let Some(i) = message.rfind(" at line ") {
+                message.truncate(i);
+            }
+        }
+        let mut key = None;
+        if let Some(i) = message.rfind(" for key `") {
+            key = Some(
+                message[i + " for key `".len()..]
+                    .trim_end_matches('`')
+                    .to_string(),
+            );
+            message.truncate(i);
+        }
+
+        // unknown fields are reported at the start of their table, point at
+        // the field itself instead
+        let field = message
+            .strip_prefix("unknown field `")
+            .and_then(|rest| rest.split('`').next())
+            .map(str::to_string);
+        if let Some(field) = field {
+            let start = position.map_or(0, |(line, _)| line);
+            if let Some(found) = find_key(source, start, &field, position.is_some()) {
+                position = Some(found);
+            }
+            key = Some(match key {
+                Some(table) => format!("{}.{}", table, field),
+                None => field,
+            });
+        }
+
+        Self {
+            path: path.to_path_buf(),
+            position,
+            key,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some((line, col)) = self.position {
+            write!(f, ":{}:{}", line + 1, col + 1)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(key) = &self.key {
+            write!(f, " in `{}`", key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the line and column where `key` is set, starting at line `start`.
+/// With `same_table` the search stops at the next table header.
+fn find_key(source: &str, start: usize, key: &str, same_table: bool) -> Option<(usize, usize)> {
+    for (i, line) in source.lines().enumerate().skip(start) {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            if same_table && i > start {
+                return None;
+            }
+            continue;
+        }
+        let rest = trimmed
+            .strip_prefix(key)
+            .or_else(|| trimmed.strip_prefix(&format!("{:?}", key)));
+        if matches!(rest, Some(rest) if rest.trim_start().starts_with('=')) {
+            return Some((i, line.len() - trimmed.len()));
+        }
+    }
+    None
+}
+
+/// Returns the comment at the end of the `key = value` line `line`, with the
+/// whitespace before it, or an empty string if there is none. A `#` only
+/// starts the comment if the value before it is valid on its own, so ones
+/// inside strings are skipped.
+fn trailing_comment(line: &str) -> &str {
+    let value_start = match line.find('=') {
+        Some(i) => i + 1,
+        None => return "",
+    };
+    line.match_indices('#')
+        .map(|(i, _)| i)
+        .filter(|&i| i > value_start)
+        .find(|&i| toml::from_str::<toml::Value>(&format!("v = {}", &line[value_start..i])).is_ok())
+        .map(|i| &line[line[..i].trim_end().len()..])
+        .unwrap_or_default()
+}
+
+/// Sets `key` to `value` in the `table` (`None` for the top level) of the TOML
+/// document `config`, keeping everything else, including comments, as it is.
+/// A comment after the old value is kept after the new one.
+pub fn set_toml_value(config: &str, table: Option<&str>, key: &str, value: &toml::Value) -> String {
+    let entry = format!("{} = {}", key, value);
+
+    let mut lines: Vec<String> = config.lines().map(str::to_string).collect();
+    let mut current = None;
+    // line after the last entry of `table`, where a new key goes
+    let mut insert_at = if table.is_none() { Some(0) } else { None };
+    for (i, line) in lines.iter_mut().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            current = Some(
+                trimmed
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .trim()
+                    .to_string(),
+            );
+            if current.as_deref() == table {
+                insert_at = Some(i + 1);
+            }
+            continue;
+        }
+        if current.as_deref() != table {
+            continue;
+        }
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            insert_at = Some(i + 1);
+        }
+        let is_key =
+            matches!(trimmed.strip_prefix(key), Some(rest) if rest.trim_start().starts_with('='));
+        if is_key {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            *line = format!("{}{}{}", indent, entry, trailing_comment(line));
+            return lines.join("\n") + "\n";
+        }
+    }
+
+    match insert_at {
+        Some(i) => lines.insert(i, entry),
+        None => {
+            if matches!(lines.last(), Some(line) if !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", table.unwrap_or_default()));
+            lines.push(entry);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,4 +280,129 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn setting_toml_values() {
+        use toml::Value;
+
+        let config = "# theme\ntheme = \"onedark\"\n\n[editor]\n# scroll\nscrolloff = 5 # margin\n\n[keys.normal]\nx = \"delete\"\n";
+        let string = |s: &str| Value::String(s.to_string());
+
+        assert_eq!(
+            set_toml_value(config, Some("editor"), "scrolloff", &Value::Integer(8)),
+            config.replace("scrolloff = 5 # margin", "scrolloff = 8 # margin")
+        );
+        assert_eq!(
+            set_toml_value(config, Some("editor"), "mouse", &Value::Boolean(false)),
+            config.replace(
+                "scrolloff = 5 # margin\n",
+                "scrolloff = 5 # margin\nmouse = false\n"
+            )
+        );
+        assert_eq!(
+            set_toml_value(config, None, "theme", &string("base16 default")),
+            config.replace("onedark", "base16 default")
+        );
+        // a `#` in a string doesn't start a comment
+        assert_eq!(
+            set_toml_value("x = \"#a\"\t# color\n", None, "x", &string("#b")),
+            "x = \"#b\"\t# color\n"
+        );
+        assert_eq!(
+            set_toml_value(
+                config,
+                Some("lsp"),
+                "display-messages",
+                &Value::Boolean(true)
+            ),
+            format!("{}\n[lsp]\ndisplay-messages = true\n", config)
+        );
+        assert_eq!(
+            set_toml_value("", None, "theme", &string("onedark")),
+            "theme = \"onedark\"\n"
+        );
+        assert_eq!(
+            set_toml_value("[editor]\n", Some("editor"), "mouse", &Value::Boolean(true)),
+            "[editor]\nmouse = true\n"
+        );
+    }
+
+    #[test]
+    fn setting_toml_lists() {
+        use helix_view::editor::GutterType;
+
+        // the values `:config-set` saves come from the parsed settings, so lists are arrays
+        let mut editor = helix_view::editor::Config {
+            rulers: vec![80, 100],
+            gutters: vec![GutterType::LineNumbers],
+            ..Default::default()
+        };
+        let value = |editor: &helix_view::editor::Config, key: &str| {
+            toml::Value::try_from(editor).unwrap()[key].clone()
+        };
+
+        let config = set_toml_value("", Some("editor"), "rulers", &value(&editor, "rulers"));
+        assert_eq!(config, "[editor]\nrulers = [80, 100]\n");
+        let config = set_toml_value(
+            &config,
+            Some("editor"),
+            "gutters",
+            &value(&editor, "gutters"),
+        );
+        assert_eq!(
+            config,
+            "[editor]\nrulers = [80, 100]\ngutters = [\"line-numbers\"]\n"
+        );
+
+        editor.rulers = vec![80];
+        let config = set_toml_value(&config, Some("editor"), "rulers", &value(&editor, "rulers"));
+        let parsed = toml::from_str::<Config>(&config).unwrap();
+        assert_eq!(parsed.editor.rulers, vec![80]);
+        assert_eq!(parsed.editor.gutters, vec![GutterType::LineNumbers]);
+    }
+
+    #[test]
+    fn config_error_locations() {
+        let path = Path::new("config.toml");
+        let error = |source| {
+            let err = toml::from_str::<Config>(source).unwrap_err();
+            ConfigError::from_toml(path, source, &err)
+        };
+
+        let err = error("theme = \"onedark\"\n\n[editor]\nscrolloff = 5\n  mosue = true\n");
+        assert_eq!(err.position, Some((4, 2)));
+        assert_eq!(err.key.as_deref(), Some("editor.mosue"));
+        assert!(err.message.starts_with("unknown field `mosue`"));
+
+        let err = error("[editor]\nscrolloff = \"x\"\n");
+        assert_eq!(err.position, Some((1, 12)));
+        assert_eq!(err.key.as_deref(), Some("editor.scrolloff"));
+        assert_eq!(
+            err.to_string(),
+            "config.toml:2:13: invalid type: string \"x\", expected usize in `editor.scrolloff`"
+        );
+
+        let err = error("theme = \n");
+        assert_eq!(err.position, Some((0, 8)));
+        assert_eq!(err.key, None);
+    }
+
+    #[test]
+    fn parsing_cursor_shape() {
+        use helix_view::{document::Mode, graphics::CursorKind};
+
+        let config: Config = toml::from_str("[editor.cursor-shape]\ninsert = \"bar\"\n").unwrap();
+        let shape = config.editor.cursor_shape;
+        assert_eq!(shape.from_mode(Mode::Normal), CursorKind::Block);
+        assert_eq!(shape.from_mode(Mode::Insert), CursorKind::Bar);
+    }
+
+    #[test]
+    fn parsing_gutters() {
+        use helix_view::editor::GutterType;
+
+        let config: Config = toml::from_str("[editor]\ngutters = [\"line-numbers\"]\n").unwrap();
+        assert_eq!(config.editor.gutters, vec![GutterType::LineNumbers]);
+        assert!(toml::from_str::<Config>("[editor]\ngutters = [\"breakpoints\"]\n").is_err());
+    }
 }
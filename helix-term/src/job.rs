@@ -1,8 +1,8 @@
-use helix_view::Editor;
+use helix_view::{tasks::Tasks, Editor};
 
 use crate::compositor::Compositor;
 
-use futures_util::future::{self, BoxFuture, Future, FutureExt};
+use futures_util::future::{self, AbortHandle, Abortable, BoxFuture, Future, FutureExt};
 use futures_util::stream::{FuturesUnordered, StreamExt};
 
 pub type Callback = Box<dyn FnOnce(&mut Editor, &mut Compositor) + Send>;
@@ -38,6 +38,21 @@ impl Job {
         }
     }
 
+    /// Lists the job in `tasks` as `name` while it runs, so it's shown in the
+    /// statusline and can be cancelled. A cancelled job finishes without
+    /// running its callback.
+    pub fn named(mut self, tasks: &Tasks, name: impl Into<String>) -> Job {
+        let (abort, registration) = AbortHandle::new_pair();
+        let guard = tasks.start(name.into(), abort);
+        self.future = Abortable::new(self.future, registration)
+            .map(move |result| {
+                drop(guard);
+                result.unwrap_or(Ok(None))
+            })
+            .boxed();
+        self
+    }
+
     pub fn wait_before_exiting(mut self) -> Job {
         self.wait = true;
         self
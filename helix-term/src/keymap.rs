@@ -364,6 +364,20 @@ impl Keymap {
         &self.state
     }
 
+    /// Returns the command `key` would run after the pending keys, without
+    /// changing them.
+    pub fn peek(&self, key: KeyEvent) -> Option<MappableCommand> {
+        let keys: Vec<_> = self.state.iter().copied().chain([key]).collect();
+        let trie = match self.sticky {
+            Some(ref node) => KeyTrie::Node(node.clone()).search(&keys).cloned(),
+            None => self.root.search(&keys).cloned(),
+        };
+        match trie {
+            Some(KeyTrie::Leaf(cmd)) => Some(cmd),
+            _ => None,
+        }
+    }
+
     /// Lookup `key` in the keymap to try and find a command to execute. Escape
     /// key cancels pending keystrokes. If there are no pending keystrokes but a
     /// sticky node is in use, it will be cleared.
@@ -429,6 +443,41 @@ impl Keymap {
     pub fn merge(&mut self, other: Self) {
         self.root.merge_nodes(other.root);
     }
+
+    /// Binds the key sequence `keys` to `command`. Returns the binding it
+    /// replaces: the old binding of `keys`, or a command bound to a prefix of
+    /// them, which becomes a menu.
+    pub fn bind(&mut self, keys: &[KeyEvent], command: MappableCommand) -> Option<KeyTrie> {
+        let (&last, prefix) = keys.split_last()?;
+        let mut node = self.root.node_mut().unwrap();
+        let mut replaced = None;
+        for &key in prefix {
+            if !node.order.contains(&key) {
+                node.order.push(key);
+            }
+            let trie = node.map.entry(key).or_insert_with(|| {
+                KeyTrie::Node(KeyTrieNode::new(
+                    &key.to_string(),
+                    HashMap::new(),
+                    Vec::new(),
+                ))
+            });
+            if trie.node().is_none() {
+                let menu = KeyTrie::Node(KeyTrieNode::new(
+                    &key.to_string(),
+                    HashMap::new(),
+                    Vec::new(),
+                ));
+                replaced = Some(std::mem::replace(trie, menu));
+            }
+            node = trie.node_mut().unwrap();
+        }
+        if !node.order.contains(&last) {
+            node.order.push(last);
+        }
+        let old = node.map.insert(last, KeyTrie::Leaf(command));
+        replaced.or(old)
+    }
 }
 
 impl Deref for Keymap {
@@ -480,8 +529,10 @@ impl Default for Keymaps {
     fn default() -> Keymaps {
         let normal = keymap!({ "Normal mode"
             "h" | "left" => move_char_left,
-            "j" | "down" => move_line_down,
-            "k" | "up" => move_line_up,
+            "j" => move_line_down,
+            "k" => move_line_up,
+            "down" => move_visual_line_down,
+            "up" => move_visual_line_up,
             "l" | "right" => move_char_right,
 
             "t" => find_till_char,
@@ -496,8 +547,10 @@ impl Default for Keymaps {
             "`" => switch_to_lowercase,
             "A-`" => switch_to_uppercase,
 
-            "home" => goto_line_start,
+            "home" => goto_line_start_smart,
             "end" => goto_line_end,
+            "C-home" => goto_file_start,
+            "C-end" => goto_file_end,
 
             "w" => move_next_word_start,
             "b" => move_prev_word_start,
@@ -564,18 +617,21 @@ impl Default for Keymaps {
                 "d" => surround_delete,
                 "a" => select_textobject_around,
                 "i" => select_textobject_inner,
+                "t" => select_tag_names,
             },
             "[" => { "Left bracket"
                 "d" => goto_prev_diag,
                 "D" => goto_first_diag,
                 "space" => add_newline_above,
                 "o" => shrink_selection,
+                "p" => paste_before_reindent,
             },
             "]" => { "Right bracket"
                 "d" => goto_next_diag,
                 "D" => goto_last_diag,
                 "space" => add_newline_below,
                 "o" => expand_selection,
+                "p" => paste_after_reindent,
             },
 
             "/" => search,
@@ -629,12 +685,16 @@ impl Default for Keymaps {
 
             "C-w" => { "Window"
                 "C-w" | "w" => rotate_view,
+                "W" => rotate_view_reverse,
                 "C-s" | "s" => hsplit,
                 "C-v" | "v" => vsplit,
                 "f" => goto_file_hsplit,
                 "F" => goto_file_vsplit,
                 "C-q" | "q" => wclose,
                 "C-o" | "o" => wonly,
+                "n" => goto_next_view_buffer,
+                "N" => goto_previous_view_buffer,
+                "p" => toggle_pin,
                 "C-h" | "h" | "left" => jump_view_left,
                 "C-j" | "j" | "down" => jump_view_down,
                 "C-k" | "k" | "up" => jump_view_up,
@@ -649,22 +709,32 @@ impl Default for Keymaps {
             "tab" => jump_forward, // tab == <C-i>
             "C-o" => jump_backward,
             "C-s" => save_selection,
+            // terminals send C-^ as C-6
+            "C-6" | "C-^" => goto_last_accessed_file,
 
             "space" => { "Space"
                 "f" => file_picker,
                 "b" => buffer_picker,
+                "B" => closed_buffer_picker,
+                "n" => toggle_notes,
+                "t" => todo_picker,
+                "\"" => yank_history_picker,
                 "s" => symbol_picker,
                 "S" => workspace_symbol_picker,
                 "a" => code_action,
                 "'" => last_picker,
                 "w" => { "Window"
                     "C-w" | "w" => rotate_view,
+                    "W" => rotate_view_reverse,
                     "C-s" | "s" => hsplit,
                     "C-v" | "v" => vsplit,
                     "f" => goto_file_hsplit,
                     "F" => goto_file_vsplit,
                     "C-q" | "q" => wclose,
                     "C-o" | "o" => wonly,
+                    "n" => goto_next_view_buffer,
+                    "N" => goto_previous_view_buffer,
+                    "p" => toggle_pin,
                     "C-h" | "h" | "left" => jump_view_left,
                     "C-j" | "j" | "down" => jump_view_down,
                     "C-k" | "k" | "up" => jump_view_up,
@@ -676,6 +746,8 @@ impl Default for Keymaps {
                 "P" => paste_clipboard_before,
                 "R" => replace_selections_with_clipboard,
                 "/" => global_search,
+                "J" => job_picker,
+                "C" => cancel_jobs,
                 "k" => hover,
                 "r" => rename_symbol,
             },
@@ -718,8 +790,10 @@ impl Default for Keymaps {
         let mut select = normal.clone();
         select.merge_nodes(keymap!({ "Select mode"
             "h" | "left" => extend_char_left,
-            "j" | "down" => extend_line_down,
-            "k" | "up" => extend_line_up,
+            "j" => extend_line_down,
+            "k" => extend_line_up,
+            "down" => extend_visual_line_down,
+            "up" => extend_visual_line_up,
             "l" | "right" => extend_char_right,
 
             "w" => extend_next_word_start,
@@ -737,7 +811,7 @@ impl Default for Keymaps {
             "T" => extend_till_prev_char,
             "F" => extend_prev_char,
 
-            "home" => extend_to_line_start,
+            "home" => extend_to_line_start_smart,
             "end" => extend_to_line_end,
             "esc" => exit_select_mode,
 
@@ -752,14 +826,14 @@ impl Default for Keymaps {
             "C-d" => delete_char_forward,
             "ret" => insert_newline,
             "tab" => insert_tab,
-            "C-w" => delete_word_backward,
-            "A-d" => delete_word_forward,
+            "C-w" | "C-backspace" => delete_word_backward,
+            "A-d" | "C-del" => delete_word_forward,
 
             "left" => move_char_left,
             "C-b" => move_char_left,
-            "down" => move_line_down,
+            "down" => move_visual_line_down,
             "C-n" => move_line_down,
-            "up" => move_line_up,
+            "up" => move_visual_line_up,
             "C-p" => move_line_up,
             "right" => move_char_right,
             "C-f" => move_char_right,
@@ -767,12 +841,24 @@ impl Default for Keymaps {
             "A-left" => move_prev_word_end,
             "A-f" => move_next_word_start,
             "A-right" => move_next_word_start,
+            "C-left" => move_prev_word_end,
+            "C-right" => move_next_word_start,
+            "S-left" => extend_char_left,
+            "S-right" => extend_char_right,
+            "S-up" => extend_visual_line_up,
+            "S-down" => extend_visual_line_down,
+            "C-S-left" => extend_prev_word_start,
+            "C-S-right" => extend_next_word_start,
+            "S-home" => extend_to_line_start_smart,
+            "S-end" => extend_to_line_end_newline,
             "pageup" => page_up,
             "pagedown" => page_down,
-            "home" => goto_line_start,
+            "home" => goto_line_start_smart,
             "C-a" => goto_line_start,
             "end" => goto_line_end_newline,
             "C-e" => goto_line_end_newline,
+            "C-home" => goto_file_start,
+            "C-end" => goto_file_end,
 
             "C-k" => kill_to_line_end,
             "C-u" => kill_to_line_start,
@@ -816,6 +902,35 @@ mod tests {
         Keymaps::default();
     }
 
+    #[test]
+    fn bind_keys() {
+        let mut keymap = Keymaps::default().0.remove(&Mode::Normal).unwrap();
+
+        let old = keymap.bind(&[key!('x')], MappableCommand::goto_line_end);
+        assert_eq!(old, Some(KeyTrie::Leaf(MappableCommand::extend_line)));
+        assert_eq!(
+            keymap.get(key!('x')).kind,
+            KeymapResultKind::Matched(MappableCommand::goto_line_end)
+        );
+
+        // into an existing menu
+        let old = keymap.bind(&[key!('g'), key!('Z')], MappableCommand::undo);
+        assert_eq!(old, None);
+        assert_eq!(
+            keymap.root().search(&[key!('g'), key!('Z')]),
+            Some(&KeyTrie::Leaf(MappableCommand::undo))
+        );
+        assert!(keymap.root().search(&[key!('g'), key!('g')]).is_some());
+
+        // a command bound to a prefix becomes a menu
+        let old = keymap.bind(&[key!('u'), key!('u')], MappableCommand::undo);
+        assert_eq!(old, Some(KeyTrie::Leaf(MappableCommand::undo)));
+        assert_eq!(
+            keymap.root().search(&[key!('u'), key!('u')]),
+            Some(&KeyTrie::Leaf(MappableCommand::undo))
+        );
+    }
+
     #[test]
     fn merge_partial_keys() {
         let config = Config {
@@ -1,8 +1,7 @@
-use anyhow::{Context, Error, Result};
+use anyhow::{Context, Result};
 use helix_term::application::Application;
 use helix_term::args::Args;
 use helix_term::config::Config;
-use helix_term::keymap::merge_keys;
 use std::path::PathBuf;
 
 fn setup_logging(logpath: PathBuf, verbosity: u64) -> Result<()> {
@@ -40,12 +39,29 @@ fn main() -> Result<()> {
 
 #[tokio::main]
 async fn main_impl() -> Result<i32> {
+    let args = Args::parse_args().context("could not parse arguments")?;
+
+    if args.timings {
+        helix_core::timings::enable();
+    }
+
+    if args.portable {
+        helix_core::enable_portable_mode();
+    }
+
+    if let Some(config_file) = &args.config_file {
+        // absolute, as opening a directory changes the working directory
+        let config_file = helix_core::path::get_canonicalized_path(config_file)
+            .context("could not resolve the config file")?;
+        helix_core::set_config_file(config_file);
+    }
+
     let cache_dir = helix_core::cache_dir();
     if !cache_dir.exists() {
         std::fs::create_dir_all(&cache_dir).ok();
     }
 
-    let logpath = cache_dir.join("helix.log");
+    let logpath = helix_core::log_file();
     let help = format!(
         "\
 {} {}
@@ -53,14 +69,21 @@ async fn main_impl() -> Result<i32> {
 {}
 
 USAGE:
-    hx [FLAGS] [files]...
+    hx [FLAGS] [[+line[:col]] files]...
 
 ARGS:
     <files>...    Sets the input file to use
+    +line[:col]   Puts the cursor at this position of the following file
 
 FLAGS:
     -h, --help       Prints help information
     --tutor          Loads the tutorial
+    --portable       Keeps config, runtime files and logs next to the
+                     executable instead of in the home directory
+    --timings        Measures startup and file loading, see :timings
+    -c, --config <file>
+                     Loads this config file instead of config.toml in
+                     the config directory
     -v               Increases logging verbosity each use for up to 3 times
                      (default file: {})
     -V, --version    Prints version information
@@ -72,8 +95,6 @@ FLAGS:
         logpath.display(),
     );
 
-    let args = Args::parse_args().context("could not parse arguments")?;
-
     // Help has a higher priority and should be handled separately.
     if args.display_help {
         print!("{}", help);
@@ -90,25 +111,19 @@ FLAGS:
         std::fs::create_dir_all(&conf_dir).ok();
     }
 
-    let config = match std::fs::read_to_string(conf_dir.join("config.toml")) {
-        Ok(config) => toml::from_str(&config)
-            .map(merge_keys)
-            .unwrap_or_else(|err| {
-                eprintln!("Bad config: {}", err);
-                eprintln!("Press <ENTER> to continue with default config");
-                use std::io::Read;
-                // This waits for an enter press.
-                let _ = std::io::stdin().read(&mut []);
-                Config::default()
-            }),
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Config::default(),
-        Err(err) => return Err(Error::new(err)),
+    // errors are shown once the editor is up, see `:goto-config-error`
+    let (config, config_error) = match helix_core::timings::time("config parse", || {
+        Config::load(&helix_core::config_file())
+    }) {
+        Ok(config) => (config, None),
+        Err(err) => (Config::default(), Some(err)),
     };
 
     setup_logging(logpath, args.verbosity).context("failed to initialize logging")?;
 
     // TODO: use the thread local executor to spawn the application task separately from the work pool
-    let mut app = Application::new(args, config).context("unable to create new application")?;
+    let mut app =
+        Application::new(args, config, config_error).context("unable to create new application")?;
 
     let exit_code = app.run().await?;
 
@@ -0,0 +1,434 @@
+use crate::{
+    compositor::{Callback, Component, Compositor, Context, EventResult},
+    key, shift,
+};
+use crossterm::event::Event;
+use tui::buffer::Buffer as Surface;
+
+use helix_core::regex::Regex;
+use helix_view::{
+    graphics::{Color, Rect, Style},
+    input::KeyEvent,
+    Editor,
+};
+use once_cell::sync::Lazy;
+
+/// The notation a color literal was written in, so that it can be written
+/// back the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorFormat {
+    /// `#rgb` or `#rrggbb`
+    Hex { short: bool },
+    /// `rgb(r, g, b)`
+    Rgb,
+    /// `hsl(h, s%, l%)`
+    Hsl,
+}
+
+/// A color literal like `#ff8000`, `rgb(255, 128, 0)` or `hsl(30, 100%, 50%)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorLiteral {
+    rgb: [u8; 3],
+    /// Hue in degrees, saturation and value in `0.0..=1.0`. Kept alongside
+    /// `rgb` so the hue survives passing through grays.
+    hsv: [f32; 3],
+    format: ColorFormat,
+}
+
+static COLOR_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?x)
+        \#[0-9a-fA-F]{6}\b
+        | \#[0-9a-fA-F]{3}\b
+        | rgb\(\s*\d+\s*,\s*\d+\s*,\s*\d+\s*\)
+        | hsl\(\s*\d+(?:\.\d+)?\s*,\s*\d+(?:\.\d+)?%\s*,\s*\d+(?:\.\d+)?%\s*\)",
+    )
+    .unwrap()
+});
+
+impl ColorLiteral {
+    pub fn from_rgb(rgb: [u8; 3], format: ColorFormat) -> Self {
+        Self {
+            rgb,
+            hsv: rgb_to_hsv(rgb),
+            format,
+        }
+    }
+
+    /// Find the color literal in `line` which contains the byte offset
+    /// `cursor`, returning its byte range.
+    pub fn find(line: &str, cursor: usize) -> Option<(std::ops::Range<usize>, Self)> {
+        COLOR_REGEX
+            .find_iter(line)
+            .find(|m| m.start() <= cursor && cursor < m.end())
+            .and_then(|m| Some((m.range(), m.as_str().parse().ok()?)))
+    }
+
+    pub fn rgb(&self) -> [u8; 3] {
+        self.rgb
+    }
+
+    fn set_rgb(&mut self, rgb: [u8; 3]) {
+        let [hue, ..] = self.hsv;
+        self.rgb = rgb;
+        self.hsv = rgb_to_hsv(rgb);
+        if self.hsv[1] == 0.0 {
+            self.hsv[0] = hue;
+        }
+    }
+
+    fn set_hsv(&mut self, hsv: [f32; 3]) {
+        self.hsv = hsv;
+        self.rgb = hsv_to_rgb(hsv);
+    }
+}
+
+impl std::str::FromStr for ColorLiteral {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow::anyhow!("Invalid color literal `{}`", s);
+
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize, len: usize| {
+                let digits = hex.get(i * len..(i + 1) * len).ok_or_else(invalid)?;
+                let value = u8::from_str_radix(digits, 16).map_err(|_| invalid())?;
+                Ok::<_, anyhow::Error>(if len == 1 { value * 0x11 } else { value })
+            };
+            let len = match hex.len() {
+                3 => 1,
+                6 => 2,
+                _ => return Err(invalid()),
+            };
+            let rgb = [channel(0, len)?, channel(1, len)?, channel(2, len)?];
+            return Ok(Self::from_rgb(rgb, ColorFormat::Hex { short: len == 1 }));
+        }
+
+        let (format, args) = if let Some(args) = s.strip_prefix("rgb(") {
+            (ColorFormat::Rgb, args)
+        } else if let Some(args) = s.strip_prefix("hsl(") {
+            (ColorFormat::Hsl, args)
+        } else {
+            return Err(invalid());
+        };
+        let args = args
+            .strip_suffix(')')
+            .ok_or_else(invalid)?
+            .split(',')
+            .map(|arg| arg.trim().trim_end_matches('%').parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        if args.len() != 3 {
+            return Err(invalid());
+        }
+
+        match format {
+            ColorFormat::Rgb => {
+                let channel = |v: f32| v.clamp(0.0, 255.0) as u8;
+                let rgb = [channel(args[0]), channel(args[1]), channel(args[2])];
+                Ok(Self::from_rgb(rgb, format))
+            }
+            _ => {
+                let hsl = [
+                    args[0].rem_euclid(360.0),
+                    (args[1] / 100.0).clamp(0.0, 1.0),
+                    (args[2] / 100.0).clamp(0.0, 1.0),
+                ];
+                let mut color = Self::from_rgb([0; 3], format);
+                color.set_hsv(hsl_to_hsv(hsl));
+                Ok(color)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ColorLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [r, g, b] = self.rgb;
+        match self.format {
+            ColorFormat::Hex { short: true } if [r, g, b].iter().all(|c| c % 0x11 == 0) => {
+                write!(f, "#{:x}{:x}{:x}", r / 0x11, g / 0x11, b / 0x11)
+            }
+            ColorFormat::Hex { .. } => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            ColorFormat::Rgb => write!(f, "rgb({}, {}, {})", r, g, b),
+            ColorFormat::Hsl => {
+                let [h, s, l] = hsv_to_hsl(self.hsv);
+                write!(
+                    f,
+                    "hsl({}, {}%, {}%)",
+                    h.round(),
+                    (s * 100.0).round(),
+                    (l * 100.0).round()
+                )
+            }
+        }
+    }
+}
+
+fn rgb_to_hsv([r, g, b]: [u8; 3]) -> [f32; 3] {
+    let [r, g, b] = [r, g, b].map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    [hue, saturation, max]
+}
+
+fn hsv_to_rgb([h, s, v]: [f32; 3]) -> [u8; 3] {
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match (h.rem_euclid(360.0) / 60.0) as u8 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+fn hsl_to_hsv([h, s, l]: [f32; 3]) -> [f32; 3] {
+    let v = l + s * l.min(1.0 - l);
+    let s = if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+    [h, s, v]
+}
+
+fn hsv_to_hsl([h, s, v]: [f32; 3]) -> [f32; 3] {
+    let l = v * (1.0 - s / 2.0);
+    let s = if l == 0.0 || l == 1.0 {
+        0.0
+    } else {
+        (v - l) / l.min(1.0 - l)
+    };
+    [h, s, l]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Channels {
+    Rgb,
+    Hsv,
+}
+
+impl Channels {
+    fn labels(self) -> [&'static str; 3] {
+        match self {
+            Self::Rgb => ["R", "G", "B"],
+            Self::Hsv => ["H", "S", "V"],
+        }
+    }
+}
+
+type ColorCallback = Box<dyn Fn(&mut Editor, &ColorLiteral)>;
+
+/// Popup contents for editing a [`ColorLiteral`] by its RGB or HSV channels.
+pub struct ColorPicker {
+    color: ColorLiteral,
+    channels: Channels,
+    selected: usize,
+    callback_fn: ColorCallback,
+}
+
+impl ColorPicker {
+    const BAR_WIDTH: u16 = 20;
+
+    pub fn new(
+        color: ColorLiteral,
+        callback_fn: impl Fn(&mut Editor, &ColorLiteral) + 'static,
+    ) -> Self {
+        Self {
+            color,
+            channels: Channels::Rgb,
+            selected: 0,
+            callback_fn: Box::new(callback_fn),
+        }
+    }
+
+    /// The selected channel scaled to `0.0..=1.0`.
+    fn fraction(&self, channel: usize) -> f32 {
+        match self.channels {
+            Channels::Rgb => self.color.rgb[channel] as f32 / 255.0,
+            Channels::Hsv if channel == 0 => self.color.hsv[0] / 360.0,
+            Channels::Hsv => self.color.hsv[channel],
+        }
+    }
+
+    fn channel_value(&self, channel: usize) -> String {
+        match self.channels {
+            Channels::Rgb => self.color.rgb[channel].to_string(),
+            Channels::Hsv if channel == 0 => format!("{}°", self.color.hsv[0].round()),
+            Channels::Hsv => format!("{}%", (self.color.hsv[channel] * 100.0).round()),
+        }
+    }
+
+    /// Move the selected channel by `steps` units: one step is 1 for RGB
+    /// channels, a degree of hue, or a percent of saturation and value.
+    fn adjust(&mut self, steps: i32) {
+        let channel = self.selected;
+        match self.channels {
+            Channels::Rgb => {
+                let mut rgb = self.color.rgb;
+                rgb[channel] = (rgb[channel] as i32 + steps).clamp(0, 255) as u8;
+                self.color.set_rgb(rgb);
+            }
+            Channels::Hsv => {
+                let mut hsv = self.color.hsv;
+                hsv[channel] = if channel == 0 {
+                    (hsv[0] + steps as f32).rem_euclid(360.0)
+                } else {
+                    ((hsv[channel] * 100.0).round() + steps as f32).clamp(0.0, 100.0) / 100.0
+                };
+                self.color.set_hsv(hsv);
+            }
+        }
+    }
+}
+
+impl Component for ColorPicker {
+    fn handle_event(&mut self, event: Event, cx: &mut Context) -> EventResult {
+        let key = match event {
+            Event::Key(key) => KeyEvent::from(key),
+            _ => return EventResult::Ignored,
+        };
+
+        match key {
+            key!(Up) | key!('k') => self.selected = self.selected.saturating_sub(1),
+            key!(Down) | key!('j') => self.selected = (self.selected + 1).min(2),
+            key!(Left) | key!('h') => self.adjust(-1),
+            key!(Right) | key!('l') => self.adjust(1),
+            shift!(Left) | key!('H') => self.adjust(-10),
+            shift!(Right) | key!('L') => self.adjust(10),
+            key!(Tab) => {
+                self.channels = match self.channels {
+                    Channels::Rgb => Channels::Hsv,
+                    Channels::Hsv => Channels::Rgb,
+                }
+            }
+            key!(Enter) => {
+                (self.callback_fn)(cx.editor, &self.color);
+                let close_fn: Callback = Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                });
+                return EventResult::Consumed(Some(close_fn));
+            }
+            _ => (),
+        }
+        // the picker keeps focus until it's closed
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let text_style = cx.editor.theme.get("ui.text");
+        let selected_style = cx.editor.theme.get("ui.menu.selected");
+
+        let [r, g, b] = self.color.rgb;
+        surface.set_string(
+            area.x + 1,
+            area.y,
+            "    ",
+            Style::default().bg(Color::Rgb(r, g, b)),
+        );
+        surface.set_stringn(
+            area.x + 6,
+            area.y,
+            self.color.to_string(),
+            area.width.saturating_sub(6) as usize,
+            text_style,
+        );
+
+        for (i, label) in self.channels.labels().iter().enumerate() {
+            let y = area.y + 1 + i as u16;
+            if y >= area.bottom() {
+                break;
+            }
+            let style = if i == self.selected {
+                selected_style
+            } else {
+                text_style
+            };
+            let filled = (self.fraction(i) * Self::BAR_WIDTH as f32).round() as usize;
+            let bar = format!(
+                "{}{}",
+                "█".repeat(filled),
+                "░".repeat(Self::BAR_WIDTH as usize - filled)
+            );
+            let line = format!(" {} {:>5} {}", label, self.channel_value(i), bar);
+            surface.set_stringn(area.x, y, &line, area.width as usize, style);
+        }
+    }
+
+    fn required_size(&mut self, _viewport: (u16, u16)) -> Option<(u16, u16)> {
+        // " R   255 " + bar + padding
+        Some((Self::BAR_WIDTH + 10, 4))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn color_literals_keep_their_format() {
+        for literal in [
+            "#ff8000",
+            "#f80",
+            "#fff",
+            "rgb(255, 128, 0)",
+            "hsl(30, 100%, 50%)",
+            "hsl(210, 40%, 20%)",
+        ] {
+            let color: ColorLiteral = literal.parse().unwrap();
+            assert_eq!(color.to_string(), literal);
+        }
+
+        let color: ColorLiteral = "#f80".parse().unwrap();
+        assert_eq!(color.rgb(), [0xff, 0x88, 0x00]);
+        let color: ColorLiteral = "hsl(30, 100%, 50%)".parse().unwrap();
+        assert_eq!(color.rgb(), [255, 128, 0]);
+    }
+
+    #[test]
+    fn short_hex_widens_when_needed() {
+        let mut color: ColorLiteral = "#f80".parse().unwrap();
+        color.set_rgb([0xff, 0x81, 0x00]);
+        assert_eq!(color.to_string(), "#ff8100");
+    }
+
+    #[test]
+    fn finding_color_literals() {
+        let line = "  color: rgb(1, 2, 3); border: 1px solid #abcdef;";
+        let (range, color) = ColorLiteral::find(line, 12).unwrap();
+        assert_eq!(&line[range], "rgb(1, 2, 3)");
+        assert_eq!(color.rgb(), [1, 2, 3]);
+
+        let (range, _) = ColorLiteral::find(line, line.len() - 2).unwrap();
+        assert_eq!(&line[range], "#abcdef");
+
+        assert!(ColorLiteral::find(line, 0).is_none());
+        assert!(ColorLiteral::find("#abcd", 0).is_none());
+    }
+
+    #[test]
+    fn hue_survives_grays() {
+        let mut picker = ColorPicker::new("#ff0000".parse().unwrap(), |_, _| {});
+        picker.channels = Channels::Hsv;
+        picker.selected = 0;
+        picker.adjust(120);
+        assert_eq!(picker.color.rgb(), [0, 255, 0]);
+
+        picker.selected = 1;
+        picker.adjust(-100);
+        assert_eq!(picker.color.rgb(), [255, 255, 255]);
+        picker.adjust(100);
+        assert_eq!(picker.color.rgb(), [0, 255, 0]);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    compositor::{Callback, Component, Compositor, Context, EventResult},
+    key,
+};
+use crossterm::event::Event;
+use helix_core::unicode::width::UnicodeWidthStr;
+use tui::buffer::Buffer as Surface;
+
+use helix_view::{
+    graphics::Rect,
+    input::KeyEvent,
+    keyboard::{KeyCode, KeyModifiers},
+    Editor,
+};
+
+type ConfirmCallback = Box<dyn FnOnce(&mut Editor)>;
+
+/// Asks a yes/no question before a destructive action. `y` or Enter runs
+/// the action, `n` or Esc cancels it.
+pub struct Confirm {
+    message: String,
+    confirm_fn: Option<ConfirmCallback>,
+}
+
+impl Confirm {
+    pub fn new(message: String, confirm_fn: impl FnOnce(&mut Editor) + 'static) -> Self {
+        Self {
+            message: format!("{} [y/n]", message),
+            confirm_fn: Some(Box::new(confirm_fn)),
+        }
+    }
+}
+
+impl Component for Confirm {
+    fn handle_event(&mut self, event: Event, cx: &mut Context) -> EventResult {
+        let key = match event {
+            Event::Key(key) => KeyEvent::from(key),
+            _ => return EventResult::Ignored,
+        };
+
+        match key {
+            key!('y') | key!(Enter) => {
+                if let Some(confirm_fn) = self.confirm_fn.take() {
+                    confirm_fn(cx.editor);
+                }
+            }
+            key!('n') | key!('q') => (),
+            // the question keeps focus until it's answered
+            _ => return EventResult::Consumed(None),
+        }
+        let close_fn: Callback = Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        });
+        EventResult::Consumed(Some(close_fn))
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        let style = theme.get("ui.text").patch(theme.get("ui.text.focus"));
+        surface.set_stringn(area.x, area.y, &self.message, area.width as usize, style);
+    }
+
+    fn required_size(&mut self, viewport: (u16, u16)) -> Option<(u16, u16)> {
+        Some(((self.message.width() as u16).min(viewport.0), 1))
+    }
+}
+
+/// Asks how to proceed, offering a few actions bound to single keys. `q` or Esc
+/// dismisses it without doing anything.
+pub struct Choices {
+    message: String,
+    choices: Vec<(char, String, Option<Callback>)>,
+}
+
+impl Choices {
+    pub fn new(message: String) -> Self {
+        Self {
+            message,
+            choices: Vec::new(),
+        }
+    }
+
+    /// Adds an action run when `key` is pressed, after the question was closed.
+    pub fn choice(
+        mut self,
+        key: char,
+        label: &str,
+        action: impl FnOnce(&mut Compositor, &mut Context) + 'static,
+    ) -> Self {
+        self.choices
+            .push((key, label.to_string(), Some(Box::new(action))));
+        self
+    }
+
+    fn choices_line(&self) -> String {
+        self.choices
+            .iter()
+            .map(|(key, label, _)| format!("[{}] {}", key, label))
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+}
+
+impl Component for Choices {
+    fn handle_event(&mut self, event: Event, _cx: &mut Context) -> EventResult {
+        let key = match event {
+            Event::Key(key) => KeyEvent::from(key),
+            _ => return EventResult::Ignored,
+        };
+
+        let action = match key {
+            key!('q') => None,
+            KeyEvent {
+                code: KeyCode::Char(ch),
+                modifiers: KeyModifiers::NONE,
+            } => match self.choices.iter_mut().find(|(key, _, _)| *key == ch) {
+                Some((_, _, action)) => action.take(),
+                None => return EventResult::Consumed(None),
+            },
+            // the question keeps focus until it's answered
+            _ => return EventResult::Consumed(None),
+        };
+        let close_fn: Callback = Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            if let Some(action) = action {
+                action(compositor, cx);
+            }
+        });
+        EventResult::Consumed(Some(close_fn))
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        let style = theme.get("ui.text");
+        let focus = style.patch(theme.get("ui.text.focus"));
+        let width = area.width as usize;
+        surface.set_stringn(area.x, area.y, &self.message, width, focus);
+        if area.height > 1 {
+            surface.set_stringn(area.x, area.y + 1, self.choices_line(), width, style);
+        }
+    }
+
+    fn required_size(&mut self, viewport: (u16, u16)) -> Option<(u16, u16)> {
+        let width = self.message.width().max(self.choices_line().width()) as u16;
+        Some((width.min(viewport.0), 2))
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    compositor::{Callback, Component, Compositor, Context, EventResult},
+    key,
+};
+use crossterm::event::Event;
+use tui::buffer::Buffer as Surface;
+
+use helix_view::{graphics::Rect, input::KeyEvent, Editor};
+
+type ApplyCallback = Box<dyn FnOnce(&mut Editor)>;
+
+/// Shows a unified diff and asks whether to apply it. `y` or Enter applies
+/// the change, `n` or Esc discards it.
+pub struct DiffPreview {
+    header: &'static str,
+    lines: Vec<String>,
+    apply_fn: Option<ApplyCallback>,
+}
+
+impl DiffPreview {
+    pub fn new(diff: &str, apply_fn: impl FnOnce(&mut Editor) + 'static) -> Self {
+        Self {
+            header: "Apply changes? [y/n]",
+            lines: diff.lines().map(str::to_string).collect(),
+            apply_fn: Some(Box::new(apply_fn)),
+        }
+    }
+
+    /// Only shows the diff below `header`, there's nothing to apply.
+    pub fn read_only(diff: &str, header: &'static str) -> Self {
+        Self {
+            header,
+            lines: diff.lines().map(str::to_string).collect(),
+            apply_fn: None,
+        }
+    }
+}
+
+impl Component for DiffPreview {
+    fn handle_event(&mut self, event: Event, cx: &mut Context) -> EventResult {
+        let key = match event {
+            Event::Key(key) => KeyEvent::from(key),
+            _ => return EventResult::Ignored,
+        };
+
+        match key {
+            key!('y') | key!(Enter) => {
+                if let Some(apply_fn) = self.apply_fn.take() {
+                    apply_fn(cx.editor);
+                }
+            }
+            key!('n') | key!('q') => (),
+            // the preview keeps focus until it's answered
+            _ => return EventResult::Consumed(None),
+        }
+        let close_fn: Callback = Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        });
+        EventResult::Consumed(Some(close_fn))
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        let text_style = theme.get("ui.text");
+        let width = area.width as usize;
+
+        surface.set_stringn(
+            area.x,
+            area.y,
+            self.header,
+            width,
+            text_style.patch(theme.get("ui.text.focus")),
+        );
+
+        let scroll = cx.scroll.unwrap_or_default();
+        let rows = area.y + 1..area.bottom();
+        for (y, line) in rows.zip(self.lines.iter().skip(scroll)) {
+            let style = match line.chars().next() {
+                Some('+') => text_style.patch(theme.get("diff.plus")),
+                Some('-') => text_style.patch(theme.get("diff.minus")),
+                Some('@') => text_style.patch(theme.get("diff.delta")),
+                _ => text_style,
+            };
+            surface.set_stringn(area.x, y, line, width, style);
+        }
+    }
+
+    fn required_size(&mut self, viewport: (u16, u16)) -> Option<(u16, u16)> {
+        let width = self
+            .lines
+            .iter()
+            .map(|line| line.chars().count())
+            .chain(std::iter::once(self.header.chars().count()))
+            .max()
+            .unwrap_or_default();
+        let height = self.lines.len() + 1;
+        Some((
+            (width as u16).min(viewport.0),
+            (height as u16).min(viewport.1),
+        ))
+    }
+}
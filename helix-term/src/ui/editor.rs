@@ -2,31 +2,46 @@ use crate::{
     commands,
     compositor::{Component, Context, EventResult},
     key,
-    keymap::{KeymapResult, KeymapResultKind, Keymaps},
-    ui::{Completion, ProgressSpinners},
+    keymap::{Keymap, KeymapResult, KeymapResultKind, Keymaps},
+    ui::{Completion, ProgressSpinners, Spinner},
 };
 
 use helix_core::{
-    coords_at_pos, encoding,
-    graphemes::{ensure_grapheme_boundary_next, next_grapheme_boundary, prev_grapheme_boundary},
+    bidi,
+    chars::char_is_line_ending,
+    coords_at_pos, data_path, encoding,
+    graphemes::ensure_grapheme_boundary_next,
+    indent::IndentStyle,
+    match_brackets,
     movement::Direction,
+    search::NormalizedText,
     syntax::{self, HighlightEvent},
+    timings,
     unicode::segmentation::UnicodeSegmentation,
     unicode::width::UnicodeWidthStr,
-    LineEnding, Position, Range, Selection,
+    LineEnding, Position, Range, RopeSlice, Selection,
 };
 use helix_view::{
     document::{Mode, SCRATCH_BUFFER_NAME},
-    graphics::{CursorKind, Modifier, Rect, Style},
+    editor::{CursorShapeConfig, Severity},
+    graphics::{Color, CursorKind, Margin, Modifier, Rect, Style},
+    highlights::{self, SelectionScopes},
     info::Info,
     input::KeyEvent,
     keyboard::{KeyCode, KeyModifiers},
-    Document, Editor, Theme, View,
+    Document, DocumentId, Editor, Theme, View, ViewId,
+};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    time::{Duration, Instant},
 };
-use std::borrow::Cow;
 
 use crossterm::event::{Event, MouseButton, MouseEvent, MouseEventKind};
-use tui::buffer::Buffer as Surface;
+use tui::{
+    buffer::Buffer as Surface,
+    widgets::{Block, Borders, Widget},
+};
 
 pub struct EditorView {
     keymaps: Keymaps,
@@ -34,9 +49,48 @@ pub struct EditorView {
     last_insert: (commands::MappableCommand, Vec<KeyEvent>),
     pub(crate) completion: Option<Completion>,
     spinners: ProgressSpinners,
+    /// Spins in the statusline while background jobs are running.
+    job_spinner: Spinner,
+    running_jobs: usize,
     autoinfo: Option<Info>,
+    /// Recently pressed key chords, shown when `show-keys` is enabled.
+    shown_keys: VecDeque<ShownKeys>,
+    /// The split separator pressed with the mouse.
+    dragged_separator: Option<DraggedSeparator>,
+    /// Tabs of the bufferline as last rendered, to handle clicks on them.
+    bufferline_tabs: Vec<BufferlineTab>,
+}
+
+/// A split separator pressed with the mouse, as its container and the index
+/// of the split before it.
+struct DraggedSeparator {
+    container: ViewId,
+    index: usize,
+    /// Whether it was dragged. Stacked splits are separated by the statusline
+    /// of the upper one, which is focused by a click without dragging.
+    moved: bool,
+}
+
+/// A tab of the bufferline and where it was drawn.
+struct BufferlineTab {
+    doc: DocumentId,
+    label: Rect,
+    close: Rect,
 }
 
+/// A key chord and the command(s) it ran, for the `show-keys` overlay.
+struct ShownKeys {
+    keys: String,
+    commands: String,
+    /// Number of times in a row this chord was pressed.
+    count: usize,
+    time: Instant,
+}
+
+/// How long a chord stays in the `show-keys` overlay.
+const SHOWN_KEYS_DURATION: Duration = Duration::from_secs(1);
+const SHOWN_KEYS_MAX: usize = 5;
+
 impl Default for EditorView {
     fn default() -> Self {
         Self::new(Keymaps::default())
@@ -51,10 +105,19 @@ impl EditorView {
             last_insert: (commands::MappableCommand::normal_mode, Vec::new()),
             completion: None,
             spinners: ProgressSpinners::default(),
+            job_spinner: Spinner::default(),
+            running_jobs: 0,
             autoinfo: None,
+            shown_keys: VecDeque::new(),
+            dragged_separator: None,
+            bufferline_tabs: Vec::new(),
         }
     }
 
+    pub fn keymaps_mut(&mut self) -> &mut Keymaps {
+        &mut self.keymaps
+    }
+
     pub fn spinners_mut(&mut self) -> &mut ProgressSpinners {
         &mut self.spinners
     }
@@ -64,6 +127,7 @@ impl EditorView {
         &self,
         doc: &Document,
         view: &View,
+        alternate: Option<&Document>,
         viewport: Rect,
         surface: &mut Surface,
         theme: &Theme,
@@ -74,18 +138,45 @@ impl EditorView {
         let inner = view.inner_area();
         let area = view.area;
 
+        let highlight_start = Instant::now();
         let highlights = Self::doc_syntax_highlights(doc, view.offset, inner.height, theme, loader);
         let highlights = syntax::merge(highlights, Self::doc_diagnostics_highlights(doc, theme));
+        let highlights = syntax::merge(highlights, Self::view_document_highlights(view, theme));
+        let highlights = syntax::merge(highlights, Self::view_search_highlights(doc, view, theme));
         let highlights: Box<dyn Iterator<Item = HighlightEvent>> = if is_focused {
             Box::new(syntax::merge(
                 highlights,
-                Self::doc_selection_highlights(doc, view, theme),
+                Self::doc_selection_highlights(doc, view, theme, &config.cursor_shape),
             ))
         } else {
             Box::new(highlights)
         };
 
-        Self::render_text_highlights(doc, view.offset, inner, surface, theme, highlights);
+        if config.cursorline && is_focused {
+            Self::render_cursorline(doc, view, surface, theme);
+        }
+
+        Self::render_text_highlights(
+            doc,
+            view.offset,
+            inner,
+            surface,
+            theme,
+            highlights,
+            view.soft_wrap,
+            config.render_whitespace,
+        );
+        if config.indent_guides {
+            Self::render_indent_guides(doc, view, inner, surface, theme);
+        }
+        Self::render_rulers(view, inner, surface, theme, &config.rulers);
+        if let (true, Some(path)) = (timings::is_enabled(), doc.path()) {
+            // highlights are computed lazily while rendering the text
+            timings::record_once(
+                format!("{}: first highlight", path.display()),
+                highlight_start.elapsed(),
+            );
+        }
         Self::render_gutter(doc, view, view.area, surface, theme, is_focused, config);
 
         if is_focused {
@@ -111,7 +202,16 @@ impl EditorView {
             .area
             .clip_top(view.area.height.saturating_sub(1))
             .clip_bottom(1); // -1 from bottom to remove commandline
-        self.render_statusline(doc, view, statusline_area, surface, theme, is_focused);
+        self.render_statusline(
+            doc,
+            view,
+            alternate,
+            statusline_area,
+            surface,
+            theme,
+            is_focused,
+            config,
+        );
     }
 
     /// Get syntax highlights for a document in a view represented by the first line
@@ -189,6 +289,8 @@ impl EditorView {
         doc: &Document,
         theme: &Theme,
     ) -> Vec<(usize, std::ops::Range<usize>)> {
+        use helix_core::diagnostic::Severity;
+
         let diagnostic_scope = theme
             .find_scope_index("diagnostic")
             .or_else(|| theme.find_scope_index("ui.cursor"))
@@ -196,14 +298,75 @@ impl EditorView {
             .expect(
                 "at least one of the following scopes must be defined in the theme: `diagnostic`, `ui.cursor`, or `ui.selection`",
             );
+        // themes may style each severity, falling back to `diagnostic`
+        let severity_scope = |scope| theme.find_scope_index(scope).unwrap_or(diagnostic_scope);
+        let error = severity_scope("diagnostic.error");
+        let warning = severity_scope("diagnostic.warning");
+        let info = severity_scope("diagnostic.info");
+        let hint = severity_scope("diagnostic.hint");
 
         doc.diagnostics()
             .iter()
             .map(|diagnostic| {
-                (
-                    diagnostic_scope,
-                    diagnostic.range.start..diagnostic.range.end,
-                )
+                let scope = match diagnostic.severity {
+                    Some(Severity::Error) => error,
+                    Some(Severity::Warning) | None => warning,
+                    Some(Severity::Info) => info,
+                    Some(Severity::Hint) => hint,
+                };
+                (scope, diagnostic.range.start..diagnostic.range.end)
+            })
+            .collect()
+    }
+
+    /// Get highlight spans for the symbol references reported by the language server.
+    pub fn view_document_highlights(
+        view: &View,
+        theme: &Theme,
+    ) -> Vec<(usize, std::ops::Range<usize>)> {
+        let scope = match theme.find_scope_index("ui.highlight") {
+            Some(scope) => scope,
+            None => return Vec::new(),
+        };
+
+        view.document_highlights
+            .iter()
+            .map(|range| (scope, range.from()..range.to()))
+            .collect()
+    }
+
+    /// Get highlight spans for the matches of the regex prompt being typed, or of the last
+    /// search when it's kept highlighted, in the visible part of the document.
+    pub fn view_search_highlights(
+        doc: &Document,
+        view: &View,
+        theme: &Theme,
+    ) -> Vec<(usize, std::ops::Range<usize>)> {
+        let (regex, normalization) = match &view.search_highlight {
+            Some(search) => search,
+            None => return Vec::new(),
+        };
+        let scope = match theme
+            .find_scope_index("ui.highlight.search")
+            .or_else(|| theme.find_scope_index("ui.search"))
+            .or_else(|| theme.find_scope_index("ui.highlight"))
+        {
+            Some(scope) => scope,
+            None => return Vec::new(),
+        };
+
+        let text = doc.text().slice(..);
+        let start = text.line_to_char(view.offset.row.min(text.len_lines()));
+        let end = text.line_to_char((view.last_line(doc) + 1).min(text.len_lines()));
+        let visible = text.slice(start..end);
+        let contents = NormalizedText::new(visible, *normalization);
+
+        regex
+            .find_iter(contents.as_str())
+            .filter(|mat| !mat.range().is_empty())
+            .map(|mat| {
+                let range = contents.char_range(visible, mat.range());
+                (scope, start + range.start..start + range.end)
             })
             .collect()
     }
@@ -213,63 +376,131 @@ impl EditorView {
         doc: &Document,
         view: &View,
         theme: &Theme,
+        cursor_shape: &CursorShapeConfig,
     ) -> Vec<(usize, std::ops::Range<usize>)> {
-        let text = doc.text().slice(..);
-        let selection = doc.selection(view.id);
-        let primary_idx = selection.primary_index();
-
-        let selection_scope = theme
-            .find_scope_index("ui.selection")
-            .expect("could not find `ui.selection` scope in the theme!");
-        let base_cursor_scope = theme
-            .find_scope_index("ui.cursor")
-            .unwrap_or(selection_scope);
-
-        let cursor_scope = match doc.mode() {
-            Mode::Insert => theme.find_scope_index("ui.cursor.insert"),
-            Mode::Select => theme.find_scope_index("ui.cursor.select"),
-            Mode::Normal => Some(base_cursor_scope),
-        }
-        .unwrap_or(base_cursor_scope);
-
-        let primary_cursor_scope = theme
-            .find_scope_index("ui.cursor.primary")
-            .unwrap_or(cursor_scope);
-        let primary_selection_scope = theme
-            .find_scope_index("ui.selection.primary")
-            .unwrap_or(selection_scope);
-
-        let mut spans: Vec<(usize, std::ops::Range<usize>)> = Vec::new();
-        for (i, range) in selection.iter().enumerate() {
-            let (cursor_scope, selection_scope) = if i == primary_idx {
-                (primary_cursor_scope, primary_selection_scope)
-            } else {
-                (cursor_scope, selection_scope)
-            };
+        // other shapes are drawn by the terminal
+        let draw_primary_cursor = cursor_shape.from_mode(doc.mode()) == CursorKind::Block;
+        highlights::selection_highlights(
+            doc.text().slice(..),
+            doc.selection(view.id),
+            SelectionScopes::from_theme(theme, doc.mode()),
+            draw_primary_cursor,
+        )
+    }
 
-            // Special-case: cursor at end of the rope.
-            if range.head == range.anchor && range.head == text.len_chars() {
-                spans.push((cursor_scope, range.head..range.head + 1));
-                continue;
+    /// Renders the open documents as tabs, the one in the focused view highlighted.
+    fn render_bufferline(&mut self, editor: &Editor, area: Rect, surface: &mut Surface) {
+        let theme = &editor.theme;
+        let active_style = theme
+            .try_get("ui.bufferline.active")
+            .unwrap_or_else(|| theme.get("ui.statusline"));
+        let style = theme
+            .try_get("ui.bufferline")
+            .unwrap_or_else(|| theme.get("ui.statusline.inactive"));
+
+        let current = view!(editor).doc;
+        let docs: Vec<_> = editor.documents().map(|doc| doc.id()).collect();
+        let labels: Vec<_> = editor
+            .documents()
+            .map(|doc| {
+                let name = doc
+                    .path()
+                    .and_then(|path| path.file_name())
+                    .map(|name| name.to_string_lossy())
+                    .unwrap_or_else(|| SCRATCH_BUFFER_NAME.into());
+                let modified = if doc.is_modified() { " ●" } else { "" };
+                let deleted = if doc.is_deleted() { " [deleted]" } else { "" };
+                let pinned = editor
+                    .tree
+                    .views()
+                    .any(|(view, _)| view.pinned && view.doc == doc.id());
+                let pinned = if pinned { "📌" } else { "" };
+                format!(" {}{}{}{} ", pinned, name, deleted, modified)
+            })
+            .collect();
+        let active = docs.iter().position(|&doc| doc == current);
+
+        let tabs = Self::draw_bufferline(&labels, active, area, surface, style, active_style);
+        self.bufferline_tabs = tabs
+            .into_iter()
+            .map(|(i, label, close)| BufferlineTab {
+                doc: docs[i],
+                label,
+                close,
+            })
+            .collect();
+    }
+
+    /// Draws the tabs `labels` in `area`, scrolled so that the `active` one
+    /// is shown. `<` and `>` mark tabs hidden on either side. Returns the
+    /// index of each tab drawn with the areas of its label and close button.
+    fn draw_bufferline(
+        labels: &[String],
+        active: Option<usize>,
+        area: Rect,
+        surface: &mut Surface,
+        style: Style,
+        active_style: Style,
+    ) -> Vec<(usize, Rect, Rect)> {
+        surface.set_style(area, style);
+
+        // each tab is followed by its close button and a space
+        let widths: Vec<u16> = labels
+            .iter()
+            .map(|label| (label.width() as u16).saturating_add(2))
+            .collect();
+        let total = widths.iter().fold(0u16, |a, &b| a.saturating_add(b));
+        // room for the markers of hidden tabs, if they don't all fit
+        let margin = if total > area.width { 2 } else { 0 };
+        let available = area.width.saturating_sub(margin * 2);
+
+        let mut start = 0;
+        if let Some(active) = active {
+            while start < active
+                && widths[start..=active]
+                    .iter()
+                    .map(|&w| w as u32)
+                    .sum::<u32>()
+                    > available as u32
+            {
+                start += 1;
             }
+        }
 
-            let range = range.min_width_1(text);
-            if range.head > range.anchor {
-                // Standard case.
-                let cursor_start = prev_grapheme_boundary(text, range.head);
-                spans.push((selection_scope, range.anchor..cursor_start));
-                spans.push((cursor_scope, cursor_start..range.head));
-            } else {
-                // Reverse case.
-                let cursor_end = next_grapheme_boundary(text, range.head);
-                spans.push((cursor_scope, range.head..cursor_end));
-                spans.push((selection_scope, cursor_end..range.anchor));
+        let mut tabs = Vec::new();
+        let mut x = area.x + margin;
+        for (i, label) in labels.iter().enumerate().skip(start) {
+            if x + widths[i] > area.x + margin + available {
+                break;
             }
+            let label_width = widths[i] - 2;
+            let style = if Some(i) == active {
+                active_style
+            } else {
+                style
+            };
+            surface.set_string(x, area.y, label, style);
+            surface.set_string(x + label_width, area.y, "×", style);
+            tabs.push((
+                i,
+                Rect::new(x, area.y, label_width, 1),
+                Rect::new(x + label_width, area.y, 1, 1),
+            ));
+            x += widths[i];
         }
 
-        spans
+        if start > 0 {
+            surface.set_string(area.x, area.y, "<", style);
+        }
+        if start + tabs.len() < labels.len() {
+            surface.set_string(area.right().saturating_sub(1), area.y, ">", style);
+        }
+        tabs
     }
 
+    /// Renders the text of `doc` from `offset`. With `soft_wrap`, lines longer than the
+    /// viewport continue on the next rows instead of being cut off.
+    #[allow(clippy::too_many_arguments)]
     pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
         doc: &Document,
         offset: Position,
@@ -277,6 +508,8 @@ impl EditorView {
         surface: &mut Surface,
         theme: &Theme,
         highlights: H,
+        soft_wrap: bool,
+        render_whitespace: bool,
     ) {
         let text = doc.text().slice(..);
 
@@ -284,9 +517,31 @@ impl EditorView {
         let mut visual_x = 0u16;
         let mut line = 0u16;
         let tab_width = doc.tab_width();
-        let tab = " ".repeat(tab_width);
+        let tab: String = if render_whitespace {
+            std::iter::once('→')
+                .chain(std::iter::repeat(' '))
+                .take(tab_width)
+                .collect()
+        } else {
+            " ".repeat(tab_width)
+        };
 
         let text_style = theme.get("ui.text");
+        let whitespace_style = theme
+            .try_get("ui.virtual.whitespace")
+            .unwrap_or_else(|| theme.get("ui.linenr"));
+        let wrap_width = viewport.width.max(1);
+        // where the grapheme at `visual_x` is drawn, relative to the viewport
+        let screen_pos = |visual_x: u16, line: u16| {
+            if soft_wrap {
+                (
+                    visual_x % wrap_width,
+                    line.saturating_add(visual_x / wrap_width),
+                )
+            } else {
+                (visual_x.wrapping_sub(offset.col as u16), line)
+            }
+        };
 
         'outer: for event in highlights {
             match event {
@@ -300,13 +555,22 @@ impl EditorView {
                     // `unwrap_or_else` part is for off-the-end indices of
                     // the rope, to allow cursor highlighting at the end
                     // of the rope.
-                    let text = text.get_slice(start..end).unwrap_or_else(|| " ".into());
+                    let (text, past_end) = match text.get_slice(start..end) {
+                        Some(text) => (text, false),
+                        None => (" ".into(), true),
+                    };
+                    let render_whitespace = render_whitespace && !past_end;
 
                     use helix_core::graphemes::{grapheme_width, RopeGraphemes};
 
                     for grapheme in RopeGraphemes::new(text) {
-                        let out_of_bounds = visual_x < offset.col as u16
-                            || visual_x >= viewport.width + offset.col as u16;
+                        let (x, y) = screen_pos(visual_x, line);
+                        let out_of_bounds = if soft_wrap {
+                            y >= viewport.height
+                        } else {
+                            visual_x < offset.col as u16
+                                || visual_x >= viewport.width + offset.col as u16
+                        };
 
                         if LineEnding::from_rope_slice(&grapheme).is_some() {
                             if !out_of_bounds {
@@ -315,16 +579,17 @@ impl EditorView {
                                 });
 
                                 // we still want to render an empty cell with the style
-                                surface.set_string(
-                                    viewport.x + visual_x - offset.col as u16,
-                                    viewport.y + line,
-                                    " ",
-                                    style,
-                                );
+                                if render_whitespace {
+                                    let style = style.patch(whitespace_style);
+                                    surface.set_string(viewport.x + x, viewport.y + y, "⏎", style);
+                                } else {
+                                    surface.set_string(viewport.x + x, viewport.y + y, " ", style);
+                                }
                             }
 
+                            // the line ending is on the last row of a wrapped line
+                            line = y + 1;
                             visual_x = 0;
-                            line += 1;
 
                             // TODO: with proper iter this shouldn't be necessary
                             if line >= viewport.height {
@@ -333,9 +598,12 @@ impl EditorView {
                         } else {
                             let grapheme = Cow::from(grapheme);
 
+                            let is_whitespace = grapheme == "\t" || grapheme == " ";
                             let (grapheme, width) = if grapheme == "\t" {
                                 // make sure we display tab as appropriate amount of spaces
                                 (tab.as_str(), tab_width)
+                            } else if render_whitespace && grapheme == " " {
+                                ("·", 1)
                             } else {
                                 // Cow will prevent allocations if span contained in a single slice
                                 // which should really be the majority case
@@ -344,15 +612,25 @@ impl EditorView {
                             };
 
                             if !out_of_bounds {
-                                let style = spans.iter().fold(text_style, |acc, span| {
+                                let mut style = spans.iter().fold(text_style, |acc, span| {
                                     acc.patch(theme.highlight(span.0))
                                 });
+                                if render_whitespace && is_whitespace {
+                                    style = style.patch(whitespace_style);
+                                }
 
-                                // if we're offscreen just keep going until we hit a new line
-                                surface.set_string(
-                                    viewport.x + visual_x - offset.col as u16,
-                                    viewport.y + line,
+                                // clip tabs and wide graphemes at the edge of the row instead of
+                                // letting them spill into the gutter or a neighbouring view
+                                let row_width = if soft_wrap {
+                                    wrap_width
+                                } else {
+                                    viewport.width
+                                };
+                                surface.set_stringn(
+                                    viewport.x + x,
+                                    viewport.y + y,
                                     grapheme,
+                                    row_width.saturating_sub(x) as usize,
                                     style,
                                 );
                             }
@@ -363,6 +641,51 @@ impl EditorView {
                 }
             }
         }
+
+        // TODO: reorder right-to-left text on wrapped rows
+        if !soft_wrap {
+            Self::reorder_bidi_lines(text, offset, viewport, surface, tab_width);
+        }
+    }
+
+    /// Moves the rendered cells of lines containing right-to-left text into display order.
+    fn reorder_bidi_lines(
+        text: RopeSlice,
+        offset: Position,
+        viewport: Rect,
+        surface: &mut Surface,
+        tab_width: usize,
+    ) {
+        let last_line = (offset.row + viewport.height as usize).min(text.len_lines());
+        for (row, line) in (offset.row..last_line).enumerate() {
+            let columns = match bidi::visual_columns(text.line(line), tab_width) {
+                Some(columns) => columns,
+                None => continue,
+            };
+            let y = viewport.y + row as u16;
+            let cells: Vec<_> = (viewport.x..viewport.right())
+                .map(|x| surface.get(x, y).clone())
+                .collect();
+            let on_screen =
+                |col: usize| col.checked_sub(offset.col).filter(|col| *col < cells.len());
+
+            for column in &columns {
+                for i in 0..column.width {
+                    let to = match on_screen(column.visual_col + i) {
+                        Some(to) => to,
+                        None => continue,
+                    };
+                    let cell = surface.get_mut(viewport.x + to as u16, y);
+                    match on_screen(column.logical_col + i) {
+                        Some(from) => *cell = cells[from].clone(),
+                        // scrolled in from outside the view
+                        None => {
+                            cell.reset();
+                        }
+                    }
+                }
+            }
+        }
     }
 
     /// Render brace match, etc (meant for the focused view only)
@@ -373,26 +696,31 @@ impl EditorView {
         theme: &Theme,
         surface: &mut Surface,
     ) {
-        // Highlight matching braces
-        if let Some(syntax) = doc.syntax() {
-            let text = doc.text().slice(..);
-            use helix_core::match_brackets;
-            let pos = doc.selection(view.id).primary().cursor(text);
+        // Highlight matching braces, for the bracket under the cursor or right before it
+        let text = doc.text().slice(..);
+        let cursor = doc.selection(view.id).primary().cursor(text);
+        let (bracket, matching) =
+            match match_brackets::find_matching_pair(doc.syntax(), doc.text(), cursor) {
+                Some(pair) => pair,
+                None => return,
+            };
 
-            let pos = match_brackets::find_matching_bracket(syntax, doc.text(), pos)
-                .and_then(|pos| view.screen_coords_at_pos(doc, text, pos));
+        let style = theme.try_get("ui.cursor.match").unwrap_or_else(|| {
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .add_modifier(Modifier::DIM)
+        });
 
-            if let Some(pos) = pos {
+        for pos in [bracket, matching] {
+            // the cursor is drawn over the bracket it's on
+            if pos == cursor {
+                continue;
+            }
+            if let Some(pos) = view.screen_coords_at_pos(doc, text, pos) {
                 // ensure col is on screen
                 if (pos.col as u16) < viewport.width + view.offset.col as u16
                     && pos.col >= view.offset.col
                 {
-                    let style = theme.try_get("ui.cursor.match").unwrap_or_else(|| {
-                        Style::default()
-                            .add_modifier(Modifier::REVERSED)
-                            .add_modifier(Modifier::DIM)
-                    });
-
                     surface
                         .get_mut(viewport.x + pos.col as u16, viewport.y + pos.row as u16)
                         .set_style(style);
@@ -401,6 +729,118 @@ impl EditorView {
         }
     }
 
+    /// Draws a line at each indentation level of the visible lines, in the empty cells of their
+    /// leading whitespace. Blank lines don't get any.
+    pub fn render_indent_guides(
+        doc: &Document,
+        view: &View,
+        viewport: Rect,
+        surface: &mut Surface,
+        theme: &Theme,
+    ) {
+        let style = theme
+            .try_get("ui.virtual.indent-guide")
+            .unwrap_or_else(|| theme.get("ui.linenr"));
+        let text = doc.text().slice(..);
+        let tab_width = doc.tab_width();
+        let indent_width = match doc.indent_style {
+            IndentStyle::Tabs => tab_width,
+            IndentStyle::Spaces(width) => width as usize,
+        }
+        .max(1);
+
+        for (i, &(line, first_row)) in view.rows(doc).iter().enumerate() {
+            // wrapped rows don't start with the indentation
+            if !first_row {
+                continue;
+            }
+            let mut indent = 0;
+            let mut blank = true;
+            for ch in text.line(line).chars() {
+                match ch {
+                    ' ' => indent += 1,
+                    '\t' => indent += tab_width - indent % tab_width,
+                    _ => {
+                        blank = char_is_line_ending(ch);
+                        break;
+                    }
+                }
+            }
+            if blank {
+                continue;
+            }
+
+            let y = viewport.y + i as u16;
+            for column in (0..indent).step_by(indent_width) {
+                let x = match column.checked_sub(view.offset.col) {
+                    Some(x) if x < viewport.width as usize => viewport.x + x as u16,
+                    _ => continue,
+                };
+                let cell = surface.get_mut(x, y);
+                if cell.symbol == " " {
+                    cell.set_symbol("│").set_style(style);
+                }
+            }
+        }
+    }
+
+    /// Shades the 1-based `rulers` columns of the text area with `ui.virtual.ruler`, keeping the
+    /// text drawn in them.
+    pub fn render_rulers(
+        view: &View,
+        viewport: Rect,
+        surface: &mut Surface,
+        theme: &Theme,
+        rulers: &[u16],
+    ) {
+        let style = theme.get("ui.virtual.ruler");
+        for &ruler in rulers {
+            // columns are counted from 1, and may be scrolled out of view
+            let column = match (ruler as usize)
+                .checked_sub(1)
+                .and_then(|column| column.checked_sub(view.offset.col))
+            {
+                Some(column) if column < viewport.width as usize => column as u16,
+                _ => continue,
+            };
+            surface.set_style(
+                Rect::new(viewport.x + column, viewport.y, 1, viewport.height),
+                style,
+            );
+        }
+    }
+
+    /// Paints the background of the lines with a cursor across the text area, the primary
+    /// cursor's line with `ui.cursorline.primary`.
+    pub fn render_cursorline(doc: &Document, view: &View, surface: &mut Surface, theme: &Theme) {
+        let text = doc.text().slice(..);
+        let selection = doc.selection(view.id);
+        let primary_line = selection.primary().cursor_line(text);
+
+        let secondary = theme.try_get("ui.cursorline");
+        let primary = theme.try_get("ui.cursorline.primary").or(secondary);
+
+        let inner = view.inner_area();
+        for (i, &(line, _)) in view.rows(doc).iter().enumerate() {
+            let style = if line == primary_line {
+                primary
+            } else if selection
+                .iter()
+                .any(|range| range.cursor_line(text) == line)
+            {
+                secondary
+            } else {
+                None
+            };
+            if let Some(style) = style {
+                surface.set_style(
+                    Rect::new(inner.x, inner.y + i as u16, inner.width, 1),
+                    style,
+                );
+            }
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn render_gutter(
         doc: &Document,
@@ -412,42 +852,94 @@ impl EditorView {
         config: &helix_view::editor::Config,
     ) {
         let text = doc.text().slice(..);
-        let last_line = view.last_line(doc);
+        // long lines take up several rows with soft-wrap, the gutters are only shown on the first
+        let rows = view.rows(doc);
+
+        let selection = doc.selection(view.id);
 
         // it's used inside an iterator so the collect isn't needless:
         // https://github.com/rust-lang/rust-clippy/issues/6164
         #[allow(clippy::needless_collect)]
-        let cursors: Vec<_> = doc
-            .selection(view.id)
+        let cursors: Vec<_> = selection
             .iter()
             .map(|range| range.cursor_line(text))
             .collect();
 
-        let mut offset = 0;
+        let selected_lines: Vec<_> = selection
+            .iter()
+            .map(|range| range.line_range(text))
+            .collect();
 
         let gutter_style = theme.get("ui.gutter");
+        let cursorline_style = theme.try_get("ui.cursorline").filter(|_| is_focused);
+
+        let inner = view.inner_area();
+        let gutter_width = view.gutter_width();
+        // the x coordinates the gutters on either side start at
+        let mut origins = Vec::with_capacity(2);
+        if view.gutter_position.left() {
+            origins.push((viewport.x, false));
+        }
+        if view.gutter_position.right() {
+            // +1 for the space between the text and the gutters
+            origins.push((inner.right() + 1, true));
+        }
+
+        // paint the whole gutter of the cursor lines, including the padding next to the text
+        if let Some(cursorline_style) = cursorline_style {
+            for &(origin, mirrored) in &origins {
+                let x = if mirrored { origin - 1 } else { origin };
+                for (i, (line, _)) in rows.iter().enumerate() {
+                    if cursors.contains(line) {
+                        surface.set_style(
+                            Rect::new(x, viewport.y + i as u16, gutter_width, 1),
+                            gutter_style.patch(cursorline_style),
+                        );
+                    }
+                }
+            }
+        }
 
         // avoid lots of small allocations by reusing a text buffer for each line
         let mut text = String::with_capacity(8);
 
-        for (constructor, width) in view.gutters() {
-            let gutter = constructor(doc, view, theme, config, is_focused, *width);
-            text.reserve(*width); // ensure there's enough space for the gutter
-            for (i, line) in (view.offset.row..(last_line + 1)).enumerate() {
-                let selected = cursors.contains(&line);
-
-                if let Some(style) = gutter(line, selected, &mut text) {
-                    surface.set_stringn(
-                        viewport.x + offset,
-                        viewport.y + i as u16,
-                        &text,
-                        *width,
-                        gutter_style.patch(style),
-                    );
+        for (mut x, mirrored) in origins {
+            // on the right the gutters are mirrored, so the line numbers stay next to the text
+            let mut gutters = view.gutters();
+            if mirrored {
+                gutters.reverse();
+            }
+            for (constructor, width) in gutters {
+                let gutter = constructor(doc, view, theme, config, is_focused, width);
+                text.reserve(width); // ensure there's enough space for the gutter
+                for (i, &(line, first_row)) in rows.iter().enumerate() {
+                    if !first_row {
+                        continue;
+                    }
+                    let selected = selected_lines
+                        .iter()
+                        .any(|&(start, end)| (start..=end).contains(&line));
+
+                    let base_style = match cursorline_style {
+                        Some(cursorline_style) if cursors.contains(&line) => {
+                            gutter_style.patch(cursorline_style)
+                        }
+                        _ => gutter_style,
+                    };
+
+                    if let Some(style) = gutter(line, selected, &mut text) {
+                        surface.set_stringn(
+                            x,
+                            viewport.y + i as u16,
+                            &text,
+                            width,
+                            base_style.patch(style),
+                        );
+                    }
+                    text.clear();
                 }
-                text.clear();
+                x += width as u16;
             }
-            offset += *width as u16;
         }
     }
 
@@ -511,10 +1003,12 @@ impl EditorView {
         &self,
         doc: &Document,
         view: &View,
+        alternate: Option<&Document>,
         viewport: Rect,
         surface: &mut Surface,
         theme: &Theme,
         is_focused: bool,
+        config: &helix_view::editor::Config,
     ) {
         use tui::text::{Span, Spans};
 
@@ -534,6 +1028,7 @@ impl EditorView {
                     .get(srv.id())
                     .and_then(|spinner| spinner.frame())
             })
+            .or_else(|| self.job_spinner.frame())
             .unwrap_or("");
 
         let base_style = if is_focused {
@@ -585,6 +1080,44 @@ impl EditorView {
                 .push(Span::styled(format!(" {} ", count), base_style));
         }
 
+        // Background jobs
+        if self.running_jobs > 0 {
+            right_side_text.0.push(Span::styled(
+                format!(
+                    " {} job{} ",
+                    self.running_jobs,
+                    if self.running_jobs == 1 { "" } else { "s" }
+                ),
+                base_style,
+            ));
+        }
+
+        // Time since the last save
+        if doc.is_modified() {
+            let unsaved = doc.last_saved_time().elapsed();
+            let style = if config.dim_unsaved_time {
+                dim_style(
+                    base_style,
+                    unsaved.as_secs_f32() / UNSAVED_DIM_DURATION.as_secs_f32(),
+                )
+            } else {
+                base_style
+            };
+            right_side_text.0.push(Span::styled(
+                format!(" {} unsaved ", format_duration(unsaved)),
+                style,
+            ));
+        }
+
+        // Position in the jumplist
+        if config.jumplist_indicator {
+            let (position, len) = view.jumps.position();
+            right_side_text.0.push(Span::styled(
+                format!(" jump {}/{} ", position, len),
+                base_style,
+            ));
+        }
+
         // Selections
         let sels_count = doc.selection(view.id).len();
         right_side_text.0.push(Span::styled(
@@ -609,6 +1142,17 @@ impl EditorView {
         //     _ => "indent:ERROR",
         // };
 
+        // Alternate file
+        if let Some(name) = alternate
+            .and_then(|doc| doc.path())
+            .and_then(|path| path.file_name())
+        {
+            right_side_text.0.push(Span::styled(
+                format!(" #{} ", name.to_string_lossy()),
+                base_style,
+            ));
+        }
+
         // Position
         let pos = coords_at_pos(
             doc.text().slice(..),
@@ -648,7 +1192,30 @@ impl EditorView {
                 .as_ref()
                 .map(|p| p.to_string_lossy())
                 .unwrap_or_else(|| SCRATCH_BUFFER_NAME.into());
-            format!("{}{}", path, if doc.is_modified() { "[+]" } else { "" })
+            let mut title = format!(
+                "{}{}{}{}",
+                path,
+                if doc.is_deleted() { "[deleted]" } else { "" },
+                if doc.is_modified() { "[+]" } else { "" },
+                if view.pinned { "[pinned]" } else { "" }
+            );
+
+            // Path to the value under the cursor in structured data files
+            if let Some((config, syntax)) = doc.language_config().zip(doc.syntax()) {
+                if data_path::SCOPES.contains(&config.scope.as_str()) {
+                    let text = doc.text().slice(..);
+                    let cursor = doc.selection(view.id).primary().cursor(text);
+                    let path = data_path::path_at(
+                        syntax.tree().root_node(),
+                        text,
+                        text.char_to_byte(cursor),
+                    );
+                    if !path.0.is_empty() {
+                        title.push_str(&format!(" > {}", path));
+                    }
+                }
+            }
+            title
         };
 
         surface.set_string_truncated(
@@ -676,9 +1243,33 @@ impl EditorView {
         event: KeyEvent,
     ) -> Option<KeymapResult> {
         self.autoinfo = None;
+        let chord = if cxt.editor.config.show_keys {
+            let mut chord = self.keymaps.pending().to_vec();
+            chord.push(event);
+            chord
+        } else {
+            Vec::new()
+        };
         let key_result = self.keymaps.get_mut(&mode).unwrap().get(event);
         self.autoinfo = key_result.sticky.map(|node| node.infobox());
 
+        if cxt.editor.config.show_keys {
+            let commands = match &key_result.kind {
+                KeymapResultKind::Matched(command) => Some(command.name().to_string()),
+                KeymapResultKind::MatchedSequence(commands) => Some(
+                    commands
+                        .iter()
+                        .map(|command| command.name())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                _ => None,
+            };
+            if let Some(commands) = commands {
+                Self::show_keys(&mut self.shown_keys, cxt, &chord, commands);
+            }
+        }
+
         match &key_result.kind {
             KeymapResultKind::Matched(command) => command.execute(cxt),
             KeymapResultKind::Pending(node) => self.autoinfo = Some(node.infobox()),
@@ -692,6 +1283,92 @@ impl EditorView {
         None
     }
 
+    /// Add a chord to the `show-keys` overlay and schedule a redraw for when
+    /// it expires.
+    fn show_keys(
+        shown_keys: &mut VecDeque<ShownKeys>,
+        cx: &mut commands::Context,
+        chord: &[KeyEvent],
+        commands: String,
+    ) {
+        let keys = chord
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let now = Instant::now();
+        match shown_keys.back_mut() {
+            Some(last) if last.keys == keys => {
+                last.count += 1;
+                last.time = now;
+            }
+            _ => {
+                if shown_keys.len() == SHOWN_KEYS_MAX {
+                    shown_keys.pop_front();
+                }
+                shown_keys.push_back(ShownKeys {
+                    keys,
+                    commands,
+                    count: 1,
+                    time: now,
+                });
+            }
+        }
+        cx.jobs.spawn(async {
+            tokio::time::sleep(SHOWN_KEYS_DURATION).await;
+            Ok(())
+        });
+    }
+
+    fn render_shown_keys(&mut self, area: Rect, surface: &mut Surface, theme: &Theme) {
+        self.shown_keys
+            .retain(|shown| shown.time.elapsed() < SHOWN_KEYS_DURATION);
+        if self.shown_keys.is_empty() {
+            return;
+        }
+
+        let lines: Vec<String> = self
+            .shown_keys
+            .iter()
+            .map(|shown| {
+                let count = match shown.count {
+                    1 => String::new(),
+                    n => format!(" ×{}", n),
+                };
+                format!("{}{}  {}", shown.keys, count, shown.commands)
+            })
+            .collect();
+        let width = lines.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 4; // +2 for border, +2 for margin
+        let height = lines.len() as u16 + 2; // +2 for border
+        let area = area.intersection(Rect::new(
+            area.right().saturating_sub(width),
+            area.y,
+            width,
+            height,
+        ));
+
+        let text_style = theme.get("ui.text");
+        let popup_style = text_style.patch(theme.get("ui.popup"));
+        surface.clear_with(area, popup_style);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(popup_style);
+        let inner = block.inner(area).inner(&Margin {
+            vertical: 0,
+            horizontal: 1,
+        });
+        block.render(area, surface);
+        for (i, line) in lines.iter().enumerate() {
+            surface.set_stringn(
+                inner.x,
+                inner.y + i as u16,
+                line,
+                inner.width as usize,
+                text_style,
+            );
+        }
+    }
+
     fn insert_mode(&mut self, cx: &mut commands::Context, event: KeyEvent) {
         if let Some(keyresult) = self.handle_keymap_event(Mode::Insert, cx, event) {
             match keyresult.kind {
@@ -746,7 +1423,17 @@ impl EditorView {
                 // set the register
                 cxt.register = cxt.editor.selected_register.take();
 
-                self.handle_keymap_event(mode, cxt, event);
+                match self.handle_keymap_event(mode, cxt, event) {
+                    Some(KeymapResult {
+                        kind: KeymapResultKind::NotFound,
+                        ..
+                    }) => cxt.editor.ring_bell(Severity::Error),
+                    Some(KeymapResult {
+                        kind: KeymapResultKind::Cancelled(_),
+                        ..
+                    }) if event != key!(Esc) => cxt.editor.ring_bell(Severity::Error),
+                    _ => (),
+                }
                 if self.keymaps.pending().is_empty() {
                     cxt.editor.count = None
                 }
@@ -754,6 +1441,19 @@ impl EditorView {
         }
     }
 
+    /// Gives up on a pending key sequence once `sequence-timeout` has passed.
+    /// Behaves like pressing escape: in insert mode the pending keys are
+    /// inserted as text.
+    pub fn cancel_pending_keys(&mut self, cx: &mut commands::Context) {
+        if self.keymaps.pending().is_empty() {
+            return;
+        }
+        match doc!(cx.editor).mode() {
+            Mode::Insert => self.insert_mode(cx, key!(Esc)),
+            mode => self.command_mode(mode, cx, key!(Esc)),
+        }
+    }
+
     pub fn set_completion(
         &mut self,
         editor: &mut Editor,
@@ -805,6 +1505,30 @@ impl EditorView {
             } => {
                 let editor = &mut cxt.editor;
 
+                let tab = self
+                    .bufferline_tabs
+                    .iter()
+                    .find(|tab| tab.label.contains(column, row) || tab.close.contains(column, row));
+                if let Some(tab) = tab {
+                    if tab.close.contains(column, row) {
+                        if let Err(err) = editor.close_document(tab.doc, false) {
+                            editor.set_error(err.to_string());
+                        }
+                    } else {
+                        editor.switch_in_view(tab.doc);
+                    }
+                    return EventResult::Consumed(None);
+                }
+
+                if let Some((container, index)) = editor.tree.separator_at(row, column) {
+                    self.dragged_separator = Some(DraggedSeparator {
+                        container,
+                        index,
+                        moved: false,
+                    });
+                    return EventResult::Consumed(None);
+                }
+
                 let result = editor.tree.views().find_map(|(view, _focus)| {
                     view.pos_at_screen_coords(&editor.documents[&view.doc], row, column)
                         .map(|pos| (pos, view.id))
@@ -825,6 +1549,42 @@ impl EditorView {
                     return EventResult::Consumed(None);
                 }
 
+                // clicking the gutter selects the whole line
+                let result = editor.tree.views().find_map(|(view, _focus)| {
+                    let doc = &editor.documents[&view.doc];
+                    let text = doc.text().slice(..);
+                    view.gutter_line_at_screen_coords(&text, row, column, doc.tab_width())
+                        .map(|line| (line, view.id))
+                });
+
+                if let Some((line, view_id)) = result {
+                    let doc = editor.document_mut(editor.tree.get(view_id).doc).unwrap();
+                    let text = doc.text().slice(..);
+                    let range = Range::new(text.line_to_char(line), text.line_to_char(line + 1));
+
+                    if modifiers == crossterm::event::KeyModifiers::ALT {
+                        let selection = doc.selection(view_id).clone();
+                        doc.set_selection(view_id, selection.push(range));
+                    } else {
+                        doc.set_selection(view_id, Selection::single(range.anchor, range.head));
+                    }
+
+                    editor.tree.focus = view_id;
+
+                    return EventResult::Consumed(None);
+                }
+
+                // clicking anywhere else in a view, like its statusline, still focuses it
+                let result = editor
+                    .tree
+                    .views()
+                    .find(|(view, _focus)| view.area.contains(column, row));
+
+                if let Some((view, _focus)) = result {
+                    editor.tree.focus = view.id;
+                    return EventResult::Consumed(None);
+                }
+
                 EventResult::Ignored
             }
 
@@ -834,8 +1594,40 @@ impl EditorView {
                 column,
                 ..
             } => {
+                if let Some(separator) = &mut self.dragged_separator {
+                    separator.moved = true;
+                    cxt.editor.tree.move_separator(
+                        separator.container,
+                        separator.index,
+                        row,
+                        column,
+                    );
+                    return EventResult::Consumed(None);
+                }
+
                 let (view, doc) = current!(cxt.editor);
 
+                let text = doc.text().slice(..);
+                if let Some(line) =
+                    view.gutter_line_at_screen_coords(&text, row, column, doc.tab_width())
+                {
+                    // dragging over the gutter extends the selection line-wise
+                    let mut selection = doc.selection(view.id).clone();
+                    let primary = selection.primary_mut();
+                    let anchor_line = if primary.head >= primary.anchor {
+                        text.char_to_line(primary.anchor)
+                    } else {
+                        text.char_to_line(primary.anchor.saturating_sub(1))
+                    };
+                    *primary = if line >= anchor_line {
+                        Range::new(text.line_to_char(anchor_line), text.line_to_char(line + 1))
+                    } else {
+                        Range::new(text.line_to_char(anchor_line + 1), text.line_to_char(line))
+                    };
+                    doc.set_selection(view.id, selection);
+                    return EventResult::Consumed(None);
+                }
+
                 let pos = match view.pos_at_screen_coords(doc, row, column) {
                     Some(pos) => pos,
                     None => return EventResult::Ignored,
@@ -882,8 +1674,25 @@ impl EditorView {
 
             MouseEvent {
                 kind: MouseEventKind::Up(MouseButton::Left),
+                row,
+                column,
                 ..
             } => {
+                if let Some(separator) = self.dragged_separator.take() {
+                    if !separator.moved {
+                        let view = cxt
+                            .editor
+                            .tree
+                            .views()
+                            .find(|(view, _focus)| view.area.contains(column, row))
+                            .map(|(view, _focus)| view.id);
+                        if let Some(view_id) = view {
+                            cxt.editor.tree.focus = view_id;
+                        }
+                    }
+                    return EventResult::Consumed(None);
+                }
+
                 if !cxt.editor.config.middle_click_paste {
                     return EventResult::Ignored;
                 }
@@ -940,6 +1749,34 @@ impl EditorView {
     }
 }
 
+/// How long it takes for the time since the last save to fade out.
+const UNSAVED_DIM_DURATION: Duration = Duration::from_secs(30 * 60);
+
+/// Formats a duration as its largest unit, like `5m`.
+fn format_duration(duration: Duration) -> String {
+    match duration.as_secs() {
+        secs if secs < 60 => format!("{}s", secs),
+        secs if secs < 60 * 60 => format!("{}m", secs / 60),
+        secs => format!("{}h", secs / (60 * 60)),
+    }
+}
+
+/// Moves the foreground of `style` towards its background by `fraction`. Colors
+/// other than RGB are dimmed once `fraction` reaches one half.
+fn dim_style(style: Style, fraction: f32) -> Style {
+    let fraction = fraction.clamp(0.0, 1.0);
+    match (style.fg, style.bg) {
+        (Some(Color::Rgb(fr, fg, fb)), Some(Color::Rgb(br, bg, bb))) => {
+            // stop short of the background to keep it legible
+            let fraction = fraction * 0.7;
+            let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * fraction) as u8;
+            style.fg(Color::Rgb(mix(fr, br), mix(fg, bg), mix(fb, bb)))
+        }
+        _ if fraction >= 0.5 => style.add_modifier(Modifier::DIM),
+        _ => style,
+    }
+}
+
 impl Component for EditorView {
     fn handle_event(
         &mut self,
@@ -963,7 +1800,22 @@ impl Component for EditorView {
             }
             Event::Key(key) => {
                 cx.editor.reset_idle_timer();
+                // stale as soon as the cursor or the text might have changed
+                view_mut!(cx.editor).document_highlights.clear();
                 let mut key = KeyEvent::from(key);
+                if !cx.editor.sticky_modifiers.is_empty() {
+                    // escape only releases the latched modifiers
+                    if key == key!(Esc) {
+                        cx.editor.sticky_modifiers = KeyModifiers::NONE;
+                        return EventResult::Consumed(None);
+                    }
+                    let mode = doc!(cx.editor).mode();
+                    let keymap = match self.on_next_key {
+                        Some(_) => None,
+                        None => self.keymaps.get(&mode),
+                    };
+                    take_sticky_modifiers(&mut cx.editor.sticky_modifiers, keymap, &mut key);
+                }
                 canonicalize_key(&mut key);
 
                 // clear status
@@ -972,6 +1824,12 @@ impl Component for EditorView {
                 let doc = doc!(cx.editor);
                 let mode = doc.mode();
 
+                // keys awaited by a command (like the character for `f`) are
+                // text, leave them as typed
+                if mode != Mode::Insert && self.on_next_key.is_none() {
+                    key = cx.editor.config.keyboard_layout.to_qwerty(key);
+                }
+
                 if let Some(on_next_key) = self.on_next_key.take() {
                     // if there's a command waiting input, do that first
                     on_next_key(&mut cx, key);
@@ -1020,6 +1878,11 @@ impl Component for EditorView {
                 }
 
                 self.on_next_key = cx.on_next_key_callback.take();
+                if self.keymaps.pending().is_empty() {
+                    cx.editor.clear_sequence_timer();
+                } else {
+                    cx.editor.reset_sequence_timer();
+                }
                 // appease borrowck
                 let callback = cx.callback.take();
 
@@ -1068,14 +1931,34 @@ impl Component for EditorView {
         surface.set_style(area, cx.editor.theme.get("ui.background"));
 
         // if the terminal size suddenly changed, we need to trigger a resize
-        cx.editor.resize(area.clip_bottom(1)); // -1 from bottom for commandline
+        let mut editor_area = area.clip_bottom(1); // -1 from bottom for commandline
+        if cx.editor.config.bufferline {
+            self.render_bufferline(cx.editor, editor_area.with_height(1), surface);
+            editor_area = editor_area.clip_top(1);
+        } else {
+            self.bufferline_tabs.clear();
+        }
+        cx.editor.resize(editor_area);
+
+        self.running_jobs = cx.editor.tasks.len();
+        if self.running_jobs == 0 {
+            self.job_spinner.stop();
+        } else if self.job_spinner.is_stopped() {
+            self.job_spinner.start();
+        }
 
         for (view, is_focused) in cx.editor.tree.views() {
             let doc = cx.editor.document(view.doc).unwrap();
+            // the file `goto_last_accessed_file` switches to
+            let alternate = view
+                .last_accessed_doc
+                .filter(|&id| id != view.doc)
+                .and_then(|id| cx.editor.document(id));
             let loader = &cx.editor.syn_loader;
             self.render_view(
                 doc,
                 view,
+                alternate,
                 area,
                 surface,
                 &cx.editor.theme,
@@ -1085,6 +1968,20 @@ impl Component for EditorView {
             );
         }
 
+        if cx.editor.bell {
+            // flash the statusline of the focused view
+            let area = view!(cx.editor).area;
+            let style = cx
+                .editor
+                .theme
+                .try_get("ui.statusline.bell")
+                .unwrap_or_else(|| Style::default().add_modifier(Modifier::REVERSED));
+            surface.set_style(
+                Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1),
+                style,
+            );
+        }
+
         if cx.editor.config.auto_info {
             if let Some(ref mut info) = self.autoinfo {
                 info.render(area, surface, cx);
@@ -1097,7 +1994,6 @@ impl Component for EditorView {
         // render status msg
         if let Some((status_msg, severity)) = &cx.editor.status_msg {
             status_msg_width = status_msg.width();
-            use helix_view::editor::Severity;
             let style = if *severity == Severity::Error {
                 cx.editor.theme.get("error")
             } else {
@@ -1125,6 +2021,15 @@ impl Component for EditorView {
                     disp.push_str(&s);
                 }
             }
+            for (modifier, prefix) in [
+                (KeyModifiers::CONTROL, "C-"),
+                (KeyModifiers::ALT, "A-"),
+                (KeyModifiers::SHIFT, "S-"),
+            ] {
+                if cx.editor.sticky_modifiers.contains(modifier) {
+                    disp.push_str(prefix);
+                }
+            }
             let style = cx.editor.theme.get("ui.text");
             let macro_width = if cx.editor.macro_recording.is_some() {
                 3
@@ -1155,6 +2060,10 @@ impl Component for EditorView {
         if let Some(completion) = self.completion.as_mut() {
             completion.render(area, surface, cx);
         }
+
+        if cx.editor.config.show_keys {
+            self.render_shown_keys(area.clip_bottom(1), surface, &cx.editor.theme);
+        }
     }
 
     fn cursor(&self, _area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
@@ -1166,6 +2075,35 @@ impl Component for EditorView {
     }
 }
 
+/// Adds the modifiers `latched` with the `sticky_*` commands to `key` and
+/// releases them. If `key` runs another `sticky_*` command in `keymap` it's
+/// left alone, so that its modifier is latched along with the others.
+fn take_sticky_modifiers(latched: &mut KeyModifiers, keymap: Option<&Keymap>, key: &mut KeyEvent) {
+    let sticky = keymap
+        .and_then(|keymap| {
+            let mut key = *key;
+            canonicalize_key(&mut key);
+            keymap.peek(key)
+        })
+        .map_or(false, |cmd| {
+            matches!(cmd.name(), "sticky_ctrl" | "sticky_alt" | "sticky_shift")
+        });
+    if !sticky {
+        apply_sticky_modifiers(key, std::mem::replace(latched, KeyModifiers::NONE));
+    }
+}
+
+/// Adds modifiers latched with the `sticky_*` commands to `key`, as if they
+/// had been held down.
+fn apply_sticky_modifiers(key: &mut KeyEvent, modifiers: KeyModifiers) {
+    key.modifiers.insert(modifiers);
+    if let KeyCode::Char(ch) = key.code {
+        if modifiers.contains(KeyModifiers::SHIFT) {
+            key.code = KeyCode::Char(ch.to_uppercase().next().unwrap_or(ch));
+        }
+    }
+}
+
 fn canonicalize_key(key: &mut KeyEvent) {
     if let KeyEvent {
         code: KeyCode::Char(_),
@@ -1175,3 +2113,250 @@ fn canonicalize_key(key: &mut KeyEvent) {
         key.modifiers.remove(KeyModifiers::SHIFT)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use helix_core::Rope;
+    use helix_view::theme::DEFAULT_THEME;
+
+    fn render(text: &str, offset: Position, width: u16) -> Vec<String> {
+        let doc = Document::from(Rope::from(text), None);
+        let area = Rect::new(0, 0, width + 1, 2);
+        let viewport = Rect::new(0, 0, width, 2);
+        let mut surface = Surface::empty(area);
+        // a border next to the view, which must not be drawn over
+        for y in 0..area.height {
+            surface.set_string(width, y, "|", Style::default());
+        }
+        let highlights = std::iter::once(HighlightEvent::Source {
+            start: 0,
+            end: doc.text().len_chars(),
+        });
+        EditorView::render_text_highlights(
+            &doc,
+            offset,
+            viewport,
+            &mut surface,
+            &DEFAULT_THEME,
+            highlights,
+            false,
+            false,
+        );
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| surface.get(x, y).symbol.as_str())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wide_graphemes_clipped_at_edge() {
+        // wide graphemes take two cells, the one crossing the edge isn't drawn
+        assert_eq!(render("中文字\n", Position::new(0, 0), 5)[0], "中 文  |");
+        // combining characters don't take any cells
+        assert_eq!(
+            render("e\u{301}ab\n", Position::new(0, 0), 5)[0],
+            "e\u{301}ab  |"
+        );
+        // tabs are clipped too
+        assert_eq!(render("ab\tc\n", Position::new(0, 0), 5)[0], "ab   |");
+        // scrolled halfway into a wide grapheme
+        assert_eq!(render("中文\n", Position::new(0, 1), 5)[0], " 文   |");
+    }
+
+    #[test]
+    fn test_indent_guides() {
+        let mut doc = Document::from(
+            Rope::from("fn a() {\n    if b {\n\n        c\n\t\td\n    }\n"),
+            None,
+        );
+        doc.indent_style = IndentStyle::Spaces(4);
+        let mut view = View::new(doc.id());
+        view.area = Rect::new(0, 0, 20, 7);
+        view.gutters = Vec::new();
+        let inner = view.inner_area();
+        let mut surface = Surface::empty(view.area);
+
+        let highlights = std::iter::once(HighlightEvent::Source {
+            start: 0,
+            end: doc.text().len_chars(),
+        });
+        EditorView::render_text_highlights(
+            &doc,
+            view.offset,
+            inner,
+            &mut surface,
+            &DEFAULT_THEME,
+            highlights,
+            false,
+            false,
+        );
+        EditorView::render_indent_guides(&doc, &view, inner, &mut surface, &DEFAULT_THEME);
+        let rows: Vec<String> = (0..6)
+            .map(|y| {
+                (inner.x..inner.x + 10)
+                    .map(|x| surface.get(x, y).symbol.as_str())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "fn a() {",
+                "│   if b {",
+                "",
+                "│   │   c",
+                "│   │   d",
+                "│   }",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rulers() {
+        let theme: Theme = toml::from_str(r#""ui.virtual.ruler" = { bg = "red" }"#).unwrap();
+        let mut view = View::new(DocumentId::default());
+        let viewport = Rect::new(10, 0, 20, 3);
+        let mut surface = Surface::empty(Rect::new(0, 0, 40, 3));
+        let rulers = |surface: &Surface| -> Vec<u16> {
+            (0..40)
+                .filter(|&x| surface.get(x, 1).bg == Color::Red)
+                .collect()
+        };
+
+        EditorView::render_rulers(&view, viewport, &mut surface, &theme, &[0, 1, 5, 20, 21]);
+        assert_eq!(rulers(&surface), [10, 14, 29]);
+
+        // scrolled horizontally
+        let mut surface = Surface::empty(Rect::new(0, 0, 40, 3));
+        view.offset.col = 4;
+        EditorView::render_rulers(&view, viewport, &mut surface, &theme, &[1, 5, 20, 21]);
+        assert_eq!(rulers(&surface), [10, 25, 26]);
+    }
+
+    #[test]
+    fn test_cursorline() {
+        let theme: Theme = toml::from_str(
+            r#"
+            "ui.cursorline" = { bg = "red" }
+            "ui.cursorline.primary" = { bg = "blue" }
+            "#,
+        )
+        .unwrap();
+        let mut doc = Document::from(Rope::from("a\nb\nc\nd\n"), None);
+        let mut view = View::new(doc.id());
+        view.area = Rect::new(0, 0, 20, 5);
+        view.gutters = Vec::new();
+        // the primary cursor on the third line, another one on the first
+        doc.set_selection(
+            view.id,
+            Selection::new(vec![Range::point(0), Range::point(4)].into(), 1),
+        );
+
+        let mut surface = Surface::empty(view.area);
+        EditorView::render_cursorline(&doc, &view, &mut surface, &theme);
+        let backgrounds: Vec<_> = (0..4).map(|y| surface.get(5, y).bg).collect();
+        assert_eq!(
+            backgrounds,
+            [Color::Red, Color::Reset, Color::Blue, Color::Reset]
+        );
+        // the whole text area is painted, but not the space before the gutters
+        assert_eq!(
+            surface.get(view.inner_area().right() - 1, 2).bg,
+            Color::Blue
+        );
+        assert_eq!(surface.get(0, 2).bg, Color::Reset);
+    }
+
+    #[test]
+    fn test_sticky_modifiers_accumulate() {
+        use crate::commands::MappableCommand;
+
+        let mut keymap = Keymaps::default().0.remove(&Mode::Normal).unwrap();
+        let f1: KeyEvent = "F1".parse().unwrap();
+        let f2: KeyEvent = "F2".parse().unwrap();
+        keymap.bind(&[f1], MappableCommand::sticky_ctrl);
+        keymap.bind(&[f2], MappableCommand::sticky_alt);
+
+        // F1 latched ctrl, F2 adds alt instead of being pressed as C-F2
+        let mut latched = KeyModifiers::CONTROL;
+        let mut key = f2;
+        take_sticky_modifiers(&mut latched, Some(&keymap), &mut key);
+        assert_eq!(key, f2);
+        assert_eq!(latched, KeyModifiers::CONTROL);
+        latched.toggle(KeyModifiers::ALT);
+
+        // the next key gets both
+        let mut key = key!('x');
+        take_sticky_modifiers(&mut latched, Some(&keymap), &mut key);
+        assert_eq!(key.modifiers, KeyModifiers::CONTROL | KeyModifiers::ALT);
+        assert!(latched.is_empty());
+
+        // keys awaited by a command always take the modifiers
+        let mut latched = KeyModifiers::CONTROL;
+        let mut key = f2;
+        take_sticky_modifiers(&mut latched, None, &mut key);
+        assert_eq!(key.modifiers, KeyModifiers::CONTROL);
+    }
+
+    #[test]
+    fn test_bufferline_scrolls_to_active_tab() {
+        let labels: Vec<String> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|name| format!(" {} ", name))
+            .collect();
+        let area = Rect::new(0, 0, 16, 1);
+        let draw = |active| {
+            let mut surface = Surface::empty(area);
+            let tabs = EditorView::draw_bufferline(
+                &labels,
+                Some(active),
+                area,
+                &mut surface,
+                Style::default(),
+                Style::default(),
+            );
+            let row: String = (0..area.width)
+                .map(|x| surface.get(x, 0).symbol.as_str())
+                .collect();
+            (row, tabs)
+        };
+        let click = |tabs: &[(usize, Rect, Rect)], column| {
+            tabs.iter()
+                .find(|(_, label, _)| label.contains(column, 0))
+                .map(|(i, _, _)| *i)
+        };
+
+        // the first tabs fit, the rest are hidden on the right
+        let (row, tabs) = draw(0);
+        assert_eq!(row, "   a ×  b ×    >");
+        assert_eq!(click(&tabs, 3), Some(0));
+        assert_eq!(click(&tabs, 8), Some(1));
+
+        // the active tab at the end scrolls the others out on the left
+        let (row, tabs) = draw(4);
+        assert_eq!(row, "<  d ×  e ×     ");
+        assert_eq!(click(&tabs, 3), Some(3));
+        assert_eq!(click(&tabs, 8), Some(4));
+        assert!(tabs.iter().any(|(_, _, close)| close.contains(10, 0)));
+
+        // everything fits in a wider bufferline
+        let area = Rect::new(0, 0, 30, 1);
+        let mut surface = Surface::empty(area);
+        let tabs = EditorView::draw_bufferline(
+            &labels,
+            Some(4),
+            area,
+            &mut surface,
+            Style::default(),
+            Style::default(),
+        );
+        assert_eq!(tabs.len(), 5);
+        assert_eq!(tabs[0].1.x, 0);
+    }
+}
@@ -1,4 +1,7 @@
+mod color_picker;
 mod completion;
+mod confirm;
+mod diff_preview;
 pub(crate) mod editor;
 mod info;
 mod markdown;
@@ -6,21 +9,29 @@ mod menu;
 mod picker;
 mod popup;
 mod prompt;
+mod signature_help;
 mod spinner;
+mod staging;
 mod text;
 
+pub use color_picker::{ColorLiteral, ColorPicker};
 pub use completion::Completion;
+pub use confirm::{Choices, Confirm};
+pub use diff_preview::DiffPreview;
 pub use editor::EditorView;
 pub use markdown::Markdown;
 pub use menu::Menu;
 pub use picker::{FilePicker, Picker};
 pub use popup::Popup;
 pub use prompt::{Prompt, PromptEvent};
+pub use signature_help::SignatureHelp;
 pub use spinner::{ProgressSpinners, Spinner};
+pub use staging::Staging;
 pub use text::Text;
 
 use helix_core::regex::Regex;
 use helix_core::regex::RegexBuilder;
+use helix_core::search::Normalization;
 use helix_view::{Document, Editor, View};
 
 use std::path::PathBuf;
@@ -36,6 +47,8 @@ pub fn regex_prompt(
     let view_id = view.id;
     let snapshot = doc.selection(view_id).clone();
     let offset_snapshot = view.offset;
+    // a highlight kept from an earlier search
+    let highlight_snapshot = view.search_highlight.clone();
 
     Prompt::new(
         prompt,
@@ -47,9 +60,11 @@ pub fn regex_prompt(
                     let (view, doc) = current!(cx.editor);
                     doc.set_selection(view.id, snapshot.clone());
                     view.offset = offset_snapshot;
+                    view.search_highlight = highlight_snapshot.clone();
                 }
                 PromptEvent::Validate => {
                     // TODO: push_jump to store selection just before jump
+                    view_mut!(cx.editor).search_highlight = highlight_snapshot.clone();
 
                     match Regex::new(input) {
                         Ok(regex) => {
@@ -62,6 +77,7 @@ pub fn regex_prompt(
                 PromptEvent::Update => {
                     // skip empty input, TODO: trigger default
                     if input.is_empty() {
+                        view_mut!(cx.editor).search_highlight = highlight_snapshot.clone();
                         return;
                     }
 
@@ -80,6 +96,7 @@ pub fn regex_prompt(
 
                             // revert state to what it was before the last update
                             doc.set_selection(view.id, snapshot.clone());
+                            view.search_highlight = Some((regex.clone(), Normalization::None));
 
                             fun(view, doc, regex, event);
 
@@ -210,6 +227,27 @@ pub mod completers {
         names
     }
 
+    pub fn template(input: &str) -> Vec<Completion> {
+        let names = std::fs::read_dir(helix_core::config_dir().join("templates"))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned());
+
+        let matcher = Matcher::default();
+
+        let mut matches: Vec<_> = names
+            .filter_map(|name| matcher.fuzzy_match(&name, input).map(|score| (name, score)))
+            .collect();
+
+        matches.sort_unstable_by_key(|(_name, score)| Reverse(*score));
+        matches
+            .into_iter()
+            .map(|(name, _)| ((0..), name.into()))
+            .collect()
+    }
+
     pub fn setting(input: &str) -> Vec<Completion> {
         static KEYS: Lazy<Vec<String>> = Lazy::new(|| {
             serde_json::to_value(Config::default())
@@ -235,6 +273,27 @@ pub mod completers {
             .collect()
     }
 
+    pub fn command(input: &str) -> Vec<Completion> {
+        use crate::commands::MappableCommand;
+
+        let matcher = Matcher::default();
+
+        let mut matches: Vec<_> = MappableCommand::STATIC_COMMAND_LIST
+            .iter()
+            .filter_map(|command| {
+                matcher
+                    .fuzzy_match(command.name(), input)
+                    .map(|score| (command.name(), score))
+            })
+            .collect();
+
+        matches.sort_unstable_by_key(|(_name, score)| Reverse(*score));
+        matches
+            .into_iter()
+            .map(|(name, _)| ((0..), name.to_string().into()))
+            .collect()
+    }
+
     pub fn filename(input: &str) -> Vec<Completion> {
         filename_impl(input, |entry| {
             let is_dir = entry.file_type().map_or(false, |entry| entry.is_dir());
@@ -21,7 +21,7 @@ use std::{
 };
 
 use crate::ui::{Prompt, PromptEvent};
-use helix_core::Position;
+use helix_core::{Position, Rope};
 use helix_view::{
     editor::Action,
     graphics::{Color, CursorKind, Margin, Rect, Style},
@@ -34,6 +34,8 @@ pub const MAX_FILE_SIZE_FOR_PREVIEW: u64 = 10 * 1024 * 1024;
 
 /// File path and range of lines (used to align and highlight lines)
 type FileLocation = (PathBuf, Option<(usize, usize)>);
+/// Text shown as the preview of an item, instead of a file.
+type TextFn<T> = Box<dyn Fn(&T) -> String>;
 
 pub struct FilePicker<T> {
     picker: Picker<T>,
@@ -43,6 +45,8 @@ pub struct FilePicker<T> {
     read_buffer: Vec<u8>,
     /// Given an item in the picker, return the file path and line number to display.
     file_fn: Box<dyn Fn(&Editor, &T) -> Option<FileLocation>>,
+    /// Given an item in the picker, return text to display instead of a file.
+    text_fn: Option<TextFn<T>>,
 }
 
 pub enum CachedPreview {
@@ -95,9 +99,22 @@ impl<T> FilePicker<T> {
             preview_cache: HashMap::new(),
             read_buffer: Vec::with_capacity(1024),
             file_fn: Box::new(preview_fn),
+            text_fn: None,
         }
     }
 
+    /// Creates a picker previewing the text of its items rather than a file.
+    pub fn with_text_preview(
+        options: Vec<T>,
+        format_fn: impl Fn(&T) -> Cow<str> + 'static,
+        callback_fn: impl Fn(&mut Editor, &T, Action) + 'static,
+        text_fn: impl Fn(&T) -> String + 'static,
+    ) -> Self {
+        let mut picker = Self::new(options, format_fn, callback_fn, |_, _| None);
+        picker.text_fn = Some(Box::new(text_fn));
+        picker
+    }
+
     fn current_file(&self, editor: &Editor) -> Option<FileLocation> {
         self.picker
             .selection()
@@ -196,6 +213,33 @@ impl<T: 'static> Component for FilePicker<T> {
         let inner = inner.inner(&margin);
         block.render(preview_area, surface);
 
+        if let Some(text) = self
+            .text_fn
+            .as_ref()
+            .zip(self.picker.selection())
+            .map(|(text_fn, current)| text_fn(current))
+        {
+            let doc = Document::from(Rope::from(text), None);
+            let highlights = EditorView::doc_syntax_highlights(
+                &doc,
+                Position::default(),
+                inner.height,
+                &cx.editor.theme,
+                &cx.editor.syn_loader,
+            );
+            EditorView::render_text_highlights(
+                &doc,
+                Position::default(),
+                inner,
+                surface,
+                &cx.editor.theme,
+                highlights,
+                false,
+                false,
+            );
+            return;
+        }
+
         if let Some((path, range)) = self.current_file(cx.editor) {
             let preview = self.get_preview(&path, cx.editor);
             let doc = match preview.document() {
@@ -234,6 +278,8 @@ impl<T: 'static> Component for FilePicker<T> {
                 surface,
                 &cx.editor.theme,
                 highlights,
+                false,
+                false,
             );
 
             // highlight the line
@@ -264,6 +310,10 @@ impl<T: 'static> Component for FilePicker<T> {
     fn cursor(&self, area: Rect, ctx: &Editor) -> (Option<Position>, CursorKind) {
         self.picker.cursor(area, ctx)
     }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
 }
 
 pub struct Picker<T> {
@@ -544,4 +594,8 @@ impl<T: 'static> Component for Picker<T> {
 
         self.prompt.cursor(area, editor)
     }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
 }
@@ -540,4 +540,8 @@ impl Component for Prompt {
             CursorKind::Block,
         )
     }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
 }
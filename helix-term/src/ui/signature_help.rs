@@ -0,0 +1,114 @@
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    key,
+};
+use crossterm::event::Event;
+use tui::buffer::Buffer as Surface;
+
+use helix_view::{
+    document::Mode,
+    graphics::{Modifier, Rect},
+    input::KeyEvent,
+};
+use std::ops::Range;
+
+/// Shows the signature of the function call around the cursor while typing
+/// its arguments, with the current parameter highlighted. Keys go through to
+/// the editor, the popup closes when leaving insert mode.
+pub struct SignatureHelp {
+    signature: String,
+    /// Byte range of the current parameter in `signature`.
+    active_param: Option<Range<usize>>,
+    documentation: Vec<String>,
+}
+
+impl SignatureHelp {
+    pub const ID: &'static str = "signature-help";
+    const MAX_DOC_LINES: usize = 5;
+
+    pub fn new(signature: String, active_param: Option<Range<usize>>, documentation: &str) -> Self {
+        Self {
+            signature,
+            active_param,
+            documentation: documentation
+                .lines()
+                .take(Self::MAX_DOC_LINES)
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl Component for SignatureHelp {
+    fn handle_event(&mut self, event: Event, cx: &mut Context) -> EventResult {
+        let key = match event {
+            Event::Key(key) => KeyEvent::from(key),
+            _ => return EventResult::Ignored,
+        };
+
+        let (_, doc) = current_ref!(cx.editor);
+        if key != key!(Esc) && doc.mode() == Mode::Insert {
+            return EventResult::Ignored;
+        }
+        // close, and let the editor handle the key
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.remove(Self::ID);
+            compositor.handle_event(event, cx);
+        })))
+    }
+
+    fn render(&mut self, viewport: Rect, surface: &mut Surface, cx: &mut Context) {
+        let cursor = match cx.editor.cursor() {
+            (Some(cursor), _) if doc!(cx.editor).mode() == Mode::Insert => cursor,
+            _ => return,
+        };
+
+        let width = std::iter::once(&self.signature)
+            .chain(&self.documentation)
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or_default()
+            .min(viewport.width as usize) as u16;
+        let height = 1 + self.documentation.len() as u16;
+        let cursor_row = viewport.y + cursor.row as u16;
+        // above the cursor line, unless there's no room
+        let y = if cursor_row >= viewport.y + height {
+            cursor_row - height
+        } else {
+            cursor_row + 1
+        };
+        let x = (viewport.x + cursor.col as u16).min(viewport.right().saturating_sub(width));
+        let area = viewport.intersection(Rect::new(x, y, width, height));
+
+        let theme = &cx.editor.theme;
+        let style = theme.get("ui.popup").patch(theme.get("ui.text"));
+        surface.clear_with(area, style);
+        surface.set_stringn(area.x, area.y, &self.signature, area.width as usize, style);
+        // a range that isn't within the signature is left unhighlighted
+        let param = self.active_param.as_ref().and_then(|range| {
+            let before = self.signature.get(..range.start)?;
+            Some((before, self.signature.get(range.clone())?))
+        });
+        if let Some((before, param)) = param {
+            let col = before.chars().count() as u16;
+            if col < area.width {
+                surface.set_stringn(
+                    area.x + col,
+                    area.y,
+                    param,
+                    (area.width - col) as usize,
+                    style
+                        .patch(theme.get("ui.text.focus"))
+                        .add_modifier(Modifier::BOLD),
+                );
+            }
+        }
+        for (y, line) in (area.y + 1..area.bottom()).zip(&self.documentation) {
+            surface.set_stringn(area.x, y, line, area.width as usize, style);
+        }
+    }
+
+    fn id(&self) -> Option<&'static str> {
+        Some(Self::ID)
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{
+    compositor::{Callback, Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use crossterm::event::Event;
+use tui::buffer::Buffer as Surface;
+
+use helix_view::{graphics::Rect, input::KeyEvent, Editor};
+
+type ApplyCallback<T> = Box<dyn FnOnce(&mut Editor, Vec<T>)>;
+
+/// Lists changes with a checkbox each, to pick which ones to apply. Space toggles the change
+/// under the cursor and `a` toggles all of them, Enter applies the checked changes, `q` or Esc
+/// discards them all.
+pub struct Staging<T> {
+    header: String,
+    /// Changes and whether they're checked.
+    items: Vec<(T, bool)>,
+    cursor: usize,
+    scroll: usize,
+    format_fn: Box<dyn Fn(&T) -> String>,
+    apply_fn: Option<ApplyCallback<T>>,
+}
+
+impl<T> Staging<T> {
+    /// All the changes start checked.
+    pub fn new(
+        header: String,
+        items: Vec<T>,
+        format_fn: impl Fn(&T) -> String + 'static,
+        apply_fn: impl FnOnce(&mut Editor, Vec<T>) + 'static,
+    ) -> Self {
+        Self {
+            header,
+            items: items.into_iter().map(|item| (item, true)).collect(),
+            cursor: 0,
+            scroll: 0,
+            format_fn: Box::new(format_fn),
+            apply_fn: Some(Box::new(apply_fn)),
+        }
+    }
+
+    fn status_line(&self) -> String {
+        let checked = self.items.iter().filter(|(_, checked)| *checked).count();
+        format!(
+            "{} of {} checked. [space] toggle  [a] toggle all  [enter] apply  [q] cancel",
+            checked,
+            self.items.len()
+        )
+    }
+}
+
+impl<T: 'static> Component for Staging<T> {
+    fn handle_event(&mut self, event: Event, cx: &mut Context) -> EventResult {
+        let key = match event {
+            Event::Key(key) => KeyEvent::from(key),
+            _ => return EventResult::Ignored,
+        };
+        let close = || {
+            let close_fn: Callback = Box::new(|compositor: &mut Compositor, _| {
+                compositor.pop();
+            });
+            EventResult::Consumed(Some(close_fn))
+        };
+
+        match key {
+            key!('j') | key!(Down) | ctrl!('n') => {
+                self.cursor = (self.cursor + 1).min(self.items.len().saturating_sub(1));
+            }
+            key!('k') | key!(Up) | ctrl!('p') => {
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            key!(' ') => {
+                if let Some((_, checked)) = self.items.get_mut(self.cursor) {
+                    *checked = !*checked;
+                }
+            }
+            key!('a') => {
+                let all = self.items.iter().all(|(_, checked)| *checked);
+                for (_, checked) in &mut self.items {
+                    *checked = !all;
+                }
+            }
+            key!(Enter) => {
+                if let Some(apply_fn) = self.apply_fn.take() {
+                    let checked = std::mem::take(&mut self.items)
+                        .into_iter()
+                        .filter_map(|(item, checked)| if checked { Some(item) } else { None })
+                        .collect();
+                    apply_fn(cx.editor, checked);
+                }
+                return close();
+            }
+            key!('q') => return close(),
+            // the list keeps focus until it's applied or discarded
+            _ => (),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        let text_style = theme.get("ui.text");
+        let focus = text_style.patch(theme.get("ui.text.focus"));
+        let width = area.width as usize;
+
+        surface.set_stringn(area.x, area.y, &self.header, width, focus);
+        if area.height > 1 {
+            surface.set_stringn(area.x, area.y + 1, self.status_line(), width, text_style);
+        }
+
+        // keep the cursor in view
+        let height = area.height.saturating_sub(2) as usize;
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if height > 0 && self.cursor >= self.scroll + height {
+            self.scroll = self.cursor + 1 - height;
+        }
+
+        let rows = area.y + 2..area.bottom();
+        let items = self.items.iter().enumerate().skip(self.scroll);
+        for (y, (i, (item, checked))) in rows.zip(items) {
+            let line = format!(
+                "[{}] {}",
+                if *checked { "x" } else { " " },
+                (self.format_fn)(item)
+            );
+            let style = if i == self.cursor { focus } else { text_style };
+            surface.set_stringn(area.x, y, line, width, style);
+        }
+    }
+
+    fn required_size(&mut self, viewport: (u16, u16)) -> Option<(u16, u16)> {
+        let width = self
+            .items
+            .iter()
+            .map(|(item, _)| (self.format_fn)(item).chars().count() + 4)
+            .chain([
+                self.header.chars().count(),
+                self.status_line().chars().count(),
+            ])
+            .max()
+            .unwrap_or_default();
+        let height = self.items.len() + 2;
+        Some((
+            (width as u16).min(viewport.0),
+            (height as u16).min(viewport.1),
+        ))
+    }
+}
@@ -7,6 +7,7 @@ use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Instant;
 
 use helix_core::{
     encoding,
@@ -14,8 +15,8 @@ use helix_core::{
     indent::{auto_detect_indent_style, IndentStyle},
     line_ending::auto_detect_line_ending,
     syntax::{self, LanguageConfiguration},
-    ChangeSet, Diagnostic, LineEnding, Rope, RopeBuilder, Selection, State, Syntax, Transaction,
-    DEFAULT_LINE_ENDING,
+    timings, ChangeSet, Diagnostic, LineEnding, Rope, RopeBuilder, Selection, State, Syntax,
+    Transaction, DEFAULT_LINE_ENDING,
 };
 use helix_lsp::util::LspFormatting;
 
@@ -104,6 +105,13 @@ pub struct Document {
     pub savepoint: Option<Transaction>,
 
     last_saved_revision: usize,
+    /// When the document was last saved, or opened if it wasn't saved since.
+    last_saved_time: Instant,
+    /// The file at `path` is gone, it was deleted or moved by another program.
+    deleted: bool,
+    /// The file at `path` existed when it was set or since, so it going missing means it was
+    /// deleted rather than not created yet.
+    file_existed: bool,
     version: i32, // should be usize?
     pub(crate) modified_since_accessed: bool,
 
@@ -128,6 +136,7 @@ impl fmt::Debug for Document {
             .field("old_state", &self.old_state)
             // .field("history", &self.history)
             .field("last_saved_revision", &self.last_saved_revision)
+            .field("last_saved_time", &self.last_saved_time)
             .field("version", &self.version)
             .field("modified_since_accessed", &self.modified_since_accessed)
             .field("diagnostics", &self.diagnostics)
@@ -347,6 +356,9 @@ impl Document {
             history: Cell::new(History::default()),
             savepoint: None,
             last_saved_revision: 0,
+            last_saved_time: Instant::now(),
+            deleted: false,
+            file_existed: false,
             modified_since_accessed: false,
             language_server: None,
         }
@@ -365,7 +377,9 @@ impl Document {
         let (rope, encoding) = if path.exists() {
             let mut file =
                 std::fs::File::open(path).context(format!("unable to open {:?}", path))?;
-            from_reader(&mut file, encoding)?
+            timings::time(&format!("{}: read", path.display()), || {
+                from_reader(&mut file, encoding)
+            })?
         } else {
             let encoding = encoding.unwrap_or(encoding::UTF_8);
             (Rope::from(DEFAULT_LINE_ENDING.as_str()), encoding)
@@ -376,7 +390,9 @@ impl Document {
         // set the path and try detecting the language
         doc.set_path(Some(path))?;
         if let Some(loader) = config_loader {
-            doc.detect_language(theme, loader);
+            timings::time(&format!("{}: parse", path.display()), || {
+                doc.detect_language(theme, loader)
+            });
         }
 
         doc.detect_indent_and_line_ending();
@@ -568,7 +584,9 @@ impl Document {
 
         // if parent doesn't exist we still want to open the document
         // and error out when document is saved
+        self.file_existed = path.as_ref().map_or(false, |path| path.exists());
         self.path = path;
+        self.deleted = false;
 
         Ok(())
     }
@@ -830,6 +848,57 @@ impl Document {
         let current_revision = history.current_revision();
         self.history.set(history);
         self.last_saved_revision = current_revision;
+        self.last_saved_time = Instant::now();
+    }
+
+    /// The current history revision, to tell whether the document changed since.
+    pub fn current_revision(&self) -> usize {
+        let history = self.history.take();
+        let current_revision = history.current_revision();
+        self.history.set(history);
+        current_revision
+    }
+
+    /// The history revision that was last saved.
+    pub fn saved_revision(&self) -> usize {
+        self.last_saved_revision
+    }
+
+    /// Restores the last saved revision, so a document whose save failed after
+    /// [`Self::reset_modified`] is reported as modified again.
+    pub fn set_saved_revision(&mut self, revision: usize) {
+        self.last_saved_revision = revision;
+    }
+
+    /// Checks whether the file at [`Self::path`] still exists. Returns `true` if it went
+    /// missing since the last check.
+    pub fn detect_deleted(&mut self) -> bool {
+        let exists = match &self.path {
+            Some(path) => path.exists(),
+            None => return false,
+        };
+        // new files aren't deleted until they were written once
+        self.file_existed |= exists;
+        let deleted = self.file_existed && !exists;
+        let newly_deleted = deleted && !self.deleted;
+        self.deleted = deleted;
+        newly_deleted
+    }
+
+    /// Records that the file at [`Self::path`] was just written, so it exists again.
+    pub fn set_written(&mut self) {
+        self.file_existed = true;
+        self.deleted = false;
+    }
+
+    /// If the file was deleted or moved on disk, as of the last [`Self::detect_deleted`].
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
+    /// When the document was last saved, or opened if it wasn't saved since.
+    pub fn last_saved_time(&self) -> Instant {
+        self.last_saved_time
     }
 
     /// Current editing mode for the [`Document`].
@@ -1114,6 +1183,49 @@ mod test {
         );
     }
 
+    #[test]
+    fn detect_deleted_file() {
+        let path = std::env::temp_dir().join(format!("helix-deleted-{}.txt", std::process::id()));
+        std::fs::write(&path, "hello").unwrap();
+
+        let mut doc = Document::default();
+        doc.set_path(Some(&path)).unwrap();
+        assert!(!doc.detect_deleted());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(doc.detect_deleted());
+        assert!(doc.is_deleted());
+        // only reported once
+        assert!(!doc.detect_deleted());
+        assert!(doc.is_deleted());
+
+        std::fs::write(&path, "hello").unwrap();
+        assert!(!doc.detect_deleted());
+        assert!(!doc.is_deleted());
+        std::fs::remove_file(&path).unwrap();
+
+        // buffers without a file are never deleted
+        let mut scratch = Document::default();
+        assert!(!scratch.detect_deleted());
+    }
+
+    #[test]
+    fn detect_deleted_new_file() {
+        let path = std::env::temp_dir().join(format!("helix-new-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // a file that wasn't created yet isn't deleted
+        let mut doc = Document::open(&path, None, None, None).unwrap();
+        assert!(!doc.detect_deleted());
+        assert!(!doc.is_deleted());
+
+        // once written, removing it is reported
+        std::fs::write(&path, "hello").unwrap();
+        assert!(!doc.detect_deleted());
+        std::fs::remove_file(&path).unwrap();
+        assert!(doc.detect_deleted());
+    }
+
     #[test]
     fn test_line_ending() {
         assert_eq!(
@@ -1,8 +1,10 @@
 use crate::{
     clipboard::{get_clipboard_provider, ClipboardProvider},
-    document::SCRATCH_BUFFER_NAME,
+    document::{Mode, SCRATCH_BUFFER_NAME},
     graphics::{CursorKind, Rect},
-    input::KeyEvent,
+    input::{KeyEvent, KeyboardLayout},
+    keyboard::KeyModifiers,
+    tasks::Tasks,
     theme::{self, Theme},
     tree::{self, Tree},
     Document, DocumentId, View, ViewId,
@@ -10,22 +12,23 @@ use crate::{
 
 use futures_util::future;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap, VecDeque},
     io::stdin,
     num::NonZeroUsize,
     path::{Path, PathBuf},
     pin::Pin,
     sync::Arc,
+    time::SystemTime,
 };
 
-use tokio::time::{sleep, Duration, Instant, Sleep};
+use tokio::time::{sleep, sleep_until, Duration, Instant, Sleep};
 
 use anyhow::{bail, Error};
 
 pub use helix_core::diagnostic::Severity;
 pub use helix_core::register::Registers;
 use helix_core::syntax;
-use helix_core::{Position, Selection};
+use helix_core::{search::Normalization, Position, Range, Selection};
 
 use serde::{Deserialize, Serialize};
 
@@ -37,6 +40,36 @@ where
     Ok(Duration::from_millis(millis))
 }
 
+/// Shape of the primary cursor in each mode. With `block` the cursor is drawn by the editor using
+/// the `ui.cursor` theme scopes, other shapes use the terminal's cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct CursorShapeConfig {
+    pub normal: CursorKind,
+    pub insert: CursorKind,
+    pub select: CursorKind,
+}
+
+impl CursorShapeConfig {
+    pub fn from_mode(&self, mode: Mode) -> CursorKind {
+        match mode {
+            Mode::Normal => self.normal,
+            Mode::Insert => self.insert,
+            Mode::Select => self.select,
+        }
+    }
+}
+
+impl Default for CursorShapeConfig {
+    fn default() -> Self {
+        Self {
+            normal: CursorKind::Block,
+            insert: CursorKind::Block,
+            select: CursorKind::Block,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct FilePickerConfig {
@@ -90,23 +123,105 @@ pub struct Config {
     pub shell: Vec<String>,
     /// Line number mode.
     pub line_number: LineNumber,
+    /// Gutters shown next to the text, in order from the outside in. Defaults to diagnostics and line numbers.
+    pub gutters: Vec<GutterType>,
+    /// Which side of the text the gutters are shown on. Defaults to `left`.
+    pub gutter_position: GutterPosition,
+    /// Wrap lines that are longer than the view is wide instead of scrolling horizontally. Defaults to false.
+    pub soft_wrap: bool,
+    /// Show spaces, tabs and line endings with dim glyphs. Defaults to false.
+    pub render_whitespace: bool,
+    /// Show the open buffers as clickable tabs above the views. Defaults to false.
+    pub bufferline: bool,
+    /// Highlight the lines with a cursor across the whole view. Defaults to false.
+    pub cursorline: bool,
+    /// Columns to shade as rulers, counted from 1. Defaults to none.
+    pub rulers: Vec<u16>,
+    /// Draw a line at each level of indentation. Defaults to false.
+    pub indent_guides: bool,
     /// Middle click paste support. Defaults to true.
     pub middle_click_paste: bool,
+    /// Reindent linewise pastes to the indentation at the paste position. Defaults to false.
+    pub paste_reindent: bool,
+    /// Escape quotes and line breaks of text pasted inside a string literal. Defaults to false.
+    pub paste_escape_strings: bool,
     /// Smart case: Case insensitive searching unless pattern contains upper case characters. Defaults to true.
     pub smart_case: bool,
+    /// How text is normalized when searching, to match regardless of its Unicode normalization form or diacritics. Defaults to `none`.
+    pub search_normalization: Normalization,
+    /// Keep highlighting the matches of the last search once it's confirmed, until cleared with `:nohl`. Defaults to false.
+    pub persist_search_highlight: bool,
     /// Automatic insertion of pairs to parentheses, brackets, etc. Defaults to true.
     pub auto_pairs: bool,
     /// Automatic auto-completion, automatically pop up without user trigger. Defaults to true.
     pub auto_completion: bool,
-    /// Time in milliseconds since last keypress before idle timers trigger. Used for autocompletion, set to 0 for instant. Defaults to 400ms.
+    /// Highlight other references to the symbol under the cursor once idle. Defaults to true.
+    pub document_highlight: bool,
+    /// Time in milliseconds since last keypress before idle timers trigger. Used for autocompletion and document highlights, set to 0 for instant. Defaults to 400ms.
     #[serde(skip_serializing, deserialize_with = "deserialize_duration_millis")]
     pub idle_timeout: Duration,
+    /// Time in milliseconds to wait for the next key of a pending key sequence before discarding it, set to 0 to wait indefinitely. Defaults to 0.
+    #[serde(skip_serializing, deserialize_with = "deserialize_duration_millis")]
+    pub sequence_timeout: Duration,
+    /// Maximum number of redraws per second caused by background events, like bursts of language server messages or job spinners. Set to 0 for no limit. Defaults to 60.
+    pub max_fps: u16,
     pub completion_trigger_len: u8,
     /// Whether to display infoboxes. Defaults to true.
     pub auto_info: bool,
     pub file_picker: FilePickerConfig,
+    pub cursor_shape: CursorShapeConfig,
     /// Set to `true` to override automatic detection of terminal truecolor support in the event of a false negative. Defaults to `false`.
     pub true_color: bool,
+    /// Which rejected operations briefly flash the statusline. Defaults to `error`.
+    pub visual_bell: VisualBell,
+    /// Layout to translate normal and select mode keys from, so that they are bound by their QWERTY position. Defaults to `logical`.
+    pub keyboard_layout: KeyboardLayout,
+    /// Show recently pressed keys and the commands they ran in the top right corner, useful for screen recordings. Defaults to false.
+    pub show_keys: bool,
+    /// Gradually dim the time since the last save in the statusline over half an hour of unsaved changes. Defaults to false.
+    pub dim_unsaved_time: bool,
+    /// Show the position in the jumplist in the statusline. Defaults to false.
+    pub jumplist_indicator: bool,
+    /// Resolve symlinks when opening files, so a file opened through several links is a single buffer. Defaults to true.
+    pub resolve_symlinks: bool,
+    /// Commands which run markdown code blocks with `:run-cell`, by the language of the block. The code is passed on stdin.
+    pub cell_runners: HashMap<String, String>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VisualBell {
+    /// Never flash the statusline
+    Off,
+
+    /// Flash on errors, like pressing a key that isn't bound to anything
+    Error,
+
+    /// Flash on errors and warnings, like searching without any matches
+    Warning,
+}
+
+impl VisualBell {
+    fn rings_for(self, severity: Severity) -> bool {
+        match self {
+            Self::Off => false,
+            Self::Error => severity >= Severity::Error,
+            Self::Warning => severity >= Severity::Warning,
+        }
+    }
+}
+
+impl std::str::FromStr for VisualBell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "error" => Ok(Self::Error),
+            "warning" => Ok(Self::Warning),
+            _ => anyhow::bail!("Visual bell can only be `off`, `error` or `warning`."),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -115,8 +230,12 @@ pub enum LineNumber {
     /// Show absolute line number
     Absolute,
 
-    /// Show relative line number to the primary cursor
+    /// Show relative line number to the primary cursor, 0 on its line. This
+    /// used to show the absolute number there, which is now `Hybrid`.
     Relative,
+
+    /// Show relative line numbers, except for the absolute number of the primary cursor's line
+    Hybrid,
 }
 
 impl std::str::FromStr for LineNumber {
@@ -126,7 +245,70 @@ impl std::str::FromStr for LineNumber {
         match s.to_lowercase().as_str() {
             "absolute" | "abs" => Ok(Self::Absolute),
             "relative" | "rel" => Ok(Self::Relative),
-            _ => anyhow::bail!("Line number can only be `absolute` or `relative`."),
+            "hybrid" => Ok(Self::Hybrid),
+            _ => anyhow::bail!("Line number can only be `absolute`, `relative` or `hybrid`."),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GutterPosition {
+    /// Gutters on the left of the text
+    Left,
+
+    /// Gutters on the right of the text, mirrored so line numbers stay next to it
+    Right,
+
+    /// Gutters on both sides of the text
+    Both,
+}
+
+impl GutterPosition {
+    pub fn left(self) -> bool {
+        matches!(self, Self::Left | Self::Both)
+    }
+
+    pub fn right(self) -> bool {
+        matches!(self, Self::Right | Self::Both)
+    }
+}
+
+impl std::str::FromStr for GutterPosition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "both" => Ok(Self::Both),
+            _ => anyhow::bail!("Gutter position can only be `left`, `right` or `both`."),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GutterType {
+    /// Show a dot on lines with diagnostics
+    Diagnostics,
+
+    /// Show line numbers, absolute or relative depending on `line-number`
+    LineNumbers,
+
+    /// An empty column, to separate other gutters
+    Spacer,
+}
+
+impl std::str::FromStr for GutterType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "diagnostics" => Ok(Self::Diagnostics),
+            "line-numbers" => Ok(Self::LineNumbers),
+            "spacer" => Ok(Self::Spacer),
+            _ => anyhow::bail!("Gutters can only be `diagnostics`, `line-numbers` or `spacer`."),
         }
     }
 }
@@ -143,19 +325,82 @@ impl Default for Config {
                 vec!["sh".to_owned(), "-c".to_owned()]
             },
             line_number: LineNumber::Absolute,
+            gutters: vec![GutterType::Diagnostics, GutterType::LineNumbers],
+            gutter_position: GutterPosition::Left,
+            soft_wrap: false,
+            render_whitespace: false,
+            bufferline: false,
+            cursorline: false,
+            rulers: Vec::new(),
+            indent_guides: false,
             middle_click_paste: true,
+            paste_reindent: false,
+            paste_escape_strings: false,
             smart_case: true,
+            search_normalization: Normalization::None,
+            persist_search_highlight: false,
             auto_pairs: true,
             auto_completion: true,
+            document_highlight: true,
             idle_timeout: Duration::from_millis(400),
+            sequence_timeout: Duration::ZERO,
+            max_fps: 60,
             completion_trigger_len: 2,
             auto_info: true,
             file_picker: FilePickerConfig::default(),
+            cursor_shape: CursorShapeConfig::default(),
             true_color: false,
+            visual_bell: VisualBell::Error,
+            keyboard_layout: KeyboardLayout::Logical,
+            show_keys: false,
+            dim_unsaved_time: false,
+            jumplist_indicator: false,
+            resolve_symlinks: true,
+            cell_runners: [
+                ("sh", "sh"),
+                ("bash", "bash"),
+                ("python", "python3"),
+                ("js", "node"),
+                ("javascript", "node"),
+                ("ruby", "ruby"),
+            ]
+            .into_iter()
+            .map(|(language, runner)| (language.to_string(), runner.to_string()))
+            .collect(),
         }
     }
 }
 
+/// How long the statusline flashes when the visual bell rings.
+const VISUAL_BELL_DURATION: Duration = Duration::from_millis(150);
+
+// equivalent to internal Instant::far_future() (30 years)
+fn far_future() -> Instant {
+    Instant::now() + Duration::from_secs(86400 * 365 * 30)
+}
+
+/// Maximum number of status messages kept around for `:messages`.
+const STATUS_HISTORY_SIZE: usize = 100;
+
+/// A status or error message as it was shown in the statusline.
+#[derive(Debug, Clone)]
+pub struct StatusMessage {
+    pub message: String,
+    pub severity: Severity,
+    pub time: SystemTime,
+}
+
+/// Maximum number of closed documents kept around to be reopened.
+const CLOSED_DOCUMENTS_SIZE: usize = 30;
+
+/// A closed document, remembered to reopen it where it was left.
+#[derive(Debug, Clone)]
+pub struct ClosedDocument {
+    pub path: PathBuf,
+    pub selection: Selection,
+    pub offset: Position,
+}
+
 pub struct Motion(pub Box<dyn Fn(&mut Editor)>);
 impl Motion {
     pub fn run(&self, e: &mut Editor) {
@@ -185,10 +430,28 @@ pub struct Editor {
     pub theme_loader: Arc<theme::Loader>,
 
     pub status_msg: Option<(String, Severity)>,
+    /// Previously shown status messages, oldest first.
+    pub status_history: VecDeque<StatusMessage>,
+    /// Recently closed documents, oldest first.
+    pub closed_documents: VecDeque<ClosedDocument>,
+    /// Documents changed by the last `:global-replace`, with the history revision each was left
+    /// at, to undo them all at once.
+    pub replace_journal: Vec<(DocumentId, usize)>,
+    /// Background jobs shown in the statusline while they run.
+    pub tasks: Tasks,
+    /// When the files of the open documents were last checked for deletion.
+    pub deleted_files_checked: Instant,
 
     pub config: Config,
 
     pub idle_timer: Pin<Box<Sleep>>,
+    /// Whether the visual bell is currently flashing, see [`Editor::ring_bell`].
+    pub bell: bool,
+    pub bell_timer: Pin<Box<Sleep>>,
+    /// Fires when a pending key sequence has waited longer than `sequence_timeout`.
+    pub sequence_timer: Pin<Box<Sleep>>,
+    /// Modifiers latched by the `sticky_*` commands, applied to the next key.
+    pub sticky_modifiers: KeyModifiers,
     pub last_motion: Option<Motion>,
 
     pub exit_code: i32,
@@ -228,7 +491,16 @@ impl Editor {
             registers: Registers::default(),
             clipboard_provider: get_clipboard_provider(),
             status_msg: None,
+            status_history: VecDeque::with_capacity(STATUS_HISTORY_SIZE),
+            closed_documents: VecDeque::with_capacity(CLOSED_DOCUMENTS_SIZE),
+            replace_journal: Vec::new(),
+            tasks: Tasks::default(),
+            deleted_files_checked: Instant::now(),
             idle_timer: Box::pin(sleep(config.idle_timeout)),
+            bell: false,
+            bell_timer: Box::pin(sleep_until(far_future())),
+            sequence_timer: Box::pin(sleep_until(far_future())),
+            sticky_modifiers: KeyModifiers::NONE,
             last_motion: None,
             config,
             exit_code: 0,
@@ -236,10 +508,7 @@ impl Editor {
     }
 
     pub fn clear_idle_timer(&mut self) {
-        // equivalent to internal Instant::far_future() (30 years)
-        self.idle_timer
-            .as_mut()
-            .reset(Instant::now() + Duration::from_secs(86400 * 365 * 30));
+        self.idle_timer.as_mut().reset(far_future());
     }
 
     pub fn reset_idle_timer(&mut self) {
@@ -248,18 +517,75 @@ impl Editor {
             .reset(Instant::now() + self.config.idle_timeout);
     }
 
+    /// Start waiting for the next key of a pending key sequence. Does nothing
+    /// if `sequence_timeout` is 0.
+    pub fn reset_sequence_timer(&mut self) {
+        if self.config.sequence_timeout.is_zero() {
+            return;
+        }
+        self.sequence_timer
+            .as_mut()
+            .reset(Instant::now() + self.config.sequence_timeout);
+    }
+
+    pub fn clear_sequence_timer(&mut self) {
+        self.sequence_timer.as_mut().reset(far_future());
+    }
+
+    /// Flash the statusline if the visual bell is configured to ring for `severity`.
+    pub fn ring_bell(&mut self, severity: Severity) {
+        if !self.config.visual_bell.rings_for(severity) {
+            return;
+        }
+        self.bell = true;
+        self.bell_timer
+            .as_mut()
+            .reset(Instant::now() + VISUAL_BELL_DURATION);
+    }
+
+    pub fn clear_bell(&mut self) {
+        self.bell = false;
+        self.bell_timer.as_mut().reset(far_future());
+    }
+
     pub fn clear_status(&mut self) {
         self.status_msg = None;
     }
 
     pub fn set_status(&mut self, status: String) {
+        self.record_status(&status, Severity::Info);
         self.status_msg = Some((status, Severity::Info));
     }
 
     pub fn set_error(&mut self, error: String) {
+        self.record_status(&error, Severity::Error);
         self.status_msg = Some((error, Severity::Error));
     }
 
+    /// Shows `status` without keeping it for `:messages`, for statuses which
+    /// are replaced quickly like language server progress.
+    pub fn set_transient_status(&mut self, status: String) {
+        self.status_msg = Some((status, Severity::Info));
+    }
+
+    fn record_status(&mut self, message: &str, severity: Severity) {
+        // a repeated message only moves to the end
+        if let Some(last) = self.status_history.back_mut() {
+            if last.message == message && last.severity == severity {
+                last.time = SystemTime::now();
+                return;
+            }
+        }
+        if self.status_history.len() == STATUS_HISTORY_SIZE {
+            self.status_history.pop_front();
+        }
+        self.status_history.push_back(StatusMessage {
+            message: message.to_owned(),
+            severity,
+            time: SystemTime::now(),
+        });
+    }
+
     pub fn set_theme(&mut self, theme: Theme) {
         // `ui.selection` is the only scope required to be able to render a theme.
         if theme.find_scope_index("ui.selection").is_none() {
@@ -329,6 +655,9 @@ impl Editor {
 
     fn _refresh(&mut self) {
         for (view, _) in self.tree.views_mut() {
+            view.gutters = self.config.gutters.clone();
+            view.gutter_position = self.config.gutter_position;
+            view.soft_wrap = self.config.soft_wrap;
             let doc = &self.documents[&view.doc];
             view.ensure_cursor_in_view(doc, self.config.scrolloff)
         }
@@ -336,7 +665,7 @@ impl Editor {
 
     fn replace_document_in_view(&mut self, current_view: ViewId, doc_id: DocumentId) {
         let view = self.tree.get_mut(current_view);
-        view.doc = doc_id;
+        view.set_doc(doc_id);
         view.offset = Position::default();
 
         let doc = self.documents.get_mut(&doc_id).unwrap();
@@ -355,6 +684,23 @@ impl Editor {
     }
 
     pub fn switch(&mut self, id: DocumentId, action: Action) {
+        // pinned views keep their document, others open next to them. Only replacing looks at
+        // the current view, there is none yet when the first document is opened.
+        let action = match action {
+            Action::Replace if view!(self).pinned && view!(self).doc != id => Action::VerticalSplit,
+            action => action,
+        };
+        self.switch_to(id, action);
+    }
+
+    /// Shows `id` in the current view even if it's pinned, for going through
+    /// the documents the user picked for that view, like its buffer list or
+    /// the bufferline.
+    pub fn switch_in_view(&mut self, id: DocumentId) {
+        self.switch_to(id, Action::Replace);
+    }
+
+    fn switch_to(&mut self, id: DocumentId, action: Action) {
         use crate::tree::Layout;
 
         if !self.documents.contains_key(&id) {
@@ -384,6 +730,7 @@ impl Editor {
                     // Copy `doc.id` into a variable before calling `self.documents.remove`, which requires a mutable
                     // borrow, invalidating direct access to `doc.id`.
                     let id = doc.id;
+                    view.docs.retain(|&doc_id| doc_id != id);
                     self.documents.remove(&id);
                 } else {
                     let jump = (view.doc, doc.selection(view.id).clone());
@@ -459,7 +806,11 @@ impl Editor {
     }
 
     pub fn open(&mut self, path: PathBuf, action: Action) -> Result<DocumentId, Error> {
-        let path = helix_core::path::get_canonicalized_path(&path)?;
+        let path = if self.config.resolve_symlinks {
+            helix_core::path::get_resolved_path(&path)?
+        } else {
+            helix_core::path::get_canonicalized_path(&path)?
+        };
         let id = self.document_by_path(&path).map(|doc| doc.id);
 
         let id = if let Some(id) = id {
@@ -476,6 +827,27 @@ impl Editor {
         Ok(id)
     }
 
+    /// Opens a closed document again, restoring its selection and scroll position.
+    pub fn reopen(&mut self, closed: ClosedDocument, action: Action) -> Result<DocumentId, Error> {
+        let id = self.open(closed.path, action)?;
+        let scrolloff = self.config.scrolloff;
+        let (view, doc) = current!(self);
+        // the file may have changed on disk since it was closed
+        let text = doc.text().slice(..);
+        let len = text.len_chars();
+        let selection = closed
+            .selection
+            .transform(|range| Range::new(range.anchor.min(len), range.head.min(len)))
+            .ensure_invariants(text);
+        view.offset = Position::new(
+            closed.offset.row.min(text.len_lines().saturating_sub(1)),
+            closed.offset.col,
+        );
+        doc.set_selection(view.id, selection);
+        view.ensure_cursor_in_view(doc, scrolloff);
+        Ok(id)
+    }
+
     pub fn close(&mut self, id: ViewId) {
         let view = self.tree.get(self.tree.focus);
         // remove selection
@@ -508,6 +880,36 @@ impl Editor {
             tokio::spawn(language_server.text_document_did_close(doc.identifier()));
         }
 
+        if let Some(path) = doc.path() {
+            // prefer where the document was left in the focused view
+            let focused = self.tree.get(self.tree.focus);
+            let view = if focused.doc == doc_id {
+                Some(focused)
+            } else {
+                self.tree
+                    .views()
+                    .map(|(view, _focus)| view)
+                    .find(|view| view.doc == doc_id)
+            };
+            let selection = view
+                .and_then(|view| doc.selections().get(&view.id))
+                .or_else(|| doc.selections().values().next())
+                .cloned()
+                .unwrap_or_else(|| Selection::point(0));
+            let closed = ClosedDocument {
+                path: path.clone(),
+                selection,
+                offset: view.map(|view| view.offset).unwrap_or_default(),
+            };
+
+            self.closed_documents
+                .retain(|closed| Some(&closed.path) != doc.path());
+            if self.closed_documents.len() == CLOSED_DOCUMENTS_SIZE {
+                self.closed_documents.pop_front();
+            }
+            self.closed_documents.push_back(closed);
+        }
+
         let views_to_close = self
             .tree
             .views()
@@ -525,6 +927,9 @@ impl Editor {
         }
 
         self.documents.remove(&doc_id);
+        for (view, _focus) in self.tree.views_mut() {
+            view.docs.retain(|&id| id != doc_id);
+        }
 
         // If the document we removed was visible in all views, we will have no more views. We don't
         // want to close the editor just for a simple buffer close, so we need to create a new view
@@ -557,6 +962,10 @@ impl Editor {
         self.tree.focus_next();
     }
 
+    pub fn focus_prev(&mut self) {
+        self.tree.focus_prev();
+    }
+
     pub fn focus_right(&mut self) {
         self.tree.focus_direction(tree::Direction::Right);
     }
@@ -623,7 +1032,12 @@ impl Editor {
             let inner = view.inner_area();
             pos.col += inner.x as usize;
             pos.row += inner.y as usize;
-            (Some(pos), CursorKind::Hidden)
+            // block cursors are drawn by the editor
+            let kind = match self.config.cursor_shape.from_mode(doc.mode()) {
+                CursorKind::Block => CursorKind::Hidden,
+                kind => kind,
+            };
+            (Some(pos), kind)
         } else {
             (None, CursorKind::Hidden)
         }
@@ -1,10 +1,12 @@
 use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::{max, min},
     str::FromStr,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 /// UNSTABLE
 pub enum CursorKind {
     /// █
@@ -199,6 +201,11 @@ impl Rect {
             && self.y < other.y + other.height
             && self.y + self.height > other.y
     }
+
+    /// Whether the cell at `x`, `y` is inside the rect.
+    pub fn contains(self, x: u16, y: u16) -> bool {
+        (self.left()..self.right()).contains(&x) && (self.top()..self.bottom()).contains(&y)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -569,6 +576,16 @@ mod tests {
         assert_eq!(Rect::new(0, 0, 20, 20), rect.clip_bottom(10));
     }
 
+    #[test]
+    fn test_rect_contains() {
+        let rect = Rect::new(10, 5, 20, 30);
+        assert!(rect.contains(10, 5));
+        assert!(rect.contains(29, 34));
+        assert!(!rect.contains(30, 34));
+        assert!(!rect.contains(29, 35));
+        assert!(!rect.contains(9, 5));
+    }
+
     fn styles() -> Vec<Style> {
         vec![
             Style::default(),
@@ -1,11 +1,38 @@
 use std::fmt::Write;
 
-use crate::{editor::Config, graphics::Style, Document, Theme, View};
+use crate::{
+    editor::{Config, GutterType},
+    graphics::Style,
+    Document, Theme, View,
+};
 
 pub type GutterFn<'doc> = Box<dyn Fn(usize, bool, &mut String) -> Option<Style> + 'doc>;
 pub type Gutter =
     for<'doc> fn(&'doc Document, &View, &Theme, &Config, bool, usize) -> GutterFn<'doc>;
 
+/// Line numbers are at least this wide, so the text doesn't shift while editing small files.
+const MIN_LINE_NUMBER_WIDTH: usize = 5;
+
+/// The gutter function and width of each type of gutter.
+pub fn gutter(gutter_type: GutterType, line_number_width: usize) -> (Gutter, usize) {
+    match gutter_type {
+        GutterType::Diagnostics => (diagnostic, 1),
+        GutterType::LineNumbers => (line_number, line_number_width),
+        GutterType::Spacer => (spacer, 1),
+    }
+}
+
+/// Width of the line numbers gutter, wide enough for the number of the last line of `doc`.
+pub fn line_number_width(doc: &Document) -> usize {
+    let mut lines = doc.text().len_lines();
+    let mut digits = 1;
+    while lines >= 10 {
+        lines /= 10;
+        digits += 1;
+    }
+    digits.max(MIN_LINE_NUMBER_WIDTH)
+}
+
 pub fn diagnostic<'doc>(
     doc: &'doc Document,
     _view: &View,
@@ -36,6 +63,17 @@ pub fn diagnostic<'doc>(
     })
 }
 
+pub fn spacer<'doc>(
+    _doc: &'doc Document,
+    _view: &View,
+    _theme: &Theme,
+    _config: &Config,
+    _is_focused: bool,
+    _width: usize,
+) -> GutterFn<'doc> {
+    Box::new(|_line: usize, _selected: bool, _out: &mut String| None)
+}
+
 pub fn line_number<'doc>(
     doc: &'doc Document,
     view: &View,
@@ -67,7 +105,8 @@ pub fn line_number<'doc>(
             use crate::editor::LineNumber;
             let line = match config {
                 LineNumber::Absolute => line + 1,
-                LineNumber::Relative => {
+                LineNumber::Relative => abs_diff(current_line, line),
+                LineNumber::Hybrid => {
                     if current_line == line {
                         line + 1
                     } else {
@@ -94,3 +133,47 @@ const fn abs_diff(a: usize, b: usize) -> usize {
         b - a
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{editor::LineNumber, graphics::Rect, theme::DEFAULT_THEME, DocumentId};
+    use helix_core::{Rope, Selection};
+
+    fn line_numbers(mode: LineNumber) -> Vec<String> {
+        let mut doc = Document::from(Rope::from("a\nb\nc\nd\n"), None);
+        let mut view = View::new(DocumentId::default());
+        view.area = Rect::new(0, 0, 20, 10);
+        // cursor on the third line
+        doc.set_selection(view.id, Selection::point(4));
+
+        let config = Config {
+            line_number: mode,
+            ..Config::default()
+        };
+        let gutter = line_number(&doc, &view, &DEFAULT_THEME, &config, true, 2);
+        (0..4)
+            .map(|line| {
+                let mut out = String::new();
+                gutter(line, line == 2, &mut out);
+                out
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_number_width() {
+        let doc = |lines: usize| Document::from(Rope::from("\n".repeat(lines - 1)), None);
+        assert_eq!(line_number_width(&doc(1)), MIN_LINE_NUMBER_WIDTH);
+        assert_eq!(line_number_width(&doc(99_999)), 5);
+        assert_eq!(line_number_width(&doc(100_000)), 6);
+        assert_eq!(line_number_width(&doc(1_234_567)), 7);
+    }
+
+    #[test]
+    fn line_number_modes() {
+        assert_eq!(line_numbers(LineNumber::Absolute), [" 1", " 2", " 3", " 4"]);
+        assert_eq!(line_numbers(LineNumber::Relative), [" 2", " 1", " 0", " 1"]);
+        assert_eq!(line_numbers(LineNumber::Hybrid), [" 2", " 1", " 3", " 1"]);
+    }
+}
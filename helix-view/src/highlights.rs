@@ -0,0 +1,214 @@
+//! Highlight spans computed from the editor state, independent of how they're painted.
+
+use std::ops::Range;
+
+use helix_core::{
+    graphemes::{next_grapheme_boundary, prev_grapheme_boundary},
+    RopeSlice, Selection,
+};
+
+use crate::{document::Mode, Theme};
+
+/// Theme scope indices used by [`selection_highlights`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionScopes {
+    pub cursor: usize,
+    pub selection: usize,
+    pub primary_cursor: usize,
+    pub primary_selection: usize,
+}
+
+impl SelectionScopes {
+    /// Looks up the scopes for `mode`, falling back to the more general scopes when the
+    /// theme doesn't define the specific ones.
+    pub fn from_theme(theme: &Theme, mode: Mode) -> Self {
+        let selection = theme
+            .find_scope_index("ui.selection")
+            .expect("could not find `ui.selection` scope in the theme!");
+        let base_cursor = theme.find_scope_index("ui.cursor").unwrap_or(selection);
+
+        let cursor = match mode {
+            Mode::Insert => theme.find_scope_index("ui.cursor.insert"),
+            Mode::Select => theme.find_scope_index("ui.cursor.select"),
+            Mode::Normal => Some(base_cursor),
+        }
+        .unwrap_or(base_cursor);
+
+        Self {
+            cursor,
+            selection,
+            primary_cursor: theme
+                .find_scope_index("ui.cursor.primary")
+                .unwrap_or(cursor),
+            primary_selection: theme
+                .find_scope_index("ui.selection.primary")
+                .unwrap_or(selection),
+        }
+    }
+}
+
+/// Spans highlighting every range of `selection`: the grapheme at the head of a range as the
+/// cursor and the rest as the selection. Without `draw_primary_cursor` (when the frontend
+/// draws its own cursor) the primary range is only highlighted if it spans several characters.
+///
+/// A cursor at the very end of the text covers the position past the last character.
+pub fn selection_highlights(
+    text: RopeSlice,
+    selection: &Selection,
+    scopes: SelectionScopes,
+    draw_primary_cursor: bool,
+) -> Vec<(usize, Range<usize>)> {
+    let primary_idx = selection.primary_index();
+
+    let mut spans: Vec<(usize, Range<usize>)> = Vec::new();
+    for (i, range) in selection.iter().enumerate() {
+        let (cursor_scope, selection_scope) = if i == primary_idx {
+            (scopes.primary_cursor, scopes.primary_selection)
+        } else {
+            (scopes.cursor, scopes.selection)
+        };
+
+        if i == primary_idx && !draw_primary_cursor {
+            // a single character under the frontend's cursor isn't a selection
+            if range.len() > 1 {
+                spans.push((selection_scope, range.from()..range.to()));
+            }
+            continue;
+        }
+
+        // Special-case: cursor at end of the rope.
+        if range.head == range.anchor && range.head == text.len_chars() {
+            spans.push((cursor_scope, range.head..range.head + 1));
+            continue;
+        }
+
+        let range = range.min_width_1(text);
+        if range.head > range.anchor {
+            // Standard case.
+            let cursor_start = prev_grapheme_boundary(text, range.head);
+            spans.push((selection_scope, range.anchor..cursor_start));
+            spans.push((cursor_scope, cursor_start..range.head));
+        } else {
+            // Reverse case.
+            let cursor_end = next_grapheme_boundary(text, range.head);
+            spans.push((cursor_scope, range.head..cursor_end));
+            spans.push((selection_scope, cursor_end..range.anchor));
+        }
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use helix_core::{graphemes::ensure_grapheme_boundary_next, Range as SelectionRange, Rope};
+
+    const SCOPES: SelectionScopes = SelectionScopes {
+        cursor: 0,
+        selection: 1,
+        primary_cursor: 2,
+        primary_selection: 3,
+    };
+
+    fn is_cursor(scope: usize) -> bool {
+        scope == SCOPES.cursor || scope == SCOPES.primary_cursor
+    }
+
+    /// Builds a selection on grapheme boundaries of `text` from arbitrary positions.
+    fn selection(text: RopeSlice, positions: &[(usize, usize)]) -> Selection {
+        let pos = |n: usize| ensure_grapheme_boundary_next(text, n % (text.len_chars() + 1));
+        let ranges = positions
+            .iter()
+            .map(|&(anchor, head)| SelectionRange::new(pos(anchor), pos(head)))
+            .collect();
+        Selection::new(ranges, positions.len().saturating_sub(1))
+    }
+
+    #[test]
+    fn test_selection_highlights() {
+        let text = Rope::from("héllo\r\nwörld");
+        let text = text.slice(..);
+
+        // forward range: the cursor is the last grapheme
+        let sel = Selection::single(0, 3);
+        assert_eq!(
+            selection_highlights(text, &sel, SCOPES, true),
+            vec![(3, 0..2), (2, 2..3)]
+        );
+
+        // reversed range: the cursor is the first grapheme
+        let sel = Selection::single(3, 0);
+        assert_eq!(
+            selection_highlights(text, &sel, SCOPES, true),
+            vec![(2, 0..1), (3, 1..3)]
+        );
+
+        // the line ending is a single grapheme
+        let sel = Selection::single(5, 7);
+        assert_eq!(
+            selection_highlights(text, &sel, SCOPES, true),
+            vec![(3, 5..5), (2, 5..7)]
+        );
+
+        // cursor at the end of the text
+        let sel = Selection::point(text.len_chars());
+        assert_eq!(
+            selection_highlights(text, &sel, SCOPES, true),
+            vec![(2, 12..13)]
+        );
+
+        // the frontend draws the primary cursor
+        let sel = Selection::new(
+            vec![
+                SelectionRange::new(0, 1),
+                SelectionRange::new(7, 8),
+                SelectionRange::new(9, 12),
+            ]
+            .into(),
+            1,
+        );
+        assert_eq!(
+            selection_highlights(text, &sel, SCOPES, false),
+            vec![(1, 0..0), (0, 0..1), (1, 9..11), (0, 11..12)]
+        );
+    }
+
+    quickcheck::quickcheck! {
+        fn test_selection_highlights_cover_ranges(
+            text: String,
+            positions: Vec<(usize, usize)>
+        ) -> bool {
+            if positions.is_empty() {
+                return true;
+            }
+            let text = Rope::from(text);
+            let text = text.slice(..);
+            let sel = selection(text, &positions);
+            let spans = selection_highlights(text, &sel, SCOPES, true);
+
+            // sorted and not overlapping, as `syntax::merge` expects
+            let sorted = spans.windows(2).all(|w| w[0].1.end <= w[1].1.start);
+            let ordered = spans.iter().all(|(_, span)| span.start <= span.end);
+
+            // one cursor per range, covering exactly one grapheme at its head
+            let cursors: Vec<_> = spans
+                .iter()
+                .filter(|(scope, _)| is_cursor(*scope))
+                .map(|(_, span)| span.clone())
+                .collect();
+            let one_grapheme = cursors.iter().all(|span| {
+                if span.start == text.len_chars() {
+                    span.end == span.start + 1
+                } else {
+                    next_grapheme_boundary(text, span.start) == span.end
+                }
+            });
+            let at_heads = sel.iter().zip(&cursors).all(|(range, span)| {
+                range.head == span.start || range.head == span.end
+            });
+
+            sorted && ordered && cursors.len() == sel.len() && one_grapheme && at_heads
+        }
+    }
+}
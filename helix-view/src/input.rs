@@ -24,6 +24,60 @@ impl KeyEvent {
     }
 }
 
+/// The layout keys are typed on, used to bind normal and select mode keys by
+/// their position on the keyboard rather than by the character they produce.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyboardLayout {
+    /// Bind keys by the character they produce
+    Logical,
+
+    /// Bind keys by their QWERTY position on a Dvorak keyboard
+    Dvorak,
+
+    /// Bind keys by their QWERTY position on a Colemak keyboard
+    Colemak,
+}
+
+// Each layout lists the characters found at the positions of `QWERTY`.
+const QWERTY: &str = "`1234567890-=qwertyuiop[]\\asdfghjkl;'zxcvbnm,./\
+    ~!@#$%^&*()_+QWERTYUIOP{}|ASDFGHJKL:\"ZXCVBNM<>?";
+const DVORAK: &str = "`1234567890[]',.pyfgcrl/=\\aoeuidhtns-;qjkxbmwvz\
+    ~!@#$%^&*(){}\"<>PYFGCRL?+|AOEUIDHTNS_:QJKXBMWVZ";
+const COLEMAK: &str = "`1234567890-=qwfpgjluy;[]\\arstdhneio'zxcvbkm,./\
+    ~!@#$%^&*()_+QWFPGJLUY:{}|ARSTDHNEIO\"ZXCVBKM<>?";
+
+impl KeyboardLayout {
+    /// Translate a typed key to the key found at the same position on a
+    /// QWERTY keyboard, which is what the default keymap is laid out for.
+    pub fn to_qwerty(self, mut key: KeyEvent) -> KeyEvent {
+        let layout = match self {
+            Self::Logical => return key,
+            Self::Dvorak => DVORAK,
+            Self::Colemak => COLEMAK,
+        };
+        if let KeyCode::Char(ch) = key.code {
+            if let Some(i) = layout.chars().position(|c| c == ch) {
+                key.code = KeyCode::Char(QWERTY.chars().nth(i).unwrap());
+            }
+        }
+        key
+    }
+}
+
+impl std::str::FromStr for KeyboardLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "logical" => Ok(Self::Logical),
+            "dvorak" => Ok(Self::Dvorak),
+            "colemak" => Ok(Self::Colemak),
+            _ => anyhow::bail!("Keyboard layout can only be `logical`, `dvorak` or `colemak`."),
+        }
+    }
+}
+
 pub(crate) mod keys {
     pub(crate) const BACKSPACE: &str = "backspace";
     pub(crate) const ENTER: &str = "ret";
@@ -365,6 +419,43 @@ mod test {
         );
     }
 
+    #[test]
+    fn keys_ambiguous_in_terminals_are_distinct() {
+        // Legacy terminal encodings can't tell these apart, but frontends
+        // that can should be able to bind them separately.
+        for (a, b) in [("C-i", "tab"), ("C-m", "ret"), ("S-space", "space")] {
+            let a = str::parse::<KeyEvent>(a).unwrap();
+            let b = str::parse::<KeyEvent>(b).unwrap();
+            assert_ne!(a, b);
+            assert_eq!(a.to_string().parse::<KeyEvent>().unwrap(), a);
+        }
+    }
+
+    #[test]
+    fn keyboard_layouts_cover_qwerty() {
+        for layout in [DVORAK, COLEMAK] {
+            assert_eq!(layout.chars().count(), QWERTY.chars().count());
+        }
+
+        let dvorak = KeyboardLayout::Dvorak;
+        assert_eq!(dvorak.to_qwerty(key('d')), key('h'));
+        assert_eq!(dvorak.to_qwerty(key('O')), key('S'));
+        assert_eq!(
+            dvorak.to_qwerty(str::parse("C-,").unwrap()),
+            str::parse("C-w").unwrap()
+        );
+        assert_eq!(dvorak.to_qwerty(key('5')), key('5'));
+        assert_eq!(KeyboardLayout::Colemak.to_qwerty(key('n')), key('j'));
+        assert_eq!(KeyboardLayout::Logical.to_qwerty(key('n')), key('n'));
+    }
+
+    fn key(ch: char) -> KeyEvent {
+        KeyEvent {
+            code: KeyCode::Char(ch),
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
     #[test]
     fn parsing_nonsensical_keys_fails() {
         assert!(str::parse::<KeyEvent>("F13").is_err());
@@ -6,9 +6,11 @@ pub mod document;
 pub mod editor;
 pub mod graphics;
 pub mod gutter;
+pub mod highlights;
 pub mod info;
 pub mod input;
 pub mod keyboard;
+pub mod tasks;
 pub mod theme;
 pub mod tree;
 pub mod view;
@@ -0,0 +1,122 @@
+//! Long-running background jobs which are shown to the user while they run
+//! and can be cancelled.
+
+use futures_util::future::AbortHandle;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub id: usize,
+    pub name: String,
+    pub started: Instant,
+    abort: AbortHandle,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    next_id: usize,
+    tasks: Vec<Task>,
+}
+
+/// The running tasks. Clones share the same list.
+#[derive(Debug, Clone, Default)]
+pub struct Tasks {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Tasks {
+    /// Adds a task which is aborted through `abort` when cancelled. It is
+    /// removed from the list when the returned guard is dropped.
+    pub fn start(&self, name: String, abort: AbortHandle) -> TaskGuard {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.tasks.push(Task {
+            id,
+            name,
+            started: Instant::now(),
+            abort,
+        });
+        TaskGuard {
+            id,
+            tasks: self.clone(),
+        }
+    }
+
+    /// The running tasks, oldest first.
+    pub fn list(&self) -> Vec<Task> {
+        self.inner.lock().unwrap().tasks.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cancels the task with `id`. Returns false if it already finished.
+    pub fn cancel(&self, id: usize) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.tasks.iter().position(|task| task.id == id) {
+            Some(i) => {
+                inner.tasks.remove(i).abort.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels every running task and returns how many there were.
+    pub fn cancel_all(&self) -> usize {
+        let tasks = std::mem::take(&mut self.inner.lock().unwrap().tasks);
+        for task in &tasks {
+            task.abort.abort();
+        }
+        tasks.len()
+    }
+}
+
+/// Removes its task from the list of running tasks when dropped.
+#[derive(Debug)]
+pub struct TaskGuard {
+    id: usize,
+    tasks: Tasks,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let mut inner = self.tasks.inner.lock().unwrap();
+        inner.tasks.retain(|task| task.id != self.id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures_util::future::{Abortable, FutureExt};
+
+    #[test]
+    fn test_tasks() {
+        let tasks = Tasks::default();
+        let (abort, _) = AbortHandle::new_pair();
+        let first = tasks.start("format".into(), abort);
+        let (abort, registration) = AbortHandle::new_pair();
+        let _second = tasks.start("search".into(), abort);
+        let mut future = Abortable::new(std::future::pending::<()>(), registration);
+        assert_eq!(tasks.len(), 2);
+
+        drop(first);
+        let names: Vec<_> = tasks.list().into_iter().map(|task| task.name).collect();
+        assert_eq!(names, ["search"]);
+        assert_eq!((&mut future).now_or_never(), None);
+
+        let id = tasks.list()[0].id;
+        assert!(tasks.cancel(id));
+        assert!(matches!(future.now_or_never(), Some(Err(_))));
+        assert!(!tasks.cancel(id));
+        assert!(tasks.is_empty());
+    }
+}
@@ -117,6 +117,8 @@ impl<'de> Deserialize<'de> for Theme {
                     })
                 })
                 .unwrap_or_default();
+            // settings for graphical frontends, like corner radii, which don't apply to terminals
+            colors.remove("gui");
 
             styles.reserve(colors.len());
             scopes.reserve(colors.len());
@@ -350,4 +352,19 @@ mod tests {
                 .add_modifier(Modifier::BOLD)
         );
     }
+
+    #[test]
+    fn test_gui_section_ignored() {
+        let theme: Theme = toml::from_str(
+            r##"
+            "ui.cursor" = { bg = "#ffffff" }
+
+            [gui]
+            cursor-corner-radius = 2
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(theme.scopes(), ["ui.cursor"]);
+    }
 }
@@ -66,6 +66,8 @@ pub enum Direction {
 pub struct Container {
     layout: Layout,
     children: Vec<ViewId>,
+    /// Share of the container each child gets, equal unless a separator was dragged.
+    weights: Vec<u32>,
     area: Rect,
 }
 
@@ -74,11 +76,41 @@ impl Container {
         Self {
             layout,
             children: Vec::new(),
+            weights: Vec::new(),
             area: Rect::default(),
         }
     }
+
+    /// Splits `total` cells between the children according to their weights, the last child
+    /// takes what's left from rounding.
+    fn sizes(&mut self, total: u16) -> Vec<u16> {
+        let len = self.children.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        if self.weights.len() != len {
+            // children were added or removed, share the space equally again
+            self.weights = vec![1; len];
+        }
+        let sum: u32 = self.weights.iter().sum();
+
+        let mut remaining = total;
+        let mut sizes: Vec<u16> = self.weights[..len - 1]
+            .iter()
+            .map(|&weight| {
+                let size = (total as u32 * weight / sum) as u16;
+                remaining -= size;
+                size
+            })
+            .collect();
+        sizes.push(remaining);
+        sizes
+    }
 }
 
+/// Views can't be dragged smaller than this many rows or columns.
+const MIN_SPLIT_SIZE: u16 = 2;
+
 impl Default for Container {
     fn default() -> Self {
         Self::new(Layout::Vertical)
@@ -341,14 +373,12 @@ impl Tree {
 
                     match container.layout {
                         Layout::Horizontal => {
-                            let len = container.children.len();
-
-                            let height = area.height / len as u16;
+                            let heights = container.sizes(area.height);
 
                             let mut child_y = area.y;
 
-                            for (i, child) in container.children.iter().enumerate() {
-                                let mut area = Rect::new(
+                            for (child, height) in container.children.iter().zip(heights) {
+                                let area = Rect::new(
                                     container.area.x,
                                     child_y,
                                     container.area.width,
@@ -356,27 +386,21 @@ impl Tree {
                                 );
                                 child_y += height;
 
-                                // last child takes the remaining width because we can get uneven
-                                // space from rounding
-                                if i == len - 1 {
-                                    area.height = container.area.y + container.area.height - area.y;
-                                }
-
                                 self.stack.push((*child, area));
                             }
                         }
                         Layout::Vertical => {
-                            let len = container.children.len();
-
-                            let width = area.width / len as u16;
+                            let len = container.children.len() as u16;
 
                             let inner_gap = 1u16;
-                            // let total_gap = inner_gap * (len as u16 - 1);
+                            let total_gap = inner_gap * len.saturating_sub(1);
+
+                            let widths = container.sizes(area.width.saturating_sub(total_gap));
 
                             let mut child_x = area.x;
 
-                            for (i, child) in container.children.iter().enumerate() {
-                                let mut area = Rect::new(
+                            for (child, width) in container.children.iter().zip(widths) {
+                                let area = Rect::new(
                                     child_x,
                                     container.area.y,
                                     width,
@@ -384,12 +408,6 @@ impl Tree {
                                 );
                                 child_x += width + inner_gap;
 
-                                // last child takes the remaining width because we can get uneven
-                                // space from rounding
-                                if i == len - 1 {
-                                    area.width = container.area.x + container.area.width - area.x;
-                                }
-
                                 self.stack.push((*child, area));
                             }
                         }
@@ -399,6 +417,87 @@ impl Tree {
         }
     }
 
+    fn node_area(&self, id: ViewId) -> Rect {
+        match &self.nodes[id].content {
+            Content::View(view) => view.area,
+            Content::Container(container) => container.area,
+        }
+    }
+
+    /// Finds the separator between two splits at the given screen coordinates: the container
+    /// and the index of the child before the separator. Side by side splits are separated by
+    /// the column between them, stacked splits by the statusline of the upper one.
+    pub fn separator_at(&self, row: u16, column: u16) -> Option<(ViewId, usize)> {
+        self.nodes.iter().find_map(|(id, node)| {
+            let container = match &node.content {
+                Content::Container(container) => container,
+                Content::View(_) => return None,
+            };
+            let area = container.area;
+            let len = container.children.len();
+            let index = container.children[..len.saturating_sub(1)]
+                .iter()
+                .position(|&child| {
+                    let child = self.node_area(child);
+                    match container.layout {
+                        Layout::Vertical => {
+                            column == child.right() && (area.top()..area.bottom()).contains(&row)
+                        }
+                        Layout::Horizontal => {
+                            row + 1 == child.bottom()
+                                && (area.left()..area.right()).contains(&column)
+                        }
+                    }
+                })?;
+            Some((id, index))
+        })
+    }
+
+    /// Drags the separator after child `index` of `container` to the given screen coordinates,
+    /// resizing the splits on either side of it.
+    pub fn move_separator(&mut self, container_id: ViewId, index: usize, row: u16, column: u16) {
+        let container = match &self.nodes[container_id].content {
+            Content::Container(container) => container,
+            Content::View(_) => return,
+        };
+        if index + 1 >= container.children.len() {
+            return;
+        }
+
+        // the current sizes become the weights, so that the other splits keep their size
+        let (starts, mut sizes): (Vec<u16>, Vec<u32>) = container
+            .children
+            .iter()
+            .map(|&child| {
+                let area = self.node_area(child);
+                match container.layout {
+                    Layout::Vertical => (area.x, area.width as u32),
+                    Layout::Horizontal => (area.y, area.height as u32),
+                }
+            })
+            .unzip();
+
+        let start = starts[index];
+        let size = match container.layout {
+            // the separator column is right after the split before it
+            Layout::Vertical => column.saturating_sub(start),
+            // the separator is the statusline, the last row of the split before it
+            Layout::Horizontal => (row + 1).saturating_sub(start),
+        } as u32;
+        let total = sizes[index] + sizes[index + 1];
+        let min = MIN_SPLIT_SIZE as u32;
+        if total < min * 2 {
+            return;
+        }
+        sizes[index] = size.clamp(min, total - min);
+        sizes[index + 1] = total - sizes[index];
+
+        if let Content::Container(container) = &mut self.nodes[container_id].content {
+            container.weights = sizes;
+        }
+        self.recalculate();
+    }
+
     pub fn traverse(&self) -> Traverse {
         Traverse::new(self)
     }
@@ -549,6 +648,13 @@ impl Tree {
         }
     }
 
+    pub fn focus_prev(&mut self) {
+        let views: Vec<_> = self.traverse().map(|(id, _view)| id).collect();
+        let idx = views.iter().position(|&id| id == self.focus).unwrap_or(0);
+        // wrap around to the last view
+        self.focus = views[idx.checked_sub(1).unwrap_or(views.len() - 1)];
+    }
+
     pub fn area(&self) -> Rect {
         self.area
     }
@@ -647,4 +753,67 @@ mod test {
         assert_eq!(None, tree.find_split_in_direction(r0, Direction::Right));
         assert_eq!(None, tree.find_split_in_direction(r0, Direction::Up));
     }
+
+    #[test]
+    fn drag_separators() {
+        let mut tree = Tree::new(Rect::new(0, 0, 101, 40));
+        let left = tree.insert(View::new(DocumentId::default()));
+        let right = tree.split(View::new(DocumentId::default()), Layout::Vertical);
+        let bottom = tree.split(View::new(DocumentId::default()), Layout::Horizontal);
+
+        // | L | R |
+        // |   | B |
+        assert_eq!(tree.get(left).area, Rect::new(0, 0, 50, 40));
+        assert_eq!(tree.get(right).area, Rect::new(51, 0, 50, 20));
+        assert_eq!(tree.get(bottom).area, Rect::new(51, 20, 50, 20));
+
+        let vertical = tree.separator_at(10, 50).unwrap();
+        assert_eq!(vertical.1, 0);
+        let horizontal = tree.separator_at(19, 60).unwrap();
+        assert_eq!(horizontal.1, 0);
+        assert_eq!(tree.separator_at(10, 49), None);
+        assert_eq!(tree.separator_at(39, 60), None);
+
+        tree.move_separator(vertical.0, vertical.1, 10, 30);
+        assert_eq!(tree.get(left).area, Rect::new(0, 0, 30, 40));
+        assert_eq!(tree.get(right).area, Rect::new(31, 0, 70, 20));
+        assert_eq!(tree.get(bottom).area, Rect::new(31, 20, 70, 20));
+
+        tree.move_separator(horizontal.0, horizontal.1, 9, 60);
+        assert_eq!(tree.get(right).area, Rect::new(31, 0, 70, 10));
+        assert_eq!(tree.get(bottom).area, Rect::new(31, 10, 70, 30));
+
+        // splits keep their share of the space on resize
+        tree.resize(Rect::new(0, 0, 201, 80));
+        assert_eq!(tree.get(left).area, Rect::new(0, 0, 60, 80));
+        assert_eq!(tree.get(right).area, Rect::new(61, 0, 140, 20));
+
+        // but can't be dragged out of existence
+        tree.move_separator(vertical.0, vertical.1, 10, 0);
+        assert_eq!(tree.get(left).area.width, MIN_SPLIT_SIZE);
+    }
+
+    #[test]
+    fn focus_next_and_prev() {
+        let mut tree = Tree::new(Rect::new(0, 0, 180, 80));
+        let v0 = tree.insert(View::new(DocumentId::default()));
+        let v1 = tree.split(View::new(DocumentId::default()), Layout::Vertical);
+        tree.focus = v0;
+        let v2 = tree.split(View::new(DocumentId::default()), Layout::Horizontal);
+
+        // | V0 |    |
+        // | V2 | V1 |
+        assert_eq!(tree.focus, v2);
+        tree.focus_next();
+        assert_eq!(tree.focus, v1);
+        tree.focus_next();
+        assert_eq!(tree.focus, v0);
+
+        tree.focus_prev();
+        assert_eq!(tree.focus, v1);
+        tree.focus_prev();
+        assert_eq!(tree.focus, v2);
+        tree.focus_prev();
+        assert_eq!(tree.focus, v0);
+    }
 }
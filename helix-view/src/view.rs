@@ -1,14 +1,18 @@
 use std::borrow::Cow;
 
 use crate::{
+    editor::{GutterPosition, GutterType},
     graphics::Rect,
     gutter::{self, Gutter},
     Document, DocumentId, ViewId,
 };
 use helix_core::{
+    bidi,
     graphemes::{grapheme_width, RopeGraphemes},
     line_ending::line_end_char_index,
-    visual_coords_at_pos, Position, RopeSlice, Selection,
+    regex::Regex,
+    search::Normalization,
+    visual_coords_at_pos, Position, Range, RopeSlice, Selection,
 };
 
 type Jump = (DocumentId, Selection);
@@ -62,9 +66,17 @@ impl JumpList {
     pub fn remove(&mut self, doc_id: &DocumentId) {
         self.jumps.retain(|(other_id, _)| other_id != doc_id);
     }
-}
 
-const GUTTERS: &[(Gutter, usize)] = &[(gutter::diagnostic, 1), (gutter::line_number, 5)];
+    /// The 1-based position in the jumplist, and its length. The current location
+    /// counts as the last entry until jumping back.
+    pub fn position(&self) -> (usize, usize) {
+        if self.current >= self.jumps.len() {
+            (self.jumps.len() + 1, self.jumps.len() + 1)
+        } else {
+            (self.current + 1, self.jumps.len())
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct View {
@@ -82,6 +94,24 @@ pub struct View {
     pub last_modified_docs: [Option<DocumentId>; 2],
     /// used to store previous selections of tree-sitter objecs
     pub object_selections: Vec<Selection>,
+    /// references to the symbol under the primary cursor, as reported by the language server
+    pub document_highlights: Vec<Range>,
+    /// pattern of a regex prompt being typed or of the last search when it's kept,
+    /// whose matches are highlighted, and how the text is normalized before matching it
+    pub search_highlight: Option<(Regex, Normalization)>,
+    /// documents shown in this view, in the order they were first shown
+    pub docs: Vec<DocumentId>,
+    /// whether the document is pinned to this view. Other documents are
+    /// opened in a new split instead of replacing it.
+    pub pinned: bool,
+    /// gutters rendered next to the text, kept in sync with the config
+    pub gutters: Vec<GutterType>,
+    /// which side of the text the gutters are rendered on, kept in sync with the config
+    pub gutter_position: GutterPosition,
+    /// whether long lines wrap at the width of the view, kept in sync with the config
+    pub soft_wrap: bool,
+    /// width of the line numbers gutter, kept in sync with the length of the document
+    pub line_number_width: usize,
 }
 
 impl View {
@@ -95,22 +125,52 @@ impl View {
             last_accessed_doc: None,
             last_modified_docs: [None, None],
             object_selections: Vec::new(),
+            document_highlights: Vec::new(),
+            search_highlight: None,
+            docs: vec![doc],
+            pinned: false,
+            gutters: vec![GutterType::Diagnostics, GutterType::LineNumbers],
+            gutter_position: GutterPosition::Left,
+            soft_wrap: false,
+            line_number_width: 5,
         }
     }
 
-    pub fn gutters(&self) -> &[(Gutter, usize)] {
-        GUTTERS
+    /// Shows `doc` in this view, adding it to the view's documents.
+    pub fn set_doc(&mut self, doc: DocumentId) {
+        self.doc = doc;
+        if !self.docs.contains(&doc) {
+            self.docs.push(doc);
+        }
     }
 
-    pub fn inner_area(&self) -> Rect {
-        // TODO: cache this
-        let offset = self
-            .gutters()
+    pub fn gutters(&self) -> Vec<(Gutter, usize)> {
+        self.gutters
+            .iter()
+            .map(|&gutter_type| gutter::gutter(gutter_type, self.line_number_width))
+            .collect()
+    }
+
+    /// Width of the gutters on one side of the text, including the space between them and the text.
+    pub fn gutter_width(&self) -> u16 {
+        self.gutters()
             .iter()
             .map(|(_, width)| *width as u16)
             .sum::<u16>()
-            + 1; // +1 for some space between gutters and line
-        self.area.clip_left(offset).clip_bottom(1) // -1 for statusline
+            + 1 // +1 for some space between gutters and line
+    }
+
+    pub fn inner_area(&self) -> Rect {
+        // TODO: cache this
+        let width = self.gutter_width();
+        let mut area = self.area.clip_bottom(1); // -1 for statusline
+        if self.gutter_position.left() {
+            area = area.clip_left(width);
+        }
+        if self.gutter_position.right() {
+            area = area.clip_right(width);
+        }
+        area
     }
 
     //
@@ -135,6 +195,15 @@ impl View {
         // as we type
         let scrolloff = scrolloff.min(inner_area.height.saturating_sub(1) as usize / 2);
 
+        if self.soft_wrap {
+            let row = self.wrapped_offset_row(doc, line, col, scrolloff);
+            return if row == self.offset.row && self.offset.col == 0 {
+                None
+            } else {
+                Some((row, 0))
+            };
+        }
+
         let last_col = self.offset.col + inner_area.width.saturating_sub(1) as usize;
 
         let row = if line > last_line.saturating_sub(scrolloff) {
@@ -163,7 +232,44 @@ impl View {
         }
     }
 
+    /// The first line to show so that the cursor at `line` and `col` is on screen with soft-wrap,
+    /// with `scrolloff` rows around it. Scrolls by whole lines.
+    fn wrapped_offset_row(
+        &self,
+        doc: &Document,
+        line: usize,
+        col: usize,
+        scrolloff: usize,
+    ) -> usize {
+        if line < self.offset.row + scrolloff {
+            // scroll up
+            return line.saturating_sub(scrolloff);
+        }
+
+        let text = doc.text().slice(..);
+        let tab_width = doc.tab_width();
+        let height = self.inner_area().height as usize;
+        // rows from the top of the view to below the cursor's row and the rows after it.
+        // Every line takes up at least one row, so skip the lines that can't fit.
+        let mut top = self
+            .offset
+            .row
+            .max((line + scrolloff + 1).saturating_sub(height));
+        let mut rows = (top..line)
+            .map(|line| self.line_rows(text, line, tab_width))
+            .sum::<usize>()
+            + col / self.wrap_width()
+            + 1
+            + scrolloff;
+        while rows > height && top < line {
+            rows -= self.line_rows(text, top, tab_width);
+            top += 1;
+        }
+        top
+    }
+
     pub fn ensure_cursor_in_view(&mut self, doc: &Document, scrolloff: usize) {
+        self.line_number_width = gutter::line_number_width(doc);
         if let Some((row, col)) = self.offset_coords_to_in_view(doc, scrolloff) {
             self.offset.row = row;
             self.offset.col = col;
@@ -174,10 +280,69 @@ impl View {
         self.offset_coords_to_in_view(doc, scrolloff).is_none()
     }
 
+    /// Width long lines are wrapped at with soft-wrap.
+    pub fn wrap_width(&self) -> usize {
+        (self.inner_area().width as usize).max(1)
+    }
+
+    /// Number of screen rows `line` takes up, which is more than one for long lines with soft-wrap.
+    pub fn line_rows(&self, text: RopeSlice, line: usize, tab_width: usize) -> usize {
+        if !self.soft_wrap {
+            return 1;
+        }
+        let line_start = text.line_to_char(line);
+        let line_end = line_end_char_index(&text, line);
+        // the end of the line, where the cursor can be, starts a new row if it's right at the wrap
+        visual_width(text.slice(line_start..line_end), tab_width) / self.wrap_width() + 1
+    }
+
+    /// The line shown on each row of the view from the top, and whether it's the first row
+    /// of that line. Lines take up several rows with soft-wrap.
+    pub fn rows(&self, doc: &Document) -> Vec<(usize, bool)> {
+        let text = doc.text().slice(..);
+        let height = self.inner_area().height as usize;
+        let mut rows = Vec::with_capacity(height);
+        for line in self.offset.row..text.len_lines() {
+            if rows.len() >= height {
+                break;
+            }
+            let line_rows = self.line_rows(text, line, doc.tab_width());
+            rows.extend((0..line_rows).map(|row| (line, row == 0)));
+        }
+        rows.truncate(height);
+        rows
+    }
+
+    /// The line shown at `row` rows from the top of the view, and the row within that line.
+    /// Returns None if the row is past the end of the text.
+    fn line_at_row(&self, text: RopeSlice, row: usize, tab_width: usize) -> Option<(usize, usize)> {
+        if !self.soft_wrap {
+            let line = self.offset.row + row;
+            return if line < text.len_lines() {
+                Some((line, 0))
+            } else {
+                None
+            };
+        }
+        let mut start = 0;
+        for line in self.offset.row..text.len_lines() {
+            let rows = self.line_rows(text, line, tab_width);
+            if row < start + rows {
+                return Some((line, row - start));
+            }
+            start += rows;
+        }
+        None
+    }
+
     /// Calculates the last visible line on screen
     #[inline]
     pub fn last_line(&self, doc: &Document) -> usize {
         let height = self.inner_area().height;
+        if self.soft_wrap {
+            let last = self.rows(doc).last().map(|(line, _)| *line);
+            return last.unwrap_or(self.offset.row);
+        }
         std::cmp::min(
             // Saturating subs to make it inclusive zero indexing.
             (self.offset.row + height as usize).saturating_sub(1),
@@ -202,17 +367,24 @@ impl View {
         }
 
         let line_start = text.line_to_char(line);
-        let line_slice = text.slice(line_start..pos);
-        let mut col = 0;
         let tab_width = doc.tab_width();
-
-        for grapheme in RopeGraphemes::new(line_slice) {
-            if grapheme == "\t" {
-                col += tab_width;
-            } else {
-                let grapheme = Cow::from(grapheme);
-                col += grapheme_width(&grapheme);
+        let mut col = visual_width(text.slice(line_start..pos), tab_width);
+
+        if self.soft_wrap {
+            let width = self.wrap_width();
+            let row = (self.offset.row..line)
+                .map(|line| self.line_rows(text, line, tab_width))
+                .sum::<usize>()
+                + col / width;
+            if row >= self.inner_area().height as usize {
+                return None;
             }
+            return Some(Position::new(row, col % width));
+        }
+
+        // right-to-left text is displayed reordered
+        if let Some(columns) = bidi::visual_columns(text.line(line), tab_width) {
+            col = bidi::visual_col(&columns, col);
         }
 
         // It is possible for underflow to occur if the buffer length is larger than the terminal width.
@@ -239,17 +411,22 @@ impl View {
             return None;
         }
 
-        let line_number = (row - inner.y) as usize + self.offset.row;
-
-        if line_number > text.len_lines() - 1 {
-            return Some(text.len_chars());
-        }
+        let (line_number, line_row) =
+            match self.line_at_row(*text, (row - inner.y) as usize, tab_width) {
+                Some(line) => line,
+                None => return Some(text.len_chars()),
+            };
 
         let mut pos = text.line_to_char(line_number);
 
         let current_line = text.line(line_number);
 
-        let target = (column - inner.x) as usize + self.offset.col;
+        let mut target = (column - inner.x) as usize + self.offset.col;
+        if self.soft_wrap {
+            target = line_row * self.wrap_width() + (column - inner.x) as usize;
+        } else if let Some(columns) = bidi::visual_columns(current_line, tab_width) {
+            target = bidi::logical_col(&columns, target);
+        }
         let mut selected = 0;
 
         for grapheme in RopeGraphemes::new(current_line) {
@@ -268,6 +445,31 @@ impl View {
         Some(pos.min(line_end_char_index(&text.slice(..), line_number)))
     }
 
+    /// Translates a screen position inside the gutter to the line it's rendered next to.
+    /// Returns None if the position isn't inside this view's gutter or is past the end of the text.
+    pub fn gutter_line_at_screen_coords(
+        &self,
+        text: &RopeSlice,
+        row: u16,
+        column: u16,
+        tab_width: usize,
+    ) -> Option<usize> {
+        let inner = self.inner_area();
+        if row < inner.top() || row >= inner.bottom() {
+            return None;
+        }
+
+        let in_left = column >= self.area.left() && column < inner.left();
+        // the column right after the text is padding, which is treated as part of the text
+        let in_right = column > inner.right() && column < self.area.right();
+        if !in_left && !in_right {
+            return None;
+        }
+
+        self.line_at_row(*text, (row - inner.y) as usize, tab_width)
+            .map(|(line, _)| line)
+    }
+
     /// Translates a screen position to position in the text document.
     /// Returns a usize typed position in bounds of the text if found in this view, None if out of view.
     pub fn pos_at_screen_coords(&self, doc: &Document, row: u16, column: u16) -> Option<usize> {
@@ -295,6 +497,19 @@ impl View {
     // }
 }
 
+/// Width of `text` on screen.
+fn visual_width(text: RopeSlice, tab_width: usize) -> usize {
+    RopeGraphemes::new(text)
+        .map(|grapheme| {
+            if grapheme == "\t" {
+                tab_width
+            } else {
+                grapheme_width(&Cow::from(grapheme))
+            }
+        })
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,6 +563,158 @@ mod tests {
         assert_eq!(view.text_pos_at_screen_coords(&text, 41, 80, 4), Some(8));
     }
 
+    #[test]
+    fn test_gutter_line_at_screen_coords() {
+        let mut view = View::new(DocumentId::default());
+        view.area = Rect::new(40, 40, 40, 40);
+        let rope = Rope::from_str("abc\n\tdef");
+        let text = rope.slice(..);
+
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 40, 39, 4), None);
+
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 40, 40, 4), Some(0));
+
+        assert_eq!(
+            view.gutter_line_at_screen_coords(&text, 41, 40 + OFFSET - 1, 4),
+            Some(1)
+        );
+
+        assert_eq!(
+            view.gutter_line_at_screen_coords(&text, 41, 40 + OFFSET, 4),
+            None
+        );
+
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 42, 40, 4), None);
+
+        view.offset.row = 1;
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 40, 40, 4), Some(1));
+    }
+
+    #[test]
+    fn test_jumplist_position() {
+        let doc = DocumentId::default();
+        let mut jumps = JumpList::new((doc, Selection::point(0)));
+        assert_eq!(jumps.position(), (1, 1));
+
+        jumps.push((doc, Selection::point(1)));
+        jumps.push((doc, Selection::point(2)));
+        assert_eq!(jumps.position(), (3, 3));
+
+        jumps.current = 0;
+        assert_eq!(jumps.position(), (1, 2));
+        assert!(jumps.forward(1).is_some());
+        assert_eq!(jumps.position(), (2, 2));
+    }
+
+    #[test]
+    fn test_gutter_position_right() {
+        let mut view = View::new(DocumentId::default());
+        view.area = Rect::new(40, 40, 40, 40);
+        view.gutter_position = GutterPosition::Right;
+        let rope = Rope::from_str("abc\n\tdef");
+        let text = rope.slice(..);
+
+        assert_eq!(view.inner_area(), Rect::new(40, 40, 40 - OFFSET, 39));
+
+        assert_eq!(view.text_pos_at_screen_coords(&text, 40, 40, 4), Some(0));
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 40, 40, 4), None);
+
+        assert_eq!(
+            view.gutter_line_at_screen_coords(&text, 41, 80 - OFFSET, 4),
+            None
+        );
+        assert_eq!(
+            view.gutter_line_at_screen_coords(&text, 41, 80 - OFFSET + 1, 4),
+            Some(1)
+        );
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 41, 79, 4), Some(1));
+
+        view.gutter_position = GutterPosition::Both;
+        assert_eq!(
+            view.inner_area(),
+            Rect::new(40 + OFFSET, 40, 40 - 2 * OFFSET, 39)
+        );
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 40, 40, 4), Some(0));
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 40, 79, 4), Some(0));
+    }
+
+    #[test]
+    fn test_configured_gutters() {
+        let mut view = View::new(DocumentId::default());
+        view.area = Rect::new(40, 40, 40, 40);
+
+        // 5 linenr + 1 gutter
+        view.gutters = vec![GutterType::LineNumbers];
+        assert_eq!(view.inner_area(), Rect::new(46, 40, 34, 39));
+
+        // 1 diagnostic + 1 spacer + 5 linenr + 1 gutter
+        view.gutters = vec![
+            GutterType::Diagnostics,
+            GutterType::Spacer,
+            GutterType::LineNumbers,
+        ];
+        assert_eq!(view.inner_area(), Rect::new(48, 40, 32, 39));
+
+        view.gutters = Vec::new();
+        assert_eq!(view.inner_area(), Rect::new(41, 40, 39, 39));
+    }
+
+    #[test]
+    fn test_soft_wrap() {
+        let mut view = View::new(DocumentId::default());
+        // 10 columns for text
+        view.area = Rect::new(40, 40, 10 + OFFSET, 40);
+        view.soft_wrap = true;
+        // the first line takes up three rows
+        let rope = Rope::from_str("0123456789abcdefghijklm\nxyz");
+        let text = rope.slice(..);
+
+        assert_eq!(view.line_rows(text, 0, 4), 3);
+        assert_eq!(view.line_rows(text, 1, 4), 1);
+
+        let x = 40 + OFFSET;
+        assert_eq!(view.text_pos_at_screen_coords(&text, 40, x + 3, 4), Some(3));
+        assert_eq!(
+            view.text_pos_at_screen_coords(&text, 41, x + 3, 4),
+            Some(13)
+        );
+        // past the end of the last row of the line
+        assert_eq!(
+            view.text_pos_at_screen_coords(&text, 42, x + 9, 4),
+            Some(23)
+        );
+        assert_eq!(
+            view.text_pos_at_screen_coords(&text, 43, x + 1, 4),
+            Some(25)
+        );
+
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 42, 40, 4), Some(0));
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 43, 40, 4), Some(1));
+        assert_eq!(view.gutter_line_at_screen_coords(&text, 44, 40, 4), None);
+    }
+
+    #[test]
+    fn test_text_pos_at_screen_coords_rtl() {
+        let mut view = View::new(DocumentId::default());
+        view.area = Rect::new(40, 40, 40, 40);
+        // displayed as "ab גבא"
+        let rope = Rope::from_str("ab אבג");
+        let text = rope.slice(..);
+
+        assert_eq!(
+            view.text_pos_at_screen_coords(&text, 40, 40 + OFFSET + 1, 4),
+            Some(1)
+        );
+        assert_eq!(
+            view.text_pos_at_screen_coords(&text, 40, 40 + OFFSET + 3, 4),
+            Some(5)
+        );
+        assert_eq!(
+            view.text_pos_at_screen_coords(&text, 40, 40 + OFFSET + 5, 4),
+            Some(3)
+        );
+    }
+
     #[test]
     fn test_text_pos_at_screen_coords_cjk() {
         let mut view = View::new(DocumentId::default());